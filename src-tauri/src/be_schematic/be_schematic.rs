@@ -1 +1,330 @@
+use crate::be_schematic::le_nbt::from_bytes_le;
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::extend_value::NbtExt;
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
+use crate::utils::tile_entities::{strip_keys, TileEntitiesList};
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::Arc;
 
+#[derive(Debug)]
+pub struct BeSchematic {
+    nbt: Value,
+}
+
+impl BeSchematic {
+    pub fn new(file_path: &str) -> Result<Self, SchematicError> {
+        let data = fs::read(file_path)?;
+        Self::new_from_bytes(data)
+    }
+
+    pub fn new_from_bytes(data: Vec<u8>) -> Result<Self, SchematicError> {
+        let nbt = from_bytes_le(&data)?;
+
+        if let Compound(_) = &nbt {
+            Ok(Self { nbt })
+        } else {
+            Err(SchematicError::InvalidFormat("Root is not a Compound"))
+        }
+    }
+
+    pub fn get_root(&self) -> Result<&HashMap<String, Value>, SchematicError> {
+        let Compound(root) = &self.nbt else {
+            return Err(SchematicError::InvalidFormat("Root is not a Compound"));
+        };
+        Ok(root)
+    }
+
+    pub fn get_structure(&self) -> Result<&HashMap<String, Value>, SchematicError> {
+        self.get_root()?.get_compound("structure")
+    }
+
+    pub fn get_format_version(&self) -> Result<i32, SchematicError> {
+        self.get_root()?.get_i32("format_version")
+    }
+
+    pub fn get_size(&self) -> Result<Size, SchematicError> {
+        let sizes = Self::int_list(self.get_root()?.get_list("size")?)?;
+        if sizes.iter().any(|&n| n <= 0) {
+            return Err(SchematicError::InvalidFormat("Size must be positive"));
+        }
+        Ok(Size {
+            width: sizes[0],
+            height: sizes[1],
+            length: sizes[2],
+        })
+    }
+
+    pub fn get_world_origin(&self) -> Result<BlockPos, SchematicError> {
+        let origin = Self::int_list(self.get_root()?.get_list("structure_world_origin")?)?;
+        Ok(BlockPos {
+            x: origin[0],
+            y: origin[1],
+            z: origin[2],
+        })
+    }
+
+    pub fn get_default_palette(&self) -> Result<&HashMap<String, Value>, SchematicError> {
+        self.get_structure()?
+            .get_compound("palette")?
+            .get_compound("default")
+    }
+
+    // 方块版本号按字节存储: 0x01140A00 -> 1.20.10
+    pub fn get_game_version(&self) -> Result<String, SchematicError> {
+        let block_palette = self.get_default_palette()?.get_list("block_palette")?;
+        let version = block_palette
+            .iter()
+            .filter_map(|entry| match entry {
+                Compound(c) => c.get_i32("version").ok(),
+                _ => None,
+            })
+            .max()
+            .ok_or(SchematicError::MissingField("version".to_string()))?;
+        Ok(format!(
+            "BE {}.{}.{}",
+            (version >> 24) & 0xFF,
+            (version >> 16) & 0xFF,
+            (version >> 8) & 0xFF
+        ))
+    }
+
+    fn int_list(list: &[Value]) -> Result<Vec<i32>, SchematicError> {
+        let values = list
+            .iter()
+            .filter_map(|v| match v {
+                Value::Int(n) => Some(*n),
+                _ => None,
+            })
+            .collect::<Vec<i32>>();
+        if values.len() != 3 {
+            return Err(SchematicError::InvalidFormat(
+                "Position requires 3 coordinates",
+            ));
+        }
+        Ok(values)
+    }
+
+    pub fn parse_state_value(value: &Value) -> Option<Arc<str>> {
+        match value {
+            Value::String(s) => Some(Arc::from(s.as_str())),
+            Value::Byte(b) => Some(Arc::from(if *b != 0 { "true" } else { "false" })),
+            Value::Short(n) => Some(Arc::from(n.to_string())),
+            Value::Int(n) => Some(Arc::from(n.to_string())),
+            _ => None,
+        }
+    }
+
+    pub fn parse_palette(&self) -> Result<Vec<Arc<BlockData>>, SchematicError> {
+        let palette_list = self.get_default_palette()?.get_list("block_palette")?;
+        let mut palette = Vec::with_capacity(palette_list.len());
+        for entry in palette_list {
+            let Compound(root) = entry else {
+                return Err(SchematicError::InvalidFormat("Root is not a Compound"));
+            };
+            let name = root
+                .get("name")
+                .and_then(Value::as_str)
+                .map(|s| Arc::<str>::from(s))
+                .unwrap_or_else(|| Arc::from("minecraft:air"));
+            let mut properties = BTreeMap::new();
+            if let Some(Compound(states)) = root.get("states") {
+                for (k, v) in states {
+                    if let Some(state) = Self::parse_state_value(v) {
+                        properties.insert(Arc::<str>::from(k.as_str()), state);
+                    }
+                }
+            }
+
+            palette.push(Arc::new(BlockData {
+                id: BlockId { name },
+                properties,
+            }));
+        }
+
+        Ok(palette)
+    }
+
+    pub fn get_index(x: i32, y: i32, z: i32, size: &Size) -> usize {
+        ((x * size.height + y) * size.length + z) as usize
+    }
+
+    pub fn get_layers(&self) -> Result<(Vec<i32>, Vec<i32>), SchematicError> {
+        let indices = self.get_structure()?.get_list("block_indices")?;
+        let mut layers = indices.iter().map(|layer| match layer {
+            Value::List(list) => list
+                .iter()
+                .map(|v| match v {
+                    Value::Int(n) => Ok(*n),
+                    _ => Err(SchematicError::InvalidFormat("block_indices must be Int")),
+                })
+                .collect::<Result<Vec<i32>, SchematicError>>(),
+            Value::IntArray(arr) => Ok(arr.to_vec()),
+            _ => Err(SchematicError::InvalidFormat("block_indices is not a list")),
+        });
+        let primary = layers
+            .next()
+            .ok_or(SchematicError::MissingField("block_indices".to_string()))??;
+        let secondary = layers.next().transpose()?.unwrap_or_default();
+        Ok((primary, secondary))
+    }
+
+    pub fn get_tile_entities(&self, size: &Size) -> Result<TileEntitiesList, SchematicError> {
+        let layer_size = size
+            .height
+            .checked_mul(size.length)
+            .filter(|&n| n > 0)
+            .ok_or(SchematicError::InvalidFormat("Size must be positive"))?;
+        let mut tile_entities = TileEntitiesList::default();
+        let Ok(position_data) = self.get_default_palette()?.get_compound("block_position_data")
+        else {
+            return Ok(tile_entities);
+        };
+        for (index_str, data) in position_data {
+            let Ok(index) = index_str.parse::<i32>() else {
+                continue;
+            };
+            if index < 0 || index / layer_size >= size.width {
+                continue;
+            }
+            let Compound(data) = data else {
+                continue;
+            };
+            let Some(entity) = data.get("block_entity_data") else {
+                continue;
+            };
+            let pos = BlockPos {
+                x: index / layer_size,
+                y: (index % layer_size) / size.length,
+                z: index % size.length,
+            };
+            tile_entities.add(pos, strip_keys(entity, &["x", "y", "z"]));
+        }
+        Ok(tile_entities)
+    }
+
+    // 基岩版方块按映射表转为 Java 版, 无法映射的保留原名并按名称计数
+    pub fn get_blocks_pos(
+        &self,
+        be_blocks: &BeBlocksData,
+    ) -> Result<(SchematicData, BTreeMap<String, i64>), SchematicError> {
+        let mut block_list = BlockVolume::default();
+        let size = self.get_size()?;
+        let be_palette = self.parse_palette()?;
+        let palette = be_palette
+            .iter()
+            .map(|block| {
+                be_blocks
+                    .to_java(&block.id.name, &block.properties)
+                    .map(|(name, properties)| {
+                        Arc::new(BlockData {
+                            id: BlockId {
+                                name: Arc::from(name),
+                            },
+                            properties: properties
+                                .into_iter()
+                                .map(|(k, v)| (Arc::from(k), Arc::from(v)))
+                                .collect(),
+                        })
+                    })
+            })
+            .collect::<Vec<Option<Arc<BlockData>>>>();
+        let mut unmapped: BTreeMap<String, i64> = BTreeMap::new();
+        let (primary, secondary) = self.get_layers()?;
+        let tile_entities = self.get_tile_entities(&size)?;
+        let total = size
+            .width
+            .checked_mul(size.height)
+            .and_then(|n| n.checked_mul(size.length))
+            .ok_or(SchematicError::InvalidFormat("Size is too large"))?;
+        let total = total as usize;
+        if primary.len() < total {
+            return Err(SchematicError::InvalidFormat("block_indices too short"));
+        }
+        let mut waterlogged_cache: HashMap<usize, Arc<BlockData>> = HashMap::new();
+
+        for x in 0..size.width {
+            for y in 0..size.height {
+                for z in 0..size.length {
+                    let index = Self::get_index(x, y, z, &size);
+                    // -1 为结构空位, 不放置任何方块
+                    let Ok(state_id) = usize::try_from(primary[index]) else {
+                        continue;
+                    };
+                    let be_block = be_palette
+                        .get(state_id)
+                        .ok_or(SchematicError::InvalidFormat("State index out of bounds"))?;
+                    let Some(block_data) = &palette[state_id] else {
+                        *unmapped.entry(be_block.id.name.to_string()).or_insert(0) += 1;
+                        block_list.add_by_pos(x, y, z, be_block.clone());
+                        continue;
+                    };
+                    let is_waterlogged = secondary
+                        .get(index)
+                        .and_then(|id| usize::try_from(*id).ok())
+                        .and_then(|id| be_palette.get(id))
+                        .map(|b| {
+                            matches!(
+                                b.id.name.as_ref(),
+                                "minecraft:water" | "minecraft:flowing_water"
+                            )
+                        })
+                        .unwrap_or(false);
+                    if is_waterlogged {
+                        let block = waterlogged_cache.entry(state_id).or_insert_with(|| {
+                            let mut data = (**block_data).clone();
+                            data.properties
+                                .insert(Arc::from("waterlogged"), Arc::from("true"));
+                            Arc::new(data)
+                        });
+                        block_list.add_by_pos(x, y, z, block.clone());
+                    } else {
+                        block_list.add_by_pos(x, y, z, block_data.clone());
+                    }
+                }
+            }
+        }
+
+        Ok((
+            SchematicData::new(block_list, tile_entities, size),
+            unmapped,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_size(size: [i32; 3]) -> BeSchematic {
+        let mut root = HashMap::new();
+        root.insert(
+            "size".to_string(),
+            Value::List(size.iter().map(|&n| Value::Int(n)).collect()),
+        );
+        BeSchematic {
+            nbt: Compound(root),
+        }
+    }
+
+    #[test]
+    fn rejects_non_positive_size() {
+        assert!(with_size([2, 0, 3]).get_size().is_err());
+        assert!(with_size([-1, 2, 3]).get_size().is_err());
+        assert_eq!(with_size([2, 1, 3]).get_size().unwrap().length, 3);
+    }
+
+    #[test]
+    fn rejects_empty_layer_for_tile_entities() {
+        let size = Size {
+            width: 1,
+            height: 0,
+            length: 1,
+        };
+        assert!(with_size([1, 1, 1]).get_tile_entities(&size).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::utils::schematic_data::SchematicError;
+use fastnbt::{ByteArray, IntArray, LongArray, Value};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+const TAG_END: u8 = 0;
+const TAG_BYTE: u8 = 1;
+const TAG_SHORT: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_LONG: u8 = 4;
+const TAG_FLOAT: u8 = 5;
+const TAG_DOUBLE: u8 = 6;
+const TAG_BYTE_ARRAY: u8 = 7;
+const TAG_STRING: u8 = 8;
+const TAG_LIST: u8 = 9;
+const TAG_COMPOUND: u8 = 10;
+const TAG_INT_ARRAY: u8 = 11;
+const TAG_LONG_ARRAY: u8 = 12;
+
+// fastnbt 只支持大端序, 基岩版 mcstructure 是小端序且不压缩, 这里单独实现读写
+pub fn from_bytes_le(data: &[u8]) -> Result<Value, SchematicError> {
+    let mut reader = Cursor::new(data);
+    let tag = read_u8(&mut reader)?;
+    if tag != TAG_COMPOUND {
+        return Err(SchematicError::RootNotCompound);
+    }
+    let _name = read_string(&mut reader)?;
+    read_payload(&mut reader, tag)
+}
+
+pub fn to_bytes_le(value: &Value) -> Result<Vec<u8>, SchematicError> {
+    let Value::Compound(_) = value else {
+        return Err(SchematicError::RootNotCompound);
+    };
+    let mut buffer = Vec::new();
+    buffer.push(TAG_COMPOUND);
+    write_string(&mut buffer, "");
+    write_payload(&mut buffer, value)?;
+    Ok(buffer)
+}
+
+fn read_u8(reader: &mut Cursor<&[u8]>) -> Result<u8, SchematicError> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_array<const N: usize>(reader: &mut Cursor<&[u8]>) -> Result<[u8; N], SchematicError> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_len(reader: &mut Cursor<&[u8]>) -> Result<usize, SchematicError> {
+    let len = i32::from_le_bytes(read_array::<4>(reader)?);
+    if len < 0 {
+        return Err(SchematicError::InvalidFormat("Negative NBT length"));
+    }
+    Ok(len as usize)
+}
+
+fn read_string(reader: &mut Cursor<&[u8]>) -> Result<String, SchematicError> {
+    let len = u16::from_le_bytes(read_array::<2>(reader)?) as usize;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf)?;
+    Ok(String::from_utf8(buf)?)
+}
+
+fn read_payload(reader: &mut Cursor<&[u8]>, tag: u8) -> Result<Value, SchematicError> {
+    let value = match tag {
+        TAG_BYTE => Value::Byte(read_u8(reader)? as i8),
+        TAG_SHORT => Value::Short(i16::from_le_bytes(read_array::<2>(reader)?)),
+        TAG_INT => Value::Int(i32::from_le_bytes(read_array::<4>(reader)?)),
+        TAG_LONG => Value::Long(i64::from_le_bytes(read_array::<8>(reader)?)),
+        TAG_FLOAT => Value::Float(f32::from_le_bytes(read_array::<4>(reader)?)),
+        TAG_DOUBLE => Value::Double(f64::from_le_bytes(read_array::<8>(reader)?)),
+        TAG_BYTE_ARRAY => {
+            let len = read_len(reader)?;
+            let mut buf = vec![0u8; len];
+            reader.read_exact(&mut buf)?;
+            Value::ByteArray(ByteArray::new(buf.into_iter().map(|b| b as i8).collect()))
+        }
+        TAG_STRING => Value::String(read_string(reader)?),
+        TAG_LIST => {
+            let element_tag = read_u8(reader)?;
+            let len = read_len(reader)?;
+            let mut list = Vec::with_capacity(len.min(1 << 16));
+            for _ in 0..len {
+                list.push(read_payload(reader, element_tag)?);
+            }
+            Value::List(list)
+        }
+        TAG_COMPOUND => {
+            let mut compound = HashMap::new();
+            loop {
+                let child_tag = read_u8(reader)?;
+                if child_tag == TAG_END {
+                    break;
+                }
+                let name = read_string(reader)?;
+                compound.insert(name, read_payload(reader, child_tag)?);
+            }
+            Value::Compound(compound)
+        }
+        TAG_INT_ARRAY => {
+            let len = read_len(reader)?;
+            let mut list = Vec::with_capacity(len.min(1 << 16));
+            for _ in 0..len {
+                list.push(i32::from_le_bytes(read_array::<4>(reader)?));
+            }
+            Value::IntArray(IntArray::new(list))
+        }
+        TAG_LONG_ARRAY => {
+            let len = read_len(reader)?;
+            let mut list = Vec::with_capacity(len.min(1 << 16));
+            for _ in 0..len {
+                list.push(i64::from_le_bytes(read_array::<8>(reader)?));
+            }
+            Value::LongArray(LongArray::new(list))
+        }
+        _ => return Err(SchematicError::InvalidFormat("Unknown NBT tag")),
+    };
+    Ok(value)
+}
+
+fn tag_of(value: &Value) -> u8 {
+    match value {
+        Value::Byte(_) => TAG_BYTE,
+        Value::Short(_) => TAG_SHORT,
+        Value::Int(_) => TAG_INT,
+        Value::Long(_) => TAG_LONG,
+        Value::Float(_) => TAG_FLOAT,
+        Value::Double(_) => TAG_DOUBLE,
+        Value::ByteArray(_) => TAG_BYTE_ARRAY,
+        Value::String(_) => TAG_STRING,
+        Value::List(_) => TAG_LIST,
+        Value::Compound(_) => TAG_COMPOUND,
+        Value::IntArray(_) => TAG_INT_ARRAY,
+        Value::LongArray(_) => TAG_LONG_ARRAY,
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn write_payload(buffer: &mut Vec<u8>, value: &Value) -> Result<(), SchematicError> {
+    match value {
+        Value::Byte(v) => buffer.push(*v as u8),
+        Value::Short(v) => buffer.extend_from_slice(&v.to_le_bytes()),
+        Value::Int(v) => buffer.extend_from_slice(&v.to_le_bytes()),
+        Value::Long(v) => buffer.extend_from_slice(&v.to_le_bytes()),
+        Value::Float(v) => buffer.extend_from_slice(&v.to_le_bytes()),
+        Value::Double(v) => buffer.extend_from_slice(&v.to_le_bytes()),
+        Value::ByteArray(v) => {
+            buffer.extend_from_slice(&(v.len() as i32).to_le_bytes());
+            buffer.extend(v.iter().map(|b| *b as u8));
+        }
+        Value::String(v) => write_string(buffer, v),
+        Value::List(list) => {
+            let element_tag = list.first().map(tag_of).unwrap_or(TAG_END);
+            buffer.push(element_tag);
+            buffer.extend_from_slice(&(list.len() as i32).to_le_bytes());
+            for element in list {
+                if tag_of(element) != element_tag {
+                    return Err(SchematicError::InvalidFormat("Mixed NBT list types"));
+                }
+                write_payload(buffer, element)?;
+            }
+        }
+        Value::Compound(compound) => {
+            for (name, child) in compound {
+                buffer.push(tag_of(child));
+                write_string(buffer, name);
+                write_payload(buffer, child)?;
+            }
+            buffer.push(TAG_END);
+        }
+        Value::IntArray(v) => {
+            buffer.extend_from_slice(&(v.len() as i32).to_le_bytes());
+            for n in v.iter() {
+                buffer.extend_from_slice(&n.to_le_bytes());
+            }
+        }
+        Value::LongArray(v) => {
+            buffer.extend_from_slice(&(v.len() as i32).to_le_bytes());
+            for n in v.iter() {
+                buffer.extend_from_slice(&n.to_le_bytes());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut inner = HashMap::new();
+        inner.insert(
+            "name".to_string(),
+            Value::String("minecraft:stone".to_string()),
+        );
+        inner.insert("version".to_string(), Value::Int(18_090_528));
+        let mut root = HashMap::new();
+        root.insert("format_version".to_string(), Value::Int(1));
+        root.insert("byte".to_string(), Value::Byte(-3));
+        root.insert("short".to_string(), Value::Short(300));
+        root.insert("long".to_string(), Value::Long(-1 << 40));
+        root.insert("float".to_string(), Value::Float(1.5));
+        root.insert("double".to_string(), Value::Double(-0.25));
+        root.insert(
+            "bytes".to_string(),
+            Value::ByteArray(ByteArray::new(vec![1, -1])),
+        );
+        root.insert(
+            "ints".to_string(),
+            Value::IntArray(IntArray::new(vec![0, -1, 7])),
+        );
+        root.insert(
+            "longs".to_string(),
+            Value::LongArray(LongArray::new(vec![i64::MIN])),
+        );
+        root.insert("empty".to_string(), Value::List(Vec::new()));
+        root.insert(
+            "palette".to_string(),
+            Value::List(vec![Value::Compound(inner.clone()), Value::Compound(inner)]),
+        );
+        let root = Value::Compound(root);
+        let bytes = to_bytes_le(&root).unwrap();
+        assert_eq!(from_bytes_le(&bytes).unwrap(), root);
+    }
+
+    #[test]
+    fn little_endian_layout() {
+        let mut root = HashMap::new();
+        root.insert("a".to_string(), Value::Int(1));
+        let bytes = to_bytes_le(&Value::Compound(root)).unwrap();
+        assert_eq!(
+            bytes,
+            [TAG_COMPOUND, 0, 0, TAG_INT, 1, 0, b'a', 1, 0, 0, 0, TAG_END]
+        );
+    }
+
+    #[test]
+    fn rejects_non_compound_root() {
+        assert!(to_bytes_le(&Value::Int(1)).is_err());
+        assert!(from_bytes_le(&[TAG_INT, 0, 0, 1, 0, 0, 0]).is_err());
+        assert!(from_bytes_le(&[TAG_COMPOUND, 0]).is_err());
+    }
+}
@@ -1 +1,3 @@
 pub mod be_schematic;
+pub mod le_nbt;
+pub mod to_be_schematic;
@@ -0,0 +1,262 @@
+use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::minecraft_data::be_blocks_data::{BeBlockState, BeBlocksData, BeStateValue};
+use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::tile_entities::{with_keys, TileEntitiesList};
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use std::collections::{BTreeMap, HashMap};
+
+// 1.21.40: 版本号按字节存储 major.minor.patch.build
+const BLOCK_VERSION: i32 = (1 << 24) | (21 << 16) | (40 << 8) | 1;
+
+#[derive(Debug)]
+pub struct ToBeSchematic<'a> {
+    blocks: &'a BlockVolume,
+    start_pos: BlockPos,
+    width: i32,
+    height: i32,
+    length: i32,
+    air_index: i32,
+    water_index: i32,
+    palette: Vec<Value>,
+    // 按体积调色板下标映射到基岩版调色板下标
+    state_to_index: Vec<i32>,
+    waterlogged: Vec<bool>,
+    tile_entities: TileEntitiesList,
+    pub unmapped: BTreeMap<String, i64>,
+}
+
+impl<'a> ToBeSchematic<'a> {
+    pub fn new(
+        schematic: &'a SchematicData,
+        be_blocks: &BeBlocksData,
+    ) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks;
+        let Some((min, max)) = blocks.bounds() else {
+            return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
+        };
+
+        let mut palette = Vec::new();
+        let mut unmapped: BTreeMap<String, i64> = BTreeMap::new();
+
+        palette.push(Self::palette_entry(&BeBlockState {
+            name: "minecraft:air".to_string(),
+            states: BTreeMap::new(),
+        }));
+        let air_index = 0;
+        let mut water_states = BTreeMap::new();
+        water_states.insert("liquid_depth".to_string(), BeStateValue::Int(0));
+        palette.push(Self::palette_entry(&BeBlockState {
+            name: "minecraft:water".to_string(),
+            states: water_states,
+        }));
+        let water_index = 1;
+
+        let counts = blocks.count_states();
+        let mut state_to_index = Vec::with_capacity(blocks.palette().len());
+        let mut waterlogged = Vec::with_capacity(blocks.palette().len());
+        for (block, &count) in blocks.palette().iter().zip(&counts) {
+            let index = if block.id.name.as_ref() == "minecraft:air" {
+                air_index
+            } else {
+                match be_blocks.to_bedrock(&block.id.name, &block.properties) {
+                    Some(state) => {
+                        palette.push(Self::palette_entry(&state));
+                        (palette.len() - 1) as i32
+                    }
+                    None => {
+                        if count > 0 {
+                            *unmapped.entry(block.id.name.to_string()).or_insert(0) += count as i64;
+                        }
+                        air_index
+                    }
+                }
+            };
+            state_to_index.push(index);
+            waterlogged.push(
+                block
+                    .properties
+                    .get("waterlogged")
+                    .map(|v| v.as_ref() == "true")
+                    .unwrap_or(false),
+            );
+        }
+
+        // Java 版方块实体 (id 带命名空间) 无法写入, 与未映射的方块一起报告
+        for te in &schematic.tile_entities_list.elements {
+            if !Self::is_bedrock_entity(te.get_id()) {
+                let id = te.get_id().unwrap_or("unknown");
+                *unmapped.entry(format!("{} (方块实体)", id)).or_insert(0) += 1;
+            }
+        }
+        // 实体 nbt 同样与基岩版不兼容, 不写入结构, 只计入报告
+        for entity in &schematic.entities.elements {
+            let id = entity.get_id().unwrap_or("unknown");
+            *unmapped.entry(format!("{} (实体)", id)).or_insert(0) += 1;
+        }
+
+        Ok(Self {
+            blocks,
+            start_pos: min,
+            width: max.x - min.x + 1,
+            height: max.y - min.y + 1,
+            length: max.z - min.z + 1,
+            air_index,
+            water_index,
+            palette,
+            state_to_index,
+            waterlogged,
+            tile_entities: schematic.tile_entities_list.clone(),
+            unmapped,
+        })
+    }
+
+    fn is_bedrock_entity(id: Option<&str>) -> bool {
+        id.is_some_and(|id| !id.contains(':'))
+    }
+
+    fn palette_entry(state: &BeBlockState) -> Value {
+        let mut states = HashMap::new();
+        for (k, v) in &state.states {
+            let value = match v {
+                BeStateValue::String(s) => Value::String(s.clone()),
+                BeStateValue::Int(n) => Value::Int(*n),
+                BeStateValue::Bool(b) => Value::Byte(*b as i8),
+            };
+            states.insert(k.clone(), value);
+        }
+        let mut entry = HashMap::new();
+        entry.insert("name".to_string(), Value::String(state.name.clone()));
+        entry.insert("states".to_string(), Compound(states));
+        entry.insert("version".to_string(), Value::Int(BLOCK_VERSION));
+        Compound(entry)
+    }
+
+    // 基岩版索引顺序为 x -> y -> z, z 变化最快
+    fn get_index(&self, pos: &BlockPos) -> usize {
+        let dx = pos.x - self.start_pos.x;
+        let dy = pos.y - self.start_pos.y;
+        let dz = pos.z - self.start_pos.z;
+        ((dx * self.height + dy) * self.length + dz) as usize
+    }
+
+    pub fn get_layers(&self) -> (Vec<i32>, Vec<i32>) {
+        let total = (self.width * self.height * self.length) as usize;
+        let mut primary = vec![self.air_index; total];
+        let mut secondary = vec![-1; total];
+        for (pos, state) in self.blocks.iter_indices() {
+            let index = self.get_index(&pos);
+            if index >= total {
+                continue;
+            }
+            primary[index] = self.state_to_index[state as usize];
+            if self.waterlogged[state as usize] {
+                secondary[index] = self.water_index;
+            }
+        }
+        (primary, secondary)
+    }
+
+    // Java 版方块实体 nbt 结构与基岩版不同, 只写回基岩版来源 (id 不带命名空间) 的数据, 其余已计入 unmapped
+    pub fn block_position_data(&self) -> Value {
+        let mut position_data = HashMap::new();
+        for te in &self.tile_entities.elements {
+            if !Self::is_bedrock_entity(te.get_id()) {
+                continue;
+            }
+            let pos = te.relative_to(&self.start_pos);
+            let nbt = with_keys(
+                &te.nbt,
+                vec![
+                    ("x", Value::Int(pos.x)),
+                    ("y", Value::Int(pos.y)),
+                    ("z", Value::Int(pos.z)),
+                ],
+            );
+            let mut entry = HashMap::new();
+            entry.insert("block_entity_data".to_string(), nbt);
+            position_data.insert(self.get_index(&te.pos).to_string(), Compound(entry));
+        }
+        Compound(position_data)
+    }
+
+    pub fn be_schematic(&self) -> Result<Value, SchematicError> {
+        let (primary, secondary) = self.get_layers();
+        let to_list = |layer: Vec<i32>| Value::List(layer.into_iter().map(Value::Int).collect());
+
+        let mut default_palette = HashMap::new();
+        default_palette.insert(
+            "block_palette".to_string(),
+            Value::List(self.palette.clone()),
+        );
+        default_palette.insert(
+            "block_position_data".to_string(),
+            self.block_position_data(),
+        );
+        let mut palette = HashMap::new();
+        palette.insert("default".to_string(), Compound(default_palette));
+
+        let mut structure = HashMap::new();
+        structure.insert(
+            "block_indices".to_string(),
+            Value::List(vec![to_list(primary), to_list(secondary)]),
+        );
+        // 实体已在 new 中计入 unmapped
+        structure.insert("entities".to_string(), Value::List(Vec::new()));
+        structure.insert("palette".to_string(), Compound(palette));
+
+        let mut nbt = HashMap::new();
+        nbt.insert("format_version".to_string(), Value::Int(1));
+        nbt.insert(
+            "size".to_string(),
+            Value::List(vec![
+                Value::Int(self.width),
+                Value::Int(self.height),
+                Value::Int(self.length),
+            ]),
+        );
+        nbt.insert("structure".to_string(), Compound(structure));
+        nbt.insert(
+            "structure_world_origin".to_string(),
+            Value::List(vec![Value::Int(0), Value::Int(0), Value::Int(0)]),
+        );
+        Ok(Compound(nbt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::{BlockData, BlockId};
+    use crate::utils::entities::EntitiesList;
+    use crate::utils::schematic_data::Size;
+    use std::sync::Arc;
+
+    #[test]
+    fn reports_entities_that_are_not_written() {
+        let mut blocks = BlockVolume::new();
+        blocks.add(
+            BlockPos { x: 0, y: 0, z: 0 },
+            Arc::new(BlockData {
+                id: BlockId {
+                    name: Arc::from("minecraft:stone"),
+                },
+                properties: BTreeMap::new(),
+            }),
+        );
+        let mut entities = EntitiesList::new();
+        entities.add([0.5, 0.0, 0.5], Compound(HashMap::new()));
+        let size = Size {
+            width: 1,
+            height: 1,
+            length: 1,
+        };
+        let data =
+            SchematicData::new(blocks, TileEntitiesList::default(), size).with_entities(entities);
+        let be_blocks = BeBlocksData::new().unwrap();
+        let schematic = ToBeSchematic::new(&data, &be_blocks).unwrap();
+        assert_eq!(schematic.unmapped.get("unknown (实体)"), Some(&1));
+        assert!(!schematic.unmapped.contains_key("minecraft:stone"));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::building_gadges::bg_schematic_data::BgSchematicData;
 use crate::building_gadges::template_json_representation::{deserialize, int_to_rel_pos};
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePosList};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::extend_value::NbtExt;
 use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
 use crate::utils::tile_entities::TileEntitiesList;
@@ -276,8 +277,8 @@ impl BgSchematic {
         }
     }
     pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
-        let tile_entities = TileEntitiesList::default();
-        let mut block_list = BlockStatePosList::default();
+        let mut tile_entities = TileEntitiesList::default();
+        let mut block_list = BlockVolume::default();
         let original_data = self.decode_schematic()?;
         let type_version = original_data.type_version;
         let size = self.get_size()?;
@@ -313,8 +314,9 @@ impl BgSchematic {
                 let Compound(root) = data else {
                     return Err(SchematicError::InvalidFormat("Root is not a Compound"));
                 };
-                let block_list_test = deserialize(root)?;
+                let (block_list_test, tile_entities_test) = deserialize(root)?;
                 block_list.merge(block_list_test);
+                tile_entities.merge(tile_entities_test);
             }
             2 => {
                 let data = original_data.data;
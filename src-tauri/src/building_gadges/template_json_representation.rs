@@ -1,6 +1,8 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePosList};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::extend_value::NbtExt;
 use crate::utils::schematic_data::SchematicError;
+use crate::utils::tile_entities::TileEntitiesList;
 use fastnbt::Value;
 use fastnbt::Value::Compound;
 use std::collections::{BTreeMap, HashMap};
@@ -10,8 +12,11 @@ pub const B1_BYTE_MASK: i64 = 0xFF;
 pub const B2_BYTE_MASK: i64 = 0xFFFF;
 pub const B3_BYTE_MASK: i64 = 0xFFFFFF;
 
-pub fn deserialize(nbt: HashMap<String, Value>) -> Result<BlockStatePosList, SchematicError> {
-    let mut block_list = BlockStatePosList::default();
+pub fn deserialize(
+    nbt: HashMap<String, Value>,
+) -> Result<(BlockVolume, TileEntitiesList), SchematicError> {
+    let mut block_list = BlockVolume::default();
+    let mut tile_entities = TileEntitiesList::default();
     let pos_list = nbt.get_list("pos")?;
     let state_list = nbt.get_list("data")?;
 
@@ -31,6 +36,12 @@ pub fn deserialize(nbt: HashMap<String, Value>) -> Result<BlockStatePosList, Sch
             return Err(SchematicError::InvalidFormat("state is not a Compound"));
         };
 
+        // 方块实体数据挂在调色板条目的 data 上, 空 compound 表示没有
+        if let Some(Compound(data)) = state.get("data") {
+            if !data.is_empty() {
+                tile_entities.add(pos, Compound(data.clone()));
+            }
+        }
         let block_state = state.get_compound("state")?;
         let name = block_state
             .get("Name")
@@ -56,7 +67,7 @@ pub fn deserialize(nbt: HashMap<String, Value>) -> Result<BlockStatePosList, Sch
         );
     }
 
-    Ok(block_list)
+    Ok((block_list, tile_entities))
 }
 pub fn pos_from_long(serialized: i64) -> Result<BlockPos, SchematicError> {
     fn sign_extend16(value: i64) -> i32 {
@@ -1,104 +1,53 @@
 use crate::building_gadges::template_json_representation::{
-    rel_pos_to_int, B1_BYTE_MASK, B2_BYTE_MASK, B3_BYTE_MASK,
+    read_state_id, rel_pos_to_int, B1_BYTE_MASK, B2_BYTE_MASK, B3_BYTE_MASK,
 };
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos};
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::tile_entities::TileEntitiesList;
 use base64::Engine;
 use fastnbt::Value::Compound;
 use fastnbt::{to_bytes, Value};
 use fastsnbt::to_string;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use rayon::iter::IntoParallelRefIterator;
-use rayon::iter::ParallelIterator;
+use rayon::prelude::*;
 use serde_json::{json};
-use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::collections::HashMap;
 use std::io::Write;
-use std::sync::atomic::{AtomicI32, AtomicI64, Ordering};
 use std::sync::Arc;
 use anyhow::Result;
 
 #[derive(Debug)]
-pub struct ToBgSchematic {
-    blocks: VecDeque<BlockStatePos>,
+pub struct ToBgSchematic<'a> {
+    blocks: &'a BlockVolume,
     start_pos: BlockPos,
     end_pos: BlockPos,
     width: i32,
     height: i32,
     length: i32,
-    air_index: usize,
+    tile_entities: TileEntitiesList,
     pub unique_block_states: Vec<Arc<BlockData>>,
     pub block_state_to_index: HashMap<Arc<BlockData>, usize>,
 }
 
-impl ToBgSchematic {
-    pub fn new(schematic: &SchematicData) -> Result<Self, SchematicError> {
-        let block_list = schematic.blocks.clone();
-        let blocks = schematic.blocks.clone().elements;
-        if blocks.is_empty() {
+impl<'a> ToBgSchematic<'a> {
+    pub fn new(schematic: &'a SchematicData) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks;
+        let Some((min, max)) = blocks.bounds() else {
             return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
-        }
-        let (min, max) = blocks.iter().fold(
-            (
-                BlockPos {
-                    x: i32::MAX,
-                    y: i32::MAX,
-                    z: i32::MAX,
-                },
-                BlockPos {
-                    x: i32::MIN,
-                    y: i32::MIN,
-                    z: i32::MIN,
-                },
-            ),
-            |(mut min, mut max), bp| {
-                min.x = min.x.min(bp.pos.x);
-                min.y = min.y.min(bp.pos.y);
-                min.z = min.z.min(bp.pos.z);
-                max.x = max.x.max(bp.pos.x);
-                max.y = max.y.max(bp.pos.y);
-                max.z = max.z.max(bp.pos.z);
-                (min, max)
-            },
-        );
+        };
 
         let width = max.x - min.x + 1;
         let height = max.y - min.y + 1;
         let length = max.z - min.z + 1;
 
-        let (unique_block_states, block_state_to_index, air_index) = {
-            let mut seen = HashMap::new();
-            let mut unique = Vec::new();
-            let mut index_map = HashMap::new();
-            let air = Arc::new(BlockData {
-                id: BlockId {
-                    name: Arc::from("minecraft:air"),
-                },
-                properties: BTreeMap::new(),
-            });
-
-            for block_pos in &block_list.elements {
-                let block_data = block_pos.block.clone();
-
-                if !seen.contains_key(&block_data) {
-                    let index = unique.len();
-                    seen.insert(block_data.clone(), index);
-                    unique.push(block_data.clone());
-                    index_map.insert(block_data, index);
-                }
-            }
-
-            if !seen.contains_key(&air) {
-                let index = unique.len();
-                seen.insert(air.clone(), index);
-                unique.push(air.clone());
-                index_map.insert(air.clone(), index);
-            }
-
-            let air_index = *seen.get(&air).unwrap();
-
-            (unique, index_map, air_index)
-        };
+        let unique_block_states = blocks.palette().to_vec();
+        let block_state_to_index = unique_block_states
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.clone(), index))
+            .collect::<HashMap<_, _>>();
 
         Ok(Self {
             blocks,
@@ -107,12 +56,22 @@ impl ToBgSchematic {
             width,
             height,
             length,
-            air_index,
+            tile_entities: schematic.tile_entities_list.clone(),
             unique_block_states,
             block_state_to_index,
         })
     }
 
+    // 按 y -> z -> x 顺序的下标还原绝对坐标
+    fn pos_at(&self, id: usize) -> BlockPos {
+        let layer = (self.width * self.length) as usize;
+        BlockPos {
+            x: self.start_pos.x + (id % self.width as usize) as i32,
+            y: self.start_pos.y + (id / layer) as i32,
+            z: self.start_pos.z + ((id % layer) / self.width as usize) as i32,
+        }
+    }
+
     pub fn bg_palette(&self) -> Value {
         let mut palette = Vec::new();
 
@@ -182,122 +141,70 @@ impl ToBgSchematic {
         Value::List(palette)
     }
 
+    // 模板格式的方块实体挂在调色板条目的 data 上, 每个方块实体单独追加一个条目
+    pub fn attach_tile_entities_type1(&self, palette: &mut Vec<Value>, longs: &mut [i64]) {
+        for te in &self.tile_entities.elements {
+            let rel = te.relative_to(&self.start_pos);
+            let id = (rel.y * self.width * self.length) + (rel.z * self.width) + rel.x;
+            let Some(long) = usize::try_from(id).ok().and_then(|i| longs.get_mut(i)) else {
+                continue;
+            };
+            let state_id = read_state_id(*long) as usize;
+            let Some(Compound(mut entry)) = palette.get(state_id).cloned() else {
+                continue;
+            };
+            entry.insert("data".to_string(), te.nbt.clone());
+            palette.push(Compound(entry));
+            let new_id = (palette.len() - 1) as i64;
+            *long = (*long & !(B3_BYTE_MASK << 40)) | ((new_id & B3_BYTE_MASK) << 40);
+        }
+    }
+
     pub fn get_block_id_list(&self) -> Vec<i32> {
         let total_blocks = (self.length * self.width * self.height) as usize;
-        let air_index = self.air_index as i32;
-        let atomic_block_list: Vec<AtomicI32> =
-            (0..total_blocks).map(|_| AtomicI32::new(air_index)).collect();
-        let atomic_block_list = Arc::new(atomic_block_list);
-
-        self.blocks.par_iter().for_each(|block| {
-            let dx = block.pos.x - self.start_pos.x;
-            let dy = block.pos.y - self.start_pos.y;
-            let dz = block.pos.z - self.start_pos.z;
-
-            let id = (dz * self.width * self.height) + (dy * self.width) + dx;
-
-            if id >= 0 && (id as usize) < atomic_block_list.len() {
-                let state_id = self
-                    .block_state_to_index
-                    .get(&block.block)
-                    .map(|v| *v as i32)
-                    .unwrap_or(air_index);
-
-                atomic_block_list[id as usize].store(state_id, Ordering::Relaxed);
-            }
-        });
-
-        Arc::try_unwrap(atomic_block_list)
-            .unwrap()
-            .into_iter()
-            .map(|atomic| atomic.into_inner())
+        let layer = (self.width * self.height) as usize;
+
+        // 该格式索引顺序为 z -> y -> x
+        (0..total_blocks)
+            .into_par_iter()
+            .map(|id| {
+                let pos = BlockPos {
+                    x: self.start_pos.x + (id % self.width as usize) as i32,
+                    y: self.start_pos.y + ((id % layer) / self.width as usize) as i32,
+                    z: self.start_pos.z + (id / layer) as i32,
+                };
+                self.blocks.get_index(pos) as i32
+            })
             .collect()
     }
 
     pub fn get_block_longs(&self) -> Vec<i64> {
         let total_blocks = (self.length * self.width * self.height) as usize;
-        let air_index = self.air_index as i32;
-        let atomic_block_list: Vec<AtomicI64> =
-            (0..total_blocks).map(|_| AtomicI64::new(0)).collect();
-        let atomic_block_list = Arc::new(atomic_block_list);
-
-        self.blocks.par_iter().for_each(|block| {
-            let dx = block.pos.x - self.start_pos.x;
-            let dy = block.pos.y - self.start_pos.y;
-            let dz = block.pos.z - self.start_pos.z;
-
-            let id = (dy * self.width * self.length) + (dz * self.width) + dx;
-
-            if id >= 0 && (id as usize) < atomic_block_list.len() {
-                let state_id = self
-                    .block_state_to_index
-                    .get(&block.block)
-                    .map(|v| *v as i32)
-                    .unwrap_or(air_index);
-                let long_val = ((state_id as i64 & B3_BYTE_MASK) << 40)
-                    | ((block.pos.x as i64 & B2_BYTE_MASK) << 24)
-                    | ((block.pos.y as i64 & B1_BYTE_MASK) << 16)
-                    | (block.pos.z as i64 & B2_BYTE_MASK);
-
-                atomic_block_list[id as usize].store(long_val, Ordering::Relaxed);
-            }
-        });
 
-        Arc::try_unwrap(atomic_block_list)
-            .unwrap()
-            .into_iter()
-            .map(|atomic| atomic.into_inner())
+        (0..total_blocks)
+            .into_par_iter()
+            .map(|id| {
+                let pos = self.pos_at(id);
+                let state_id = self.blocks.get_index(pos) as i64;
+                ((state_id & B3_BYTE_MASK) << 40)
+                    | ((pos.x as i64 & B2_BYTE_MASK) << 24)
+                    | ((pos.y as i64 & B1_BYTE_MASK) << 16)
+                    | (pos.z as i64 & B2_BYTE_MASK)
+            })
             .collect()
     }
 
     pub fn get_block_and_pos(&self) -> (Vec<i32>, Vec<i32>) {
         let total_blocks = (self.length * self.width * self.height) as usize;
-        let air_index = self.air_index as i32;
-        let atomic_block_list: Vec<AtomicI32> =
-            (0..total_blocks).map(|_| AtomicI32::new(0)).collect();
-        let atomic_block_list = Arc::new(atomic_block_list);
-        let atomic_block_pos_list: Vec<AtomicI32> =
-            (0..total_blocks).map(|_| AtomicI32::new(0)).collect();
-        let atomic_block_pos_list = Arc::new(atomic_block_pos_list);
-
-        self.blocks.par_iter().for_each(|block| {
-            let dx = block.pos.x - self.start_pos.x;
-            let dy = block.pos.y - self.start_pos.y;
-            let dz = block.pos.z - self.start_pos.z;
-
-            let id = (dy * self.width * self.length) + (dz * self.width) + dx;
-
-            if id >= 0 && (id as usize) < atomic_block_list.len() {
-                let state_id = self
-                    .block_state_to_index
-                    .get(&block.block)
-                    .map(|v| *v as i32)
-                    .unwrap_or(air_index);
-                let pos_id = rel_pos_to_int(
-                    self.start_pos,
-                    BlockPos {
-                        x: block.pos.x,
-                        y: block.pos.y,
-                        z: block.pos.z,
-                    },
-                );
-                atomic_block_list[id as usize].store(state_id + 1, Ordering::Relaxed);
-                atomic_block_pos_list[id as usize].store(pos_id, Ordering::Relaxed);
-            }
-        });
-
-        (
-            Arc::try_unwrap(atomic_block_list)
-                .unwrap()
-                .into_iter()
-                .map(|atomic| atomic.into_inner())
-                .collect(),
-            Arc::try_unwrap(atomic_block_pos_list)
-                .unwrap()
-                .into_iter()
-                .map(|atomic| atomic.into_inner())
-                .collect(),
-        )
+
+        (0..total_blocks)
+            .into_par_iter()
+            .map(|id| {
+                let pos = self.pos_at(id);
+                let state_id = self.blocks.get_index(pos) as i32;
+                (state_id + 1, rel_pos_to_int(self.start_pos, pos))
+            })
+            .unzip()
     }
 
     pub fn to_base64(&self, value: &Value) -> Result<String, SchematicError> {
@@ -344,14 +251,18 @@ impl ToBgSchematic {
         bounds.insert("minZ".to_string(), Value::Int(self.start_pos.z));
         header.insert("bounds".to_string(), Compound(bounds));
         header.insert("name".to_string(), Value::String("null".to_string()));
-        let longs = self.get_block_longs();
+        let mut longs = self.get_block_longs();
+        let Value::List(mut palette) = self.bg_palette_type1() else {
+            return Err(SchematicError::InvalidFormat("palette is not a list"));
+        };
+        self.attach_tile_entities_type1(&mut palette, &mut longs);
         let mut blocks = Vec::new();
         for id in longs {
             blocks.push(Value::Long(id));
         }
         compound.insert("pos".to_string(), Value::List(blocks));
         compound.insert("header".to_string(), Compound(header));
-        compound.insert("data".to_string(), self.bg_palette_type1());
+        compound.insert("data".to_string(), Value::List(palette));
         Ok(Compound(compound))
     }
 
@@ -1,6 +1,8 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePosList};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::extend_value::NbtExt;
 use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
+use crate::utils::entities::{read_pos, EntitiesList};
 use crate::utils::tile_entities::TileEntitiesList;
 use fastnbt::{self, Value, Value::Compound};
 use flate2::read::GzDecoder;
@@ -109,6 +111,24 @@ impl CreateSchematic {
         }
     }
 
+    // 结构文件实体: {pos: [double], blockPos: [int], nbt: {...}}, nbt 中已带 id
+    pub fn parse_entities(&self) -> EntitiesList {
+        let mut entities = EntitiesList::default();
+        let Ok(list) = self.get_entities() else {
+            return entities;
+        };
+        for entity in list {
+            let Compound(entry) = entity else {
+                continue;
+            };
+            let (Some(pos), Some(nbt)) = (read_pos(entry.get("pos")), entry.get("nbt")) else {
+                continue;
+            };
+            entities.add_read(pos, BlockPos::default(), nbt.clone());
+        }
+        entities
+    }
+
     fn parse_palette(&self) -> Result<Vec<Arc<BlockData>>, SchematicError> {
         let palette_list = self.get_palette()?;
 
@@ -142,8 +162,8 @@ impl CreateSchematic {
     }
 
     pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
-        let mut block_list = BlockStatePosList::default();
-        let tile_entities = TileEntitiesList::default();
+        let mut block_list = BlockVolume::default();
+        let mut tile_entities = TileEntitiesList::default();
         let blocks = self.get_pos_list()?;
         let palette = self.parse_palette()?;
         let size = self.get_size()?;
@@ -205,6 +225,11 @@ impl CreateSchematic {
                 _ => return Err(SchematicError::InvalidFormat("State ID must be integer")),
             };
             let block_data = &palette[state_id];
+            if let Compound(compound) = block {
+                if let Some(nbt) = compound.get("nbt") {
+                    tile_entities.add(pos, nbt.clone());
+                }
+            }
             block_list.add(pos, Arc::clone(block_data))
         }
 
@@ -216,7 +241,9 @@ impl CreateSchematic {
                 height: sizes[1],
                 length: sizes[2],
             },
-        ))
+        )
+        .with_entities(self.parse_entities())
+        .with_data_version(self.get_data_version().ok()))
     }
 }
 
@@ -1,68 +1,34 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockPos, BlockStatePos};
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::entities::{pos_value, EntitiesList};
+use crate::utils::tile_entities::{strip_keys, TileEntitiesList};
 use fastnbt::Value;
 use fastnbt::Value::Compound;
-use rayon::iter::IndexedParallelIterator;
-use rayon::iter::IntoParallelRefIterator;
-use rayon::iter::ParallelIterator;
-use std::collections::{HashMap, VecDeque};
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::sync::Arc;
 use anyhow::Result;
 
 #[derive(Debug)]
-pub struct ToCreateSchematic {
-    blocks: VecDeque<BlockStatePos>,
+pub struct ToCreateSchematic<'a> {
+    blocks: &'a BlockVolume,
     start_pos: BlockPos,
     end_pos: BlockPos,
     width: i32,
     height: i32,
     length: i32,
+    tile_entities: TileEntitiesList,
+    entities: EntitiesList,
     pub unique_block_states: Vec<Arc<BlockData>>,
     pub block_state_to_index: HashMap<Arc<BlockData>, usize>,
 }
 
-impl ToCreateSchematic {
-    pub fn new(schematic: &SchematicData) -> Result<Self, SchematicError> {
-        let blocks = schematic.blocks.clone().elements;
-        if blocks.is_empty() {
+impl<'a> ToCreateSchematic<'a> {
+    pub fn new(schematic: &'a SchematicData) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks;
+        let Some((min, _)) = blocks.bounds() else {
             return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
-        }
-        let min = {
-            let global_min = blocks
-                .par_iter()
-                .with_min_len(1_000_000)
-                .fold(
-                    || BlockPos {
-                        x: i32::MAX,
-                        y: i32::MAX,
-                        z: i32::MAX,
-                    },
-                    |mut acc, bp| {
-                        acc.x = std::cmp::min(acc.x, bp.pos.x);
-                        acc.y = std::cmp::min(acc.y, bp.pos.y);
-                        acc.z = std::cmp::min(acc.z, bp.pos.z);
-                        acc
-                    },
-                )
-                .reduce(
-                    || BlockPos {
-                        x: i32::MAX,
-                        y: i32::MAX,
-                        z: i32::MAX,
-                    },
-                    |mut rel, tem| {
-                        rel.x = std::cmp::min(rel.x, tem.x);
-                        rel.y = std::cmp::min(rel.y, tem.y);
-                        rel.z = std::cmp::min(rel.z, tem.z);
-                        rel
-                    },
-                );
-
-            BlockPos {
-                x: global_min.x,
-                y: global_min.y,
-                z: global_min.z,
-            }
         };
         let size = schematic.size;
         let max = BlockPos {
@@ -74,24 +40,12 @@ impl ToCreateSchematic {
         let height = max.y - min.y + 1;
         let length = max.z - min.z + 1;
 
-        let (unique_block_states, block_state_to_index) = {
-            let mut seen = HashMap::new();
-            let mut unique = Vec::new();
-            let mut index_map = HashMap::new();
-
-            for block_pos in &blocks {
-                let block_data = block_pos.block.clone();
-
-                if !seen.contains_key(&block_data) {
-                    let index = unique.len();
-                    seen.insert(block_data.clone(), index);
-                    unique.push(block_data.clone());
-                    index_map.insert(block_data, index);
-                }
-            }
-
-            (unique, index_map)
-        };
+        let unique_block_states = blocks.palette().to_vec();
+        let block_state_to_index = unique_block_states
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.clone(), index))
+            .collect::<HashMap<_, _>>();
 
         Ok(Self {
             blocks,
@@ -100,6 +54,8 @@ impl ToCreateSchematic {
             width,
             height,
             length,
+            tile_entities: schematic.tile_entities_list.clone(),
+            entities: schematic.entities.clone(),
             unique_block_states,
             block_state_to_index,
         })
@@ -127,38 +83,71 @@ impl ToCreateSchematic {
     }
 
     pub fn create_blocks(&self, air: bool) -> Value {
-        let block_list: Vec<Value> = self
-            .blocks
-            .par_iter()
-            .filter_map(|block_pos| {
-                let data = (*block_pos.block).clone();
-                if data.id.name.to_string() == "minecraft:air" && !air {
-                    return None;
-                }
-
-                let state_id = self
-                    .block_state_to_index
-                    .get(&data)
-                    .expect("Block state not found in palette");
+        let tile_entities = self.tile_entities.to_map();
+        let block_tag = |pos: BlockPos, state_id: u32| {
+            let relative = Value::List(vec![
+                Value::Int(pos.x - self.start_pos.x),
+                Value::Int(pos.y - self.start_pos.y),
+                Value::Int(pos.z - self.start_pos.z),
+            ]);
+
+            let mut block_tag = HashMap::new();
+            block_tag.insert("state".to_string(), Value::Int(state_id as i32));
+            block_tag.insert("pos".to_string(), relative);
+            if let Some(nbt) = tile_entities.get(&pos) {
+                block_tag.insert("nbt".to_string(), (*nbt).clone());
+            }
+            Compound(block_tag)
+        };
 
-                let pos = Value::List(vec![
-                    Value::Int(block_pos.pos.x - self.start_pos.x),
-                    Value::Int(block_pos.pos.y - self.start_pos.y),
-                    Value::Int(block_pos.pos.z - self.start_pos.z),
-                ]);
+        // 保留空气时遍历整个体积, 否则只遍历已分配的非空气方块
+        let block_list: Vec<Value> = match (air, self.blocks.bounds()) {
+            (true, Some((min, max))) => (min.y..=max.y)
+                .into_par_iter()
+                .flat_map_iter(|y| {
+                    (min.z..=max.z).flat_map(move |z| {
+                        (min.x..=max.x).map(move |x| BlockPos { x, y, z })
+                    })
+                })
+                .map(|pos| block_tag(pos, self.blocks.get_index(pos)))
+                .collect(),
+            _ => self
+                .blocks
+                .par_iter()
+                .map(|(pos, block)| {
+                    let state_id = self
+                        .block_state_to_index
+                        .get(block)
+                        .expect("Block state not found in palette");
+                    block_tag(pos, *state_id as u32)
+                })
+                .collect(),
+        };
 
-                let mut block_tag = HashMap::new();
-                block_tag.insert("state".to_string(), Value::Int(*state_id as i32));
-                block_tag.insert("pos".to_string(), pos);
+        Value::List(block_list)
+    }
 
-                Some(Compound(block_tag))
+    pub fn create_entities(&self) -> Value {
+        let list = self
+            .entities
+            .elements
+            .iter()
+            .map(|e| {
+                let pos = e.relative_to(&self.start_pos);
+                let mut tag = HashMap::new();
+                tag.insert("pos".to_string(), pos_value(pos));
+                tag.insert(
+                    "blockPos".to_string(),
+                    Value::List(pos.iter().map(|n| Value::Int(n.floor() as i32)).collect()),
+                );
+                tag.insert("nbt".to_string(), strip_keys(&e.nbt, &["Pos", "UUID"]));
+                Compound(tag)
             })
             .collect();
-
-        Value::List(block_list)
+        Value::List(list)
     }
 
-    pub fn create_schematic(&self, air: bool) -> Value {
+    pub fn create_schematic(&self, air: bool, data_version: i32) -> Value {
         let mut tag = HashMap::new();
 
         let size = Value::List(vec![
@@ -169,8 +158,8 @@ impl ToCreateSchematic {
         tag.insert("size".to_string(), size);
         tag.insert("blocks".to_string(), self.create_blocks(air));
         tag.insert("palette".to_string(), self.create_palette());
-        tag.insert("entities".to_string(), Value::List(vec![]));
-        tag.insert("DataVersion".to_string(), Value::Int(3465)); // 你说的对别问我这里为什么写固定值
+        tag.insert("entities".to_string(), self.create_entities());
+        tag.insert("DataVersion".to_string(), Value::Int(data_version));
 
         Compound(tag)
     }
@@ -3,6 +3,8 @@ use std::fs;
 use anyhow::{Context};
 use anyhow::Result;
 use crate::database::db_control::{drop_all_tables_in_transaction, DatabaseState};
+use crate::utils::minecraft_data::versions_data::VersionData;
+use std::path::Path;
 use tauri_plugin_shell::ShellExt;
 #[tauri::command]
 pub async fn clear_app_data(
@@ -33,3 +35,14 @@ pub async fn clear_app_data(
     }
     Ok(())
 }
+
+// 离线导入新的版本表, 与现有数据合并后立即生效
+#[tauri::command]
+pub async fn import_version_data(
+    versions_data: State<'_, VersionData>,
+    path: String,
+) -> Result<usize, String> {
+    versions_data
+        .import(Path::new(&path))
+        .map_err(|e| e.to_string())
+}
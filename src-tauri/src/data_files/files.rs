@@ -1,21 +1,16 @@
-use crate::building_gadges::bg_schematic::BgSchematic;
-use crate::create::create_schematic::CreateSchematic;
-use crate::litematica::lm_schematic::LmSchematic;
 use crate::modules::modules_data::convert_data::{ConvertData, SchematicType, Target};
+use crate::schematic_format::{
+    file_extension, format_by_code, formats, EncodedSchematic, ReadOptions,
+};
 use crate::utils::schematic_data::SchematicData;
-use crate::word_edit::we_schematic::WeSchematic;
 use anyhow::Result;
-use anyhow::{anyhow, Context};
-use fastnbt::Value;
-use flate2::read::GzDecoder;
-use flate2::write::GzEncoder;
+use anyhow::Context;
 use tempfile::NamedTempFile;
-use flate2::Compression;
 use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::{BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
@@ -143,121 +138,6 @@ impl FileManager {
         Ok(final_path)
     }
 
-    pub fn save_json_value(
-        &self,
-        id: i64,
-        data: String,
-        version: i32,
-        sub_version: i32,
-        v_type: i32,
-    ) -> Result<PathBuf> {
-        let schematic_dir = self.schematic_dir(id)?;
-
-        let final_filename = format!(
-            "schematic_{}.{}.{}.{}",
-            version, sub_version, v_type, "json"
-        );
-        let final_path = schematic_dir.join(final_filename);
-        let out_path = final_path.clone();
-        let mut file = File::create(final_path)?;
-        file.write_all(data.as_bytes())?;
-        Ok(out_path)
-    }
-
-    pub fn save_json_value_temp(
-        &self,
-        data: String,
-    ) -> Result<NamedTempFile> {
-        let mut temp_file = tempfile::Builder::new()
-            .prefix("schematic_")
-            .suffix(".json")
-            .tempfile()?;
-
-        temp_file.write_all(data.as_bytes())?;
-
-        temp_file.seek(SeekFrom::Start(0))?;
-
-        Ok(temp_file)
-    }
-
-    pub fn save_nbt_value(
-        &self,
-        id: i64,
-        data: Value,
-        version: i32,
-        sub_version: i32,
-        v_type: i32,
-        compress: bool,
-    ) -> Result<PathBuf> {
-        let schematic_dir = self.schematic_dir(id)?;
-
-        let file_ext = match v_type {
-            1 => "nbt",
-            2 => "litematic",
-            3 => "schem",
-            4 => "json",
-            5 => "mcstruct",
-            _ => "unknown",
-        };
-
-        let final_filename = format!(
-            "schematic_{}.{}.{}.{}",
-            version, sub_version, v_type, file_ext
-        );
-
-        let final_path = schematic_dir.join(final_filename);
-        let out_path = final_path.clone();
-
-        let bytes = fastnbt::to_bytes(&data)?;
-
-        if compress {
-            let file = File::create(final_path)?;
-            let mut encoder = GzEncoder::new(file, Compression::default());
-            encoder.write_all(&bytes)?;
-            encoder.finish()?;
-        } else {
-            let mut file = File::create(final_path)?;
-            file.write_all(&bytes)?;
-        }
-
-        Ok(out_path)
-    }
-
-    pub fn save_nbt_value_temp(
-        &self,
-        data: Value,
-        v_type: i32,
-        compress: bool,
-    ) -> Result<NamedTempFile> {
-
-        let file_ext = match v_type {
-            1 => ".nbt",
-            2 => ".litematic",
-            3 => ".schem",
-            4 => ".json",
-            5 => ".mcstruct",
-            _ => ".unknown",
-        };
-
-        let mut temp_file = tempfile::Builder::new()
-            .prefix("schematic_")
-            .suffix(file_ext)
-            .tempfile()?;
-
-        let bytes = fastnbt::to_bytes(&data)?;
-
-        if compress {
-            let mut encoder = GzEncoder::new(&mut temp_file, Compression::default());
-            encoder.write_all(&bytes)?;
-            encoder.finish()?;
-        } else {
-            temp_file.write_all(&bytes)?;
-        }
-
-        temp_file.seek(SeekFrom::Start(0))?;
-
-        Ok(temp_file)
-    }
     pub fn read_schematic_str(
         &self,
         id: i64,
@@ -266,14 +146,7 @@ impl FileManager {
         v_type: i32,
     ) -> Result<String> {
         let schematic_dir = self.schematic_dir(id)?;
-        let file_ext = match v_type {
-            1 => "nbt",
-            2 => "litematic",
-            3 => "schem",
-            4 => "json",
-            5 => "mcstruct",
-            _ => "unknown",
-        };
+        let file_ext = file_extension(v_type);
         let filename = format!(
             "schematic_{}.{}.{}.{}",
             version, sub_version, v_type, file_ext
@@ -283,57 +156,11 @@ impl FileManager {
 
         let data = fs::read(&file_path)
             .with_context(|| format!("Unable to read schematic file: {}", file_path.display()))?;
-        match v_type {
-            1 => {
-                if data.len() > 8 * 1024 * 1024 {
-                    return Ok(String::new());
-                }
-                let cursor = Cursor::new(data);
-                let mut decoder = GzDecoder::new(cursor);
-
-                let nbt: Value = fastnbt::from_reader(&mut decoder)?;
-                let ser = fastsnbt::to_string(&nbt)?;
-                Ok(ser)
-            }
-            2 => {
-                if data.len() > 1 * 512 * 1024 {
-                    return Ok(String::new());
-                }
-                let cursor = Cursor::new(data);
-                let mut decoder = GzDecoder::new(cursor);
-
-                let nbt: Value = fastnbt::from_reader(&mut decoder)?;
-                let ser = fastsnbt::to_string(&nbt)?;
-                Ok(ser)
-            }
-            3 => {
-                if data.len() > 8 * 1024 * 1024 {
-                    return Ok(String::new());
-                }
-                let cursor = Cursor::new(data);
-                let mut decoder = GzDecoder::new(cursor);
-
-                let nbt: Value = fastnbt::from_reader(&mut decoder)?;
-                let ser = fastsnbt::to_string(&nbt)?;
-                Ok(ser)
-            }
-            4 => {
-                if data.len() > 8 * 1024 * 1024 {
-                    return Ok(String::new());
-                }
-                let json_str = String::from_utf8(data)?;
-                Ok(json_str)
-            }
-            5 => {
-                if data.len() > 8 * 1024 * 1024 {
-                    return Ok(String::new());
-                }
-                let nbt: Value = fastnbt::from_bytes(&data)?;
-                let ser = fastsnbt::to_string(&nbt)?;
-                Ok(ser)
-            }
-            _ => Err(anyhow!("UNK: {}", v_type)),
+        let format = format_by_code(v_type)?;
+        if data.len() > format.preview_limit() {
+            return Ok(String::new());
         }
+        format.read_text(data)
     }
 
     pub fn copy_file(
@@ -345,33 +172,51 @@ impl FileManager {
         target_path: String,
     ) -> Result<bool> {
         let schematic_dir = self.schematic_dir(id)?;
-        let file_ext = match v_type {
-            1 => "nbt",
-            2 => "litematic",
-            3 => "schem",
-            4 => "json",
-            5 => "mcstruct",
-            _ => "unknown",
-        };
+        let file_ext = file_extension(v_type);
         let filename = format!(
             "schematic_{}.{}.{}.{}",
             version, sub_version, v_type, file_ext
         );
-        let path = PathBuf::from(&target_path);
         let file_path = schematic_dir.join(&filename);
+        let dest_path = Self::export_path(&target_path, &filename)?;
+
+        fs::copy(&file_path, &dest_path).map_err(|e| anyhow::anyhow!("File copying failed: {}", e))?;
+
+        Ok(true)
+    }
+    // 目标为目录时使用默认文件名, 并创建缺失的上级目录
+    fn export_path(target_path: &str, filename: &str) -> Result<PathBuf> {
+        let path = PathBuf::from(target_path);
         let dest_path = if path.is_dir() {
-            path.join(&filename)
+            path.join(filename)
         } else {
-            path.to_path_buf()
+            path
         };
 
         if let Some(parent) = dest_path.parent() {
             fs::create_dir_all(parent)?;
         }
+        Ok(dest_path)
+    }
 
-        fs::copy(&file_path, &dest_path).map_err(|e| anyhow::anyhow!("File copying failed: {}", e))?;
+    // 直接导出到目标路径, 不占用蓝图目录中的转换缓存
+    pub fn export_encoded(
+        &self,
+        target_path: String,
+        filename: &str,
+        encoded: &EncodedSchematic,
+        v_type: i32,
+    ) -> Result<PathBuf> {
+        let bytes = format_by_code(v_type)?.encode(encoded)?;
+        let dest_path = Self::export_path(&target_path, filename)?;
+        fs::write(&dest_path, bytes).map_err(|e| anyhow::anyhow!("File writing failed: {}", e))?;
+        Ok(dest_path)
+    }
 
-        Ok(true)
+    pub fn export_text(&self, target_path: String, filename: &str, content: &str) -> Result<PathBuf> {
+        let dest_path = Self::export_path(&target_path, filename)?;
+        fs::write(&dest_path, content).map_err(|e| anyhow::anyhow!("File writing failed: {}", e))?;
+        Ok(dest_path)
     }
     pub fn get_convert_data(
         &self,
@@ -390,22 +235,16 @@ impl FileManager {
             schematics: HashMap::new(),
         };
 
-        for schematic_type in [
-            SchematicType::Create,
-            SchematicType::Litematic,
-            SchematicType::Bg,
-            SchematicType::We,
-            SchematicType::Be,
-        ] {
+        for format in formats() {
             let mut version_map = HashMap::new();
 
-            for sub_v in schematic_type.get_sub_versions() {
+            for sub_v in format.sub_versions() {
                 let filename = format!(
                     "schematic_{}.{}.{}.{}",
                     version,
                     sub_v,
-                    schematic_type.type_id(),
-                    schematic_type.file_extension()
+                    format.type_id(),
+                    format.extension()
                 );
                 let path = schematic_dir.join(&filename);
 
@@ -423,7 +262,9 @@ impl FileManager {
             }
 
             if !version_map.is_empty() {
-                convert_data.schematics.insert(schematic_type, version_map);
+                convert_data
+                    .schematics
+                    .insert(format.schematic_type(), version_map);
             }
         }
 
@@ -443,16 +284,10 @@ impl FileManager {
         version: i32,
         sub_version: i32,
         v_type: i32,
+        options: &ReadOptions,
     ) -> Result<SchematicData> {
         let schematic_dir = self.schematic_dir(id)?;
-        let file_ext = match v_type {
-            1 => "nbt",
-            2 => "litematic",
-            3 => "schem",
-            4 => "json",
-            5 => "mcstruct",
-            _ => "unknown",
-        };
+        let file_ext = file_extension(v_type);
         let filename = format!(
             "schematic_{}.{}.{}.{}",
             version, sub_version, v_type, file_ext
@@ -461,29 +296,46 @@ impl FileManager {
         let file_path = schematic_dir.join(filename);
         let data = fs::read(&file_path)
             .with_context(|| format!("Unable to read blueprint file: {}", file_path.display()))?;
-        match v_type {
-            1 => {
-                let schematic = CreateSchematic::new_from_bytes(data)?;
-                let blocks = schematic.get_blocks_pos();
-                Ok(blocks?)
-            }
-            2 => {
-                let schematic = LmSchematic::new_from_bytes(data)?;
-                let blocks = schematic.get_blocks_pos();
-                Ok(blocks?)
-            }
-            3 => {
-                let schematic = WeSchematic::new_from_bytes(data)?;
-                let blocks = schematic.get_blocks_pos();
-                Ok(blocks?)
-            }
-            4 => {
-                let schematic = BgSchematic::new_from_data(data)?;
-                let blocks = schematic.get_blocks_pos();
-                Ok(blocks?)
-            }
-            _ => Err(anyhow!("UNK: {}", v_type)),
-        }
+        format_by_code(v_type)?.read(data, options)
+    }
+
+    pub fn save_encoded(
+        &self,
+        id: i64,
+        encoded: EncodedSchematic,
+        version: i32,
+        v_type: i32,
+    ) -> Result<PathBuf> {
+        let format = format_by_code(v_type)?;
+        let bytes = format.encode(&encoded)?;
+        self.save_schematic_data(
+            id,
+            bytes,
+            version,
+            encoded.sub_version,
+            v_type,
+            format.extension().to_string(),
+        )
+    }
+
+    pub fn save_encoded_temp(
+        &self,
+        encoded: EncodedSchematic,
+        v_type: i32,
+    ) -> Result<NamedTempFile> {
+        let format = format_by_code(v_type)?;
+        let bytes = format.encode(&encoded)?;
+
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("schematic_")
+            .suffix(&format!(".{}", format.extension()))
+            .tempfile()?;
+
+        temp_file.write_all(&bytes)?;
+
+        temp_file.seek(SeekFrom::Start(0))?;
+
+        Ok(temp_file)
     }
 }
 
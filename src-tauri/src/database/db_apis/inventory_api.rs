@@ -0,0 +1,41 @@
+use anyhow::Result;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension};
+
+pub fn save_inventory(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+    schematic_id: i64,
+    source: &str,
+    inventory: &str,
+) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        r#"INSERT INTO schematic_inventory (
+            schematic_id, source, inventory
+        ) VALUES (?1, ?2, ?3)
+        ON CONFLICT(schematic_id) DO UPDATE SET
+            source = excluded.source,
+            inventory = excluded.inventory,
+            updated_at = CURRENT_TIMESTAMP"#,
+        params![schematic_id, source, inventory],
+    )?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+// (来源, 背包 JSON, 更新时间)
+pub fn find_inventory(
+    conn: &PooledConnection<SqliteConnectionManager>,
+    schematic_id: i64,
+) -> Result<Option<(String, String, String)>> {
+    let row = conn
+        .query_row(
+            "SELECT source, inventory, updated_at FROM schematic_inventory WHERE schematic_id = ?1",
+            [schematic_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?;
+    Ok(row)
+}
@@ -1,4 +1,5 @@
 pub mod history_api;
+pub mod inventory_api;
 pub mod logs_api;
 pub mod schematic_data_api;
 pub mod schematics_api;
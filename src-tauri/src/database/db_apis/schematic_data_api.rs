@@ -1,4 +1,6 @@
 use crate::database::db_control::DatabaseState;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::RequirementStr;
 use r2d2::PooledConnection;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::params;
@@ -43,35 +45,83 @@ pub fn update_schematic_data(
 
     Ok(rowid)
 }
+// 所有未删除蓝图的 (id, 名称, unique_blocks)
+pub fn list_unique_blocks(
+    conn: &mut PooledConnection<SqliteConnectionManager>,
+) -> anyhow::Result<Vec<(i64, String, String)>> {
+    let mut stmt = conn.prepare(
+        r#"SELECT s.id, s.name, d.unique_blocks
+        FROM schematics s
+        JOIN schematic_data d ON d.schematic_id = s.id
+        WHERE s.is_deleted = FALSE
+        ORDER BY s.id"#,
+    )?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+pub fn find_requirements(
+    conn: &PooledConnection<SqliteConnectionManager>,
+    schematic_id: i64,
+) -> anyhow::Result<String> {
+    let requirements = conn.query_row(
+        "SELECT requirements FROM schematic_data WHERE schematic_id = ?1",
+        [schematic_id],
+        |row| row.get("requirements"),
+    )?;
+    Ok(requirements)
+}
+
 #[tauri::command]
 pub fn get_schematic_requirements(
     db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
     id: i64,
+    locale: Option<String>,
 ) -> anyhow::Result<String, String> {
     let conn = db.0.get().map_err(|e| e.to_string())?;
 
-    conn.query_row(
-        "SELECT requirements FROM schematic_data WHERE schematic_id = ?1",
-        [id],
-        |row| {
-            let metadata_str: String = row.get("requirements")?;
-            Ok(metadata_str)
-        },
-    )
-    .map_err(|e| e.to_string())
+    let requirements = find_requirements(&conn, id).map_err(|e| e.to_string())?;
+    match locale {
+        Some(locale) => RequirementStr::localize_str(&requirements, &je_blocks, &locale)
+            .map_err(|e| e.to_string()),
+        None => Ok(requirements),
+    }
 }
 
 #[tauri::command]
-pub fn get_unique_block(db: State<'_, DatabaseState>, id: i64) -> anyhow::Result<String, String> {
+pub fn get_unique_block(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    id: i64,
+    locale: Option<String>,
+) -> anyhow::Result<String, String> {
     let conn = db.0.get().map_err(|e| e.to_string())?;
 
-    conn.query_row(
-        "SELECT unique_blocks FROM schematic_data WHERE schematic_id = ?1",
-        [id],
-        |row| {
-            let unique_block_str: String = row.get("unique_blocks")?;
-            Ok(unique_block_str)
-        },
-    )
-    .map_err(|e| e.to_string())
+    let unique_blocks = conn
+        .query_row(
+            "SELECT unique_blocks FROM schematic_data WHERE schematic_id = ?1",
+            [id],
+            |row| {
+                let unique_block_str: String = row.get("unique_blocks")?;
+                Ok(unique_block_str)
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    let Some(locale) = locale else {
+        return Ok(unique_blocks);
+    };
+    // 每个方块状态补充 name 字段
+    let mut blocks: Vec<serde_json::Value> =
+        serde_json::from_str(&unique_blocks).map_err(|e| e.to_string())?;
+    for block in blocks.iter_mut() {
+        let Some(id) = block.get("id").and_then(|id| id.as_str()) else {
+            continue;
+        };
+        let name = je_blocks.get_locale_name(id, &locale);
+        block["name"] = serde_json::Value::String(name);
+    }
+    serde_json::to_string(&blocks).map_err(|e| e.to_string())
 }
@@ -102,6 +102,22 @@ pub fn init_db(app_handle: &AppHandle) -> Result<DatabaseState> {
         CREATE INDEX IF NOT EXISTS idx_requirements_schematic 
         ON schematic_data(schematic_id);
         
+        CREATE TABLE IF NOT EXISTS schematic_inventory (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            schematic_id INTEGER NOT NULL,
+            source TEXT DEFAULT '', -- 导入的背包文件路径
+            inventory TEXT DEFAULT '{}', -- 物品 ID -> 数量（JSON格式存储）
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+
+            FOREIGN KEY (
+                schematic_id
+            ) REFERENCES schematics (
+                id
+            ) ON DELETE CASCADE,
+
+            UNIQUE(schematic_id)
+        );
+
         CREATE TABLE IF NOT EXISTS app_logs (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
@@ -139,6 +155,7 @@ pub fn drop_all_tables_in_transaction(conn: &Connection) -> Result<()> {
         "app_logs",
         "user_data",
         "schematics_history",
+        "schematic_inventory",
         "schematic_data",
         "schematics"
     ];
@@ -6,26 +6,41 @@ mod data_files;
 mod database;
 pub mod litematica;
 mod map_art;
+mod mcedit;
 pub mod modules;
+mod schematic_format;
 pub mod utils;
 mod word_edit;
 mod split_schematic;
 
 use crate::database::db_control;
 use crate::litematica::lm_schematic::LmSchematic;
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
 use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::minecraft_data::recipes_data::RecipesData;
 use crate::utils::schematic_data::SchematicError;
 use data_files::{config, config::get_config, config::update_config, files::FileManager};
 use database::db_apis::logs_api::{add_logs, get_logs};
+use database::db_data::LogEntry;
 use database::db_apis::schematic_data_api::{get_schematic_requirements, get_unique_block};
 use database::db_apis::schematics_api::{add_schematic, get_schematic, get_schematics};
 use database::db_apis::user_api::get_user_data;
-use modules::convert::{convert, get_je_blocks, get_map_arts, get_schematic_convert_data};
+use modules::compatibility::{check_library_compatibility, check_schematic_compatibility};
+use modules::convert::{
+    convert, get_je_blocks, get_map_arts, get_schematic_convert_data, get_schematic_regions,
+};
 use modules::history::get_history;
-use modules::map_art::create_map_art;
+use modules::inventory::{compare_schematic_inventory, get_schematic_inventory};
+use modules::layers::get_layer_requirements;
+use modules::map_art::{
+    create_map_art, delete_map_art_profile, generate_map_art, list_map_art_profiles,
+    save_map_art_profile,
+};
+use modules::materials::{export_requirements, get_schematic_materials};
 use modules::modules_data;
 use modules::replace::schematic_replacement;
+use modules::transform::schematic_transform;
 use modules::schematic::{
     copy_schematic, delete_schematic, encode_uploaded_schematic, get_schematic_str,
     update_schematic_name_description, get_schematic_data
@@ -38,7 +53,7 @@ use utils::loading::close_splashscreen;
 use utils::minecraft_data::versions_data::VersionData;
 use CMS::get_cms_data::perform_search;
 use utils::open_dev::open_dev;
-use data_files::data_flies::clear_app_data;
+use data_files::data_flies::{clear_app_data, import_version_data};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -57,12 +72,39 @@ pub fn run() {
             app.manage(config);
             let file_manager = FileManager::new(app.handle())?;
             app.manage(file_manager);
-            let version_data = VersionData::new();
+            let version_data = VersionData::new(&config::get_config_dir(app.handle())?);
             app.manage(version_data);
             let je_blocks = BlocksData::new()?;
+            // 跳过的语言文件写入日志, 不影响启动
+            for message in &je_blocks.skipped_files {
+                add_logs(
+                    app.state(),
+                    LogEntry {
+                        level: "WARN".to_string(),
+                        target: "lang".to_string(),
+                        message: message.clone(),
+                        context: None,
+                    },
+                )?;
+            }
             app.manage(je_blocks);
+            let be_blocks = BeBlocksData::new()?;
+            app.manage(be_blocks);
             let map_arts = MapArtsData::new()?;
             app.manage(map_arts);
+            let recipes_data = RecipesData::new()?;
+            for message in &recipes_data.skipped_files {
+                add_logs(
+                    app.state(),
+                    LogEntry {
+                        level: "WARN".to_string(),
+                        target: "recipes".to_string(),
+                        message: message.clone(),
+                        context: None,
+                    },
+                )?;
+            }
+            app.manage(recipes_data);
             Ok(())
         })
         .plugin(tauri_plugin_opener::init())
@@ -75,6 +117,10 @@ pub fn run() {
             open_dev,
             encode_uploaded_schematic,
             create_map_art,
+            generate_map_art,
+            list_map_art_profiles,
+            save_map_art_profile,
+            delete_map_art_profile,
             update_schematic_name_description,
             get_user_data,
             copy_schematic,
@@ -82,6 +128,7 @@ pub fn run() {
             add_logs,
             schematic_split,
             schematic_replacement,
+            schematic_transform,
             get_je_blocks,
             perform_search,
             get_map_arts,
@@ -94,7 +141,16 @@ pub fn run() {
             get_unique_block,
             get_schematic_str,
             get_schematic_data,
-            get_schematic_convert_data
+            get_schematic_convert_data,
+            get_schematic_regions,
+            check_schematic_compatibility,
+            check_library_compatibility,
+            get_schematic_materials,
+            export_requirements,
+            compare_schematic_inventory,
+            get_schematic_inventory,
+            get_layer_requirements,
+            import_version_data
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
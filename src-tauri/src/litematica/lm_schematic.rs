@@ -1,12 +1,13 @@
 use crate::litematica::lm_schematic_data::{LmMetadata, RegionData, RegionList, RegionNameList};
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePosList};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::extend_value::NbtExt;
-use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
-use crate::utils::tile_entities::TileEntitiesList;
+use crate::utils::schematic_data::{Region, SchematicData, SchematicError, Size};
+use crate::utils::entities::{read_pos, EntitiesList};
+use crate::utils::tile_entities::{strip_keys, TileEntitiesList};
 use fastnbt::Value;
 use fastnbt::Value::Compound;
 use flate2::read::GzDecoder;
-use rayon::prelude::*;
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{BufReader, Cursor};
@@ -123,6 +124,10 @@ impl LmSchematic {
             let size = region.get_pos("Size")?;
             let block_state_palette = region.get_list("BlockStatePalette")?;
             let tile_entities = region.get_list("TileEntities")?;
+            let entities = region
+                .get_list("Entities")
+                .map(|list| list.to_vec())
+                .unwrap_or_default();
             let palette_size = block_state_palette.len();
             let adjusted = if palette_size == 0 {
                 u32::MAX
@@ -139,6 +144,7 @@ impl LmSchematic {
                 size,
                 block_state_palette: block_state_palette.to_vec(),
                 tile_entities: tile_entities.to_vec(),
+                entities,
                 bits,
             });
             regions_name_list.add(name.to_string());
@@ -218,72 +224,88 @@ impl LmSchematic {
         self.get_at(index, bits, long_array)
     }
 
+    pub fn get_region(&self, region: &RegionData) -> Region {
+        Region {
+            name: region.region_name.clone(),
+            position: region.position,
+            size: region.size,
+        }
+    }
+
+    // 方块实体坐标相对于选区最小角, 与方块一样加上选区偏移
+    pub fn get_tile_entities(&self, region: &RegionData) -> TileEntitiesList {
+        let min = self.get_region(region).min();
+        let mut tile_entities = TileEntitiesList::default();
+        for entity in &region.tile_entities {
+            let Compound(nbt) = entity else {
+                continue;
+            };
+            let (Ok(x), Ok(y), Ok(z)) = (nbt.get_i32("x"), nbt.get_i32("y"), nbt.get_i32("z"))
+            else {
+                continue;
+            };
+            tile_entities.add(
+                BlockPos {
+                    x: x + min.x,
+                    y: y + min.y,
+                    z: z + min.z,
+                },
+                strip_keys(entity, &["x", "y", "z"]),
+            );
+        }
+        tile_entities
+    }
+
+    // 实体坐标相对于选区原点 (Position), 不随负尺寸偏移
+    pub fn get_entities(&self, region: &RegionData) -> EntitiesList {
+        let mut entities = EntitiesList::default();
+        for entity in &region.entities {
+            let Compound(nbt) = entity else {
+                continue;
+            };
+            let Some(pos) = read_pos(nbt.get("Pos")) else {
+                continue;
+            };
+            entities.add_read(pos, region.position, strip_keys(entity, &["Pos"]));
+        }
+        entities
+    }
+
     pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
         let (regions_list, regions_name_list) = self.process_regions()?;
-        let tile_entities = TileEntitiesList::default();
+        let mut tile_entities = TileEntitiesList::default();
+        let mut entities = EntitiesList::default();
+        let mut regions = Vec::with_capacity(regions_name_list.names.len());
+        for name in &regions_name_list.names {
+            if let Some(region) = regions_list.get(name) {
+                tile_entities.merge(self.get_tile_entities(region));
+                entities.merge(self.get_entities(region));
+                regions.push(self.get_region(region));
+            }
+        }
         let metadata = self.read_metadata()?;
         let size = metadata.enclosing_size;
-        let region_blocks: Vec<BlockStatePosList> = regions_name_list
-            .names
-            .par_iter()
-            .map(|name| {
-                let region = regions_list
-                    .get(name)
-                    .ok_or_else(|| SchematicError::MissingField(name.clone()))?;
+        // 各区域按区块并行解码, 直接写入调色板索引
+        let mut final_block_list = BlockVolume::default();
+        for name in &regions_name_list.names {
+            let region = regions_list
+                .get(name)
+                .ok_or_else(|| SchematicError::MissingField(name.clone()))?;
 
-                let palette = self.parse_palette(&region.block_state_palette)?;
-                let block_states = region.block_states.clone();
-                let bits = region.bits;
-                let size = region.size;
-                let position = region.position;
+            let palette = self.parse_palette(&region.block_state_palette)?;
+            let block_states = &region.block_states;
+            let bits = region.bits;
+            let size = region.size;
+            let bounds = self.get_region(region);
 
-                let width = size.x.unsigned_abs() as usize;
-                let height = size.y.unsigned_abs() as usize;
-                let length = size.z.unsigned_abs() as usize;
-                let y_blocks: Vec<BlockStatePosList> = (0..height)
-                    .into_par_iter()
-                    .map(|y| {
-                        let mut local_blocks = BlockStatePosList::default();
-
-                        for z in 0..length {
-                            for x in 0..width {
-                                let state_id = self.get_block_state(
-                                    x as i32,
-                                    y as i32,
-                                    z as i32,
-                                    size,
-                                    bits,
-                                    &block_states,
-                                ) as usize;
-                                let block_data = &palette[state_id];
-                                local_blocks.add_by_pos(
-                                    x as i32 + position.x,
-                                    y as i32 + position.y,
-                                    z as i32 + position.z,
-                                    block_data.clone(),
-                                );
-                            }
-                        }
-                        local_blocks
-                    })
-                    .collect();
-
-                Ok(y_blocks
-                    .into_iter()
-                    .fold(BlockStatePosList::default(), |mut acc, blocks| {
-                        acc.merge(blocks);
-                        acc
-                    }))
-            })
-            .collect::<Result<Vec<_>, SchematicError>>()?;
-
-        let final_block_list =
-            region_blocks
-                .into_iter()
-                .fold(BlockStatePosList::default(), |mut acc, blocks| {
-                    acc.merge(blocks);
-                    acc
-                });
+            // 负尺寸的选区从最小角开始存储
+            final_block_list.fill_region(
+                bounds.min(),
+                bounds.abs_size(),
+                &palette,
+                |x, y, z| self.get_block_state(x, y, z, size, bits, block_states) as usize,
+            );
+        }
 
         Ok(SchematicData::new(
             final_block_list,
@@ -293,6 +315,9 @@ impl LmSchematic {
                 height: size.y,
                 length: size.z,
             },
-        ))
+        )
+        .with_entities(entities)
+        .with_regions(regions)
+        .with_data_version(self.get_data_version().ok()))
     }
 }
@@ -1,7 +1,8 @@
-use crate::utils::block_state_pos_list::{
-    BlockData, BlockId, BlockPos, BlockStatePos, BlockStatePosList,
-};
-use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::schematic_data::{Region, SchematicData, SchematicError};
+use crate::utils::entities::{pos_value, EntitiesList};
+use crate::utils::tile_entities::{with_keys, TileEntitiesList};
 use chrono::Utc;
 use fastnbt::Value;
 use fastnbt::Value::Compound;
@@ -9,69 +10,38 @@ use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 use rayon::prelude::*;
-use std::collections::{BTreeMap, HashMap, VecDeque};
-use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use anyhow::Result;
 
 #[derive(Debug)]
-pub struct ToLmSchematic {
-    blocks: VecDeque<BlockStatePos>,
+pub struct ToLmSchematic<'a> {
+    blocks: &'a BlockVolume,
     pub start_pos: BlockPos,
     pub end_pos: BlockPos,
     width: i32,
     height: i32,
     length: i32,
     bits: i32,
+    tile_entities: TileEntitiesList,
+    entities: EntitiesList,
+    regions: Vec<Region>,
     pub unique_block_states: Vec<Arc<BlockData>>,
     pub block_state_to_index: HashMap<Arc<BlockData>, usize>,
 }
 
-impl ToLmSchematic {
-    pub fn new(schematic: &SchematicData) -> Result<Self, SchematicError> {
-        let mut block_list = schematic.blocks.clone();
-
-        let min = {
-            let elements = &block_list.elements;
-            if elements.is_empty() {
-                return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
-            }
-
-            let global_min = elements
-                .par_iter()
-                .with_min_len(1_000_000)
-                .fold(
-                    || BlockPos {
-                        x: i32::MAX,
-                        y: i32::MAX,
-                        z: i32::MAX,
-                    },
-                    |mut acc, bp| {
-                        acc.x = std::cmp::min(acc.x, bp.pos.x);
-                        acc.y = std::cmp::min(acc.y, bp.pos.y);
-                        acc.z = std::cmp::min(acc.z, bp.pos.z);
-                        acc
-                    },
-                )
-                .reduce(
-                    || BlockPos {
-                        x: i32::MAX,
-                        y: i32::MAX,
-                        z: i32::MAX,
-                    },
-                    |mut rel, tem| {
-                        rel.x = std::cmp::min(rel.x, tem.x);
-                        rel.y = std::cmp::min(rel.y, tem.y);
-                        rel.z = std::cmp::min(rel.z, tem.z);
-                        rel
-                    },
-                );
-
-            BlockPos {
-                x: global_min.x.saturating_sub(1),
-                y: global_min.y,
-                z: global_min.z.saturating_sub(1),
-            }
+impl<'a> ToLmSchematic<'a> {
+    pub fn new(schematic: &'a SchematicData) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks;
+        let Some((global_min, _)) = blocks.bounds() else {
+            return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
+        };
+        // 四周留一圈空气, 未写入的位置读出即为空气 (调色板 0 号)
+        let min = BlockPos {
+            x: global_min.x.saturating_sub(1),
+            y: global_min.y,
+            z: global_min.z.saturating_sub(1),
         };
         let size = schematic.size;
         let max = BlockPos {
@@ -80,78 +50,16 @@ impl ToLmSchematic {
             z: min.z + size.length + 1,
         };
 
-        let air = Arc::new(BlockData {
-            id: BlockId {
-                name: Arc::from("minecraft:air"),
-            },
-            properties: BTreeMap::new(),
-        });
-        let capacity = ((max.y - min.y) as usize)
-            * (((max.z - min.z) * 2) + ((max.x - min.x) * 2) + 4) as usize;
-
-        block_list.reserve_front(capacity);
-
-        let positions: Vec<_> = (min.y..max.y)
-            .into_par_iter()
-            .flat_map(|y| {
-                let mut positions = Vec::with_capacity(
-                    (max.z - min.z) as usize * 2 + (max.x - min.x) as usize * 2 + 4,
-                );
-
-                for z in min.z..max.z {
-                    positions.push((min.x - 1, y, z));
-                    positions.push((max.x + 1, y, z));
-                }
-
-                for x in min.x..max.x {
-                    positions.push((x, y, min.z - 1));
-                    positions.push((x, y, max.z + 1));
-                }
-
-                positions.push((min.x - 1, y, min.z - 1));
-                positions.push((min.x - 1, y, max.z + 1));
-                positions.push((max.x + 1, y, min.z - 1));
-                positions.push((max.x + 1, y, max.z + 1));
-
-                positions
-            })
-            .collect();
-        let air_blocks: VecDeque<_> = positions
-            .into_par_iter()
-            .map(|(x, y, z)| BlockStatePos::new(BlockPos { x, y, z }, Arc::clone(&air)))
-            .collect();
-
-        block_list.bulk_prepend(air_blocks);
         let width = max.x - min.x + 1;
         let height = max.y - min.y;
         let length = max.z - min.z + 1;
-        let (unique_block_states, block_state_to_index) = {
-            let mut seen = HashMap::new();
-            let mut unique = Vec::new();
-            let mut index_map = HashMap::new();
-            for block_pos in &block_list.elements {
-                let block_data = block_pos.block.clone();
-
-                if !seen.contains_key(&block_data) {
-                    let index = unique.len();
-                    seen.insert(block_data.clone(), index);
-                    unique.push(block_data.clone());
-                    index_map.insert(block_data, index);
-                }
-            }
-
-            (unique, index_map)
-        };
-        let palette_size = unique_block_states.len() as i32;
-        let adjusted = if palette_size == 0 {
-            u32::MAX
-        } else {
-            palette_size.saturating_sub(1) as u32
-        };
-        let leading_zeros = adjusted.leading_zeros();
-        let bits_unclamped = 32u32.saturating_sub(leading_zeros);
-        let bits = (bits_unclamped as f64).max(2.0) as i32;
-        let blocks = block_list.elements;
+        let unique_block_states = blocks.palette().to_vec();
+        let block_state_to_index = unique_block_states
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.clone(), index))
+            .collect::<HashMap<_, _>>();
+        let bits = Self::palette_bits(unique_block_states.len());
         Ok(Self {
             blocks,
             start_pos: min,
@@ -160,44 +68,46 @@ impl ToLmSchematic {
             height,
             length,
             bits,
+            tile_entities: schematic.tile_entities_list.clone(),
+            entities: schematic.entities.clone(),
+            regions: schematic.regions.clone(),
             unique_block_states,
             block_state_to_index,
         })
     }
+    fn palette_bits(palette_size: usize) -> i32 {
+        let adjusted = if palette_size == 0 {
+            u32::MAX
+        } else {
+            palette_size.saturating_sub(1) as u32
+        };
+        let leading_zeros = adjusted.leading_zeros();
+        let bits_unclamped = 32u32.saturating_sub(leading_zeros);
+        (bits_unclamped as f64).max(2.0) as i32
+    }
+
     pub fn get_block_id_list(&self) -> Vec<i32> {
         let total_blocks = (self.length * self.width * self.height) as usize;
+        let layer = (self.width * self.length) as usize;
 
-        let atomic_block_list: Vec<AtomicI32> =
-            (0..total_blocks).map(|_| AtomicI32::new(0)).collect();
-        let atomic_block_list = Arc::new(atomic_block_list);
-
-        self.blocks.par_iter().for_each(|block| {
-            let dx = block.pos.x - self.start_pos.x;
-            let dy = block.pos.y - self.start_pos.y;
-            let dz = block.pos.z - self.start_pos.z;
-
-            let id = (dy * self.width * self.length) + (dz * self.width) + dx;
-
-            if id >= 0 && (id as usize) < atomic_block_list.len() {
-                let state_id = self
-                    .block_state_to_index
-                    .get(&block.block)
-                    .map(|v| *v as i32)
-                    .unwrap_or(0);
-
-                atomic_block_list[id as usize].store(state_id, Ordering::Relaxed);
-            }
-        });
-
-        Arc::try_unwrap(atomic_block_list)
-            .unwrap()
-            .into_iter()
-            .map(|atomic| atomic.into_inner())
+        (0..total_blocks)
+            .into_par_iter()
+            .map(|id| {
+                let pos = BlockPos {
+                    x: self.start_pos.x + (id % self.width as usize) as i32,
+                    y: self.start_pos.y + (id / layer) as i32,
+                    z: self.start_pos.z + ((id % layer) / self.width as usize) as i32,
+                };
+                self.blocks.get_index(pos) as i32
+            })
             .collect()
     }
     pub fn encode_block_states(&self) -> Vec<u64> {
-        let state_ids = self.get_block_id_list();
-        let bits = self.bits as usize;
+        Self::pack_states(&self.get_block_id_list(), self.bits)
+    }
+
+    fn pack_states(state_ids: &[i32], bits: i32) -> Vec<u64> {
+        let bits = bits as usize;
         let total_bits = state_ids.len() * bits;
         let longs_needed = (total_bits + 63) / 64;
 
@@ -242,9 +152,13 @@ impl ToLmSchematic {
             .collect()
     }
     pub fn lm_palette(&self) -> Value {
+        Self::palette_value(self.unique_block_states.iter())
+    }
+
+    fn palette_value<'b>(blocks: impl Iterator<Item = &'b Arc<BlockData>>) -> Value {
         let mut palette = Vec::new();
 
-        for block in &self.unique_block_states {
+        for block in blocks {
             let mut compound = HashMap::new();
             compound.insert("Name".to_string(), Value::String(block.id.name.to_string()));
 
@@ -261,20 +175,48 @@ impl ToLmSchematic {
 
         Value::List(palette)
     }
+    pub fn lm_tile_entities(&self) -> Value {
+        let list = self
+            .tile_entities
+            .elements
+            .iter()
+            .map(|te| {
+                let pos = te.relative_to(&self.start_pos);
+                with_keys(
+                    &te.nbt,
+                    vec![
+                        ("x", Value::Int(pos.x)),
+                        ("y", Value::Int(pos.y)),
+                        ("z", Value::Int(pos.z)),
+                    ],
+                )
+            })
+            .collect();
+        Value::List(list)
+    }
+    pub fn lm_entities(&self) -> Value {
+        let list = self
+            .entities
+            .elements
+            .iter()
+            .map(|e| with_keys(&e.nbt, vec![("Pos", pos_value(e.relative_to(&self.start_pos)))]))
+            .collect();
+        Value::List(list)
+    }
     pub fn lm_metadata(&self) -> Value {
         let mut metadata = HashMap::new();
         let timestamp_sec = Utc::now().timestamp();
-        let mut enclosing_size = HashMap::new();
-        enclosing_size.insert("x".to_string(), Value::Int(self.width));
-        enclosing_size.insert("y".to_string(), Value::Int(self.height));
-        enclosing_size.insert("z".to_string(), Value::Int(self.length));
-        metadata.insert("EnclosingSize".to_string(), Compound(enclosing_size));
+        let enclosing = self.enclosing_size();
+        metadata.insert("EnclosingSize".to_string(), Self::pos_value(enclosing));
 
         metadata.insert(
             "Description".to_string(),
-            Value::String("来自蓝图站www.mcschematic.top自动转换,不保留实体".to_string()),
+            Value::String("来自蓝图站www.mcschematic.top自动转换".to_string()),
+        );
+        metadata.insert(
+            "RegionCount".to_string(),
+            Value::Int(self.regions.len().max(1) as i32),
         );
-        metadata.insert("RegionCount".to_string(), Value::Int(1));
         metadata.insert("Name".to_string(), Value::String("null".to_string()));
         metadata.insert(
             "Author".to_string(),
@@ -287,7 +229,142 @@ impl ToLmSchematic {
 
         Compound(metadata)
     }
+    fn pos_value(pos: BlockPos) -> Value {
+        let mut compound = HashMap::new();
+        compound.insert("x".to_string(), Value::Int(pos.x));
+        compound.insert("y".to_string(), Value::Int(pos.y));
+        compound.insert("z".to_string(), Value::Int(pos.z));
+        Compound(compound)
+    }
+
+    fn enclosing_size(&self) -> BlockPos {
+        let mut boxes = self.regions.iter().map(|r| (r.min(), r.max()));
+        let Some(first) = boxes.next() else {
+            return BlockPos {
+                x: self.width,
+                y: self.height,
+                z: self.length,
+            };
+        };
+        let (min, max) = boxes.fold(first, |(min, max), (r_min, r_max)| {
+            (
+                BlockPos {
+                    x: min.x.min(r_min.x),
+                    y: min.y.min(r_min.y),
+                    z: min.z.min(r_min.z),
+                },
+                BlockPos {
+                    x: max.x.max(r_max.x),
+                    y: max.y.max(r_max.y),
+                    z: max.z.max(r_max.z),
+                },
+            )
+        });
+        BlockPos {
+            x: max.x - min.x + 1,
+            y: max.y - min.y + 1,
+            z: max.z - min.z + 1,
+        }
+    }
+
+    // 实体归属第一个包含它的选区, 都不包含时归入第一个选区
+    fn entity_region(&self, pos: &BlockPos) -> usize {
+        self.regions
+            .iter()
+            .position(|r| r.contains(pos))
+            .unwrap_or(0)
+    }
+
+    // 按原选区写出: 方块与方块实体相对于选区最小角, 实体相对于选区原点
+    fn lm_region(&self, index: usize, region: &Region) -> Value {
+        let min = region.min();
+        let size = region.abs_size();
+        let layer = (size.x * size.z) as usize;
+        let total = layer * size.y as usize;
+        let global_ids: Vec<u32> = (0..total)
+            .into_par_iter()
+            .map(|id| {
+                self.blocks.get_index(BlockPos {
+                    x: min.x + (id % size.x as usize) as i32,
+                    y: min.y + (id / layer) as i32,
+                    z: min.z + ((id % layer) / size.x as usize) as i32,
+                })
+            })
+            .collect();
+
+        // 每个选区只写出自己用到的方块状态, 空气固定为 0
+        let mut local_palette = vec![0u32];
+        let mut local_index: HashMap<u32, i32> = HashMap::from([(0, 0)]);
+        let state_ids: Vec<i32> = global_ids
+            .iter()
+            .map(|id| {
+                *local_index.entry(*id).or_insert_with(|| {
+                    local_palette.push(*id);
+                    (local_palette.len() - 1) as i32
+                })
+            })
+            .collect();
+        let bits = Self::palette_bits(local_palette.len());
+        let long_array: Vec<i64> = Self::pack_states(&state_ids, bits)
+            .iter()
+            .map(|&v| v as i64)
+            .collect();
+
+        let tile_entities = self
+            .tile_entities
+            .elements
+            .iter()
+            .filter(|te| region.contains(&te.pos))
+            .map(|te| {
+                let pos = te.relative_to(&min);
+                with_keys(
+                    &te.nbt,
+                    vec![
+                        ("x", Value::Int(pos.x)),
+                        ("y", Value::Int(pos.y)),
+                        ("z", Value::Int(pos.z)),
+                    ],
+                )
+            })
+            .collect();
+        let entities = self
+            .entities
+            .elements
+            .iter()
+            .filter(|e| self.entity_region(&e.block_pos()) == index)
+            .map(|e| with_keys(&e.nbt, vec![("Pos", pos_value(e.relative_to(&region.position)))]))
+            .collect();
+
+        let mut compound: HashMap<String, Value> = HashMap::new();
+        compound.insert(
+            "BlockStates".to_string(),
+            Value::LongArray(fastnbt::LongArray::new(long_array)),
+        );
+        compound.insert("Position".to_string(), Self::pos_value(region.position));
+        compound.insert("Size".to_string(), Self::pos_value(region.size));
+        compound.insert(
+            "BlockStatePalette".to_string(),
+            Self::palette_value(
+                local_palette
+                    .iter()
+                    .map(|id| &self.unique_block_states[*id as usize]),
+            ),
+        );
+        compound.insert("TileEntities".to_string(), Value::List(tile_entities));
+        compound.insert("Entities".to_string(), Value::List(entities));
+        Compound(compound)
+    }
+
     pub fn lm_regions(&self) -> Value {
+        if !self.regions.is_empty() {
+            let regions = self
+                .regions
+                .iter()
+                .enumerate()
+                .map(|(index, region)| (region.name.clone(), self.lm_region(index, region)))
+                .collect();
+            return Compound(regions);
+        }
         let mut regions = HashMap::new();
         let mut region: HashMap<String, Value> = HashMap::new();
 
@@ -311,13 +388,14 @@ impl ToLmSchematic {
         region.insert("Size".to_string(), Compound(size));
 
         region.insert("BlockStatePalette".to_string(), self.lm_palette());
-        region.insert("TileEntities".to_string(), Value::List(vec![]));
+        region.insert("TileEntities".to_string(), self.lm_tile_entities());
+        region.insert("Entities".to_string(), self.lm_entities());
         regions.insert("null".to_string(), Compound(region));
         Compound(regions)
     }
-    pub fn lm_schematic(&self, version: i32) -> Value {
+    pub fn lm_schematic(&self, version: i32, data_version: i32) -> Value {
         let mut nbt = HashMap::new();
-        nbt.insert("MinecraftDataVersion".to_string(), Value::Int(3465));
+        nbt.insert("MinecraftDataVersion".to_string(), Value::Int(data_version));
         nbt.insert("Version".to_string(), Value::Int(version));
         let metadata = self.lm_metadata();
         nbt.insert("Metadata".to_string(), metadata);
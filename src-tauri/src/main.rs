@@ -121,7 +121,7 @@ fn lm_schematic_write() -> Result<(), SchematicError> {
     let schem3 = schematic3.get_blocks_pos()?;
 
     let bg = ToLmSchematic::new(&schem3)?;
-    let data = bg.lm_schematic(6);
+    let data = bg.lm_schematic(6, 3465);
     let output_path = "./schematic/out2.litematic";
     to_writer_gzip(&data, output_path)?;
 
@@ -150,7 +150,7 @@ fn lm_big_schematic_write() -> Result<(), SchematicError> {
     let schem3 = schematic3.get_blocks_pos()?;
 
     let bg = ToCreateSchematic::new(&schem3)?;
-    let data = bg.create_schematic(false);
+    let data = bg.create_schematic(false, 3465);
     let output_path = "./schematic/out.nbt";
     to_writer_gzip(&data, output_path)?;
 
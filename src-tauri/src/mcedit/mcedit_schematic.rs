@@ -0,0 +1,202 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::entities::{read_pos, EntitiesList};
+use crate::utils::extend_value::NbtExt;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
+use crate::utils::tile_entities::{strip_keys, TileEntitiesList};
+use fastnbt::Value;
+use fastnbt::Value::Compound;
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::sync::Arc;
+
+// 1.13 之前 MCEdit / Schematica 的 .schematic, 方块以数字 ID + meta 存储
+#[derive(Debug)]
+pub struct McEditSchematic {
+    nbt: Value,
+}
+
+impl McEditSchematic {
+    pub fn new_from_bytes(data: Vec<u8>) -> Result<Self, SchematicError> {
+        let cursor = Cursor::new(data);
+        let mut decoder = GzDecoder::new(cursor);
+
+        let nbt: Value = fastnbt::from_reader(&mut decoder)?;
+
+        if let Compound(_) = &nbt {
+            Ok(Self { nbt })
+        } else {
+            Err(SchematicError::InvalidFormat("Root is not a Compound"))
+        }
+    }
+
+    pub fn is_legacy(root: &HashMap<String, Value>) -> bool {
+        root.contains_key("Blocks") && root.contains_key("Data")
+    }
+
+    pub fn get_root(&self) -> Result<&HashMap<String, Value>, SchematicError> {
+        let Compound(root) = &self.nbt else {
+            return Err(SchematicError::InvalidFormat("Root is not a Compound"));
+        };
+        Ok(root)
+    }
+
+    pub fn get_size(&self) -> Result<Size, SchematicError> {
+        let root = self.get_root()?;
+        Ok(Size {
+            width: root.get_i16("Width")? as i32,
+            height: root.get_i16("Height")? as i32,
+            length: root.get_i16("Length")? as i32,
+        })
+    }
+
+    // 完整 ID = AddBlocks 半字节 << 8 | Blocks, 与 WorldEdit 的读取方式一致
+    pub fn get_block_ids(&self) -> Result<Vec<u16>, SchematicError> {
+        let root = self.get_root()?;
+        let blocks = root.get_i8_array("Blocks")?;
+        let add_blocks = root.get_i8_array("AddBlocks").ok();
+        let ids = blocks
+            .iter()
+            .enumerate()
+            .map(|(index, &id)| {
+                let base = id as u8 as u16;
+                let add = add_blocks
+                    .and_then(|add| add.get(index >> 1))
+                    .map(|&add| {
+                        let add = add as u8 as u16;
+                        if index & 1 == 0 {
+                            (add & 0x0f) << 8
+                        } else {
+                            (add & 0xf0) << 4
+                        }
+                    })
+                    .unwrap_or(0);
+                add | base
+            })
+            .collect();
+        Ok(ids)
+    }
+
+    // "daylight_detector[inverted=true]" 这类带状态的名称拆成方块数据
+    fn parse_block_name(name: &str) -> BlockData {
+        let (id, props) = match name.split_once('[') {
+            Some((id, rest)) => (id, rest.trim_end_matches(']')),
+            None => (name, ""),
+        };
+        let id = if id.contains(':') {
+            id.to_string()
+        } else {
+            format!("minecraft:{}", id)
+        };
+        let properties = props
+            .split(',')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(k, v)| (Arc::<str>::from(k.trim()), Arc::<str>::from(v.trim())))
+            .collect();
+        BlockData {
+            id: BlockId {
+                name: Arc::from(id),
+            },
+            properties,
+        }
+    }
+
+    pub fn get_tile_entities(&self) -> TileEntitiesList {
+        let mut tile_entities = TileEntitiesList::default();
+        let Ok(root) = self.get_root() else {
+            return tile_entities;
+        };
+        let Ok(list) = root.get_list("TileEntities") else {
+            return tile_entities;
+        };
+        for entity in list {
+            let Compound(nbt) = entity else {
+                continue;
+            };
+            let (Ok(x), Ok(y), Ok(z)) = (nbt.get_i32("x"), nbt.get_i32("y"), nbt.get_i32("z"))
+            else {
+                continue;
+            };
+            tile_entities.add(BlockPos { x, y, z }, strip_keys(entity, &["x", "y", "z"]));
+        }
+        tile_entities
+    }
+
+    pub fn get_entities(&self) -> EntitiesList {
+        let mut entities = EntitiesList::default();
+        let Ok(root) = self.get_root() else {
+            return entities;
+        };
+        let Ok(list) = root.get_list("Entities") else {
+            return entities;
+        };
+        for entity in list {
+            let Compound(nbt) = entity else {
+                continue;
+            };
+            let Some(pos) = read_pos(nbt.get("Pos")) else {
+                continue;
+            };
+            entities.add_read(pos, BlockPos::default(), strip_keys(entity, &["Pos"]));
+        }
+        entities
+    }
+
+    // 返回方块数据以及未能映射的 "id:meta" 及其数量, 未映射的方块按空气处理
+    pub fn get_blocks_pos(
+        &self,
+        je_blocks: &BlocksData,
+    ) -> Result<(SchematicData, BTreeMap<String, i64>), SchematicError> {
+        let root = self.get_root()?;
+        let size = self.get_size()?;
+        let ids = self.get_block_ids()?;
+        let data = root.get_i8_array("Data")?;
+        let volume = (size.width * size.height * size.length) as usize;
+        if ids.len() < volume || data.len() < volume {
+            return Err(SchematicError::InvalidFormat(
+                "Blocks/Data shorter than volume",
+            ));
+        }
+
+        let mut palette: Vec<Arc<BlockData>> = Vec::new();
+        let mut palette_index: HashMap<u32, usize> = HashMap::new();
+        let mut unmapped: BTreeMap<String, i64> = BTreeMap::new();
+        let mut cells = Vec::with_capacity(volume);
+        for (&id, &meta) in ids.iter().zip(data.iter()).take(volume) {
+            let meta = meta as u8 & 0x0f;
+            let key = (id as u32) << 4 | meta as u32;
+            let index = *palette_index.entry(key).or_insert_with(|| {
+                match je_blocks.get_legacy_name(id, meta) {
+                    Some(name) => {
+                        palette.push(Arc::new(Self::parse_block_name(name)));
+                        palette.len() - 1
+                    }
+                    None => usize::MAX,
+                }
+            });
+            if index == usize::MAX {
+                *unmapped.entry(format!("{}:{}", id, meta)).or_insert(0) += 1;
+            }
+            cells.push(index);
+        }
+
+        let (width, length) = (size.width as usize, size.length as usize);
+        let mut blocks = BlockVolume::default();
+        blocks.fill_region(
+            BlockPos { x: 0, y: 0, z: 0 },
+            BlockPos {
+                x: size.width,
+                y: size.height,
+                z: size.length,
+            },
+            &palette,
+            |x, y, z| cells[(y as usize * length + z as usize) * width + x as usize],
+        );
+
+        let data = SchematicData::new(blocks, self.get_tile_entities(), size)
+            .with_entities(self.get_entities());
+        Ok((data, unmapped))
+    }
+}
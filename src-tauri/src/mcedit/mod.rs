@@ -0,0 +1 @@
+pub mod mcedit_schematic;
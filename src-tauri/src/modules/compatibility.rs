@@ -0,0 +1,75 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematic_data_api::list_unique_blocks;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::modules::modules_data::compatibility_data::{CompatibilityChecker, CompatibilityReport};
+use crate::schematic_format::ReadOptions;
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::versions_data::{TargetVersion, VersionData};
+use tauri::State;
+
+fn resolve_target(
+    versions_data: &VersionData,
+    game_version: &str,
+) -> anyhow::Result<TargetVersion> {
+    versions_data
+        .resolve(game_version)
+        .ok_or(anyhow::anyhow!("unknow game version: {}", game_version))
+}
+
+#[tauri::command]
+pub async fn check_schematic_compatibility(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    be_blocks: State<'_, BeBlocksData>,
+    versions_data: State<'_, VersionData>,
+    id: i64,
+    game_version: String,
+) -> anyhow::Result<CompatibilityReport, String> {
+    async move {
+        let target = resolve_target(&versions_data, &game_version)?;
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let version = schematic.version;
+        let sub_version = schematic.sub_type;
+        let v_type = schematic.schematic_type;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let data =
+            file_manager.get_schematic_data(id, version, sub_version, v_type, &read_options)?;
+        let checker = CompatibilityChecker::new(&je_blocks, &target);
+        Ok(checker.check_volume(id, schematic.name, &data.blocks))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+// 基于数据库中的 unique_blocks 检查整个蓝图库, 只返回存在问题或方块列表无法解析的蓝图
+#[tauri::command]
+pub async fn check_library_compatibility(
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    versions_data: State<'_, VersionData>,
+    game_version: String,
+) -> anyhow::Result<Vec<CompatibilityReport>, String> {
+    async move {
+        let target = resolve_target(&versions_data, &game_version)?;
+        let mut conn = db.0.get()?;
+        let checker = CompatibilityChecker::new(&je_blocks, &target);
+        let reports = list_unique_blocks(&mut conn)?
+            .into_iter()
+            .map(|(id, name, unique_blocks)| {
+                checker
+                    .check_unique_blocks(id, name.clone(), &unique_blocks)
+                    .unwrap_or_else(|e| checker.failed_report(id, name, e.to_string()))
+            })
+            .filter(|report| !report.issues.is_empty() || report.error.is_some())
+            .collect();
+        Ok(reports)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
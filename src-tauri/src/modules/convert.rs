@@ -1,13 +1,13 @@
-use crate::building_gadges::to_bg_schematic::ToBgSchematic;
-use crate::create::to_create_schematic::ToCreateSchematic;
 use crate::data_files::files::FileManager;
 use crate::database::db_apis::schematics_api::find_schematic;
 use crate::database::db_control::DatabaseState;
-use crate::litematica::to_lm_schematic::ToLmSchematic;
-use crate::modules::modules_data::convert_data::ConvertData;
+use crate::modules::modules_data::convert_data::{ConvertData, ConvertReport, SchematicType};
+use crate::schematic_format::{format_by_code, ReadOptions, WriteOptions};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
 use crate::utils::minecraft_data::je_blocks_data::{BlocksData, SubData};
 use crate::utils::minecraft_data::map_art_data::{BlockColorData, MapArtsData};
-use crate::word_edit::to_we_schematic::ToWeSchematic;
+use crate::utils::minecraft_data::versions_data::VersionData;
+use crate::utils::schematic_data::Region;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use tauri::State;
@@ -31,6 +31,31 @@ pub async fn get_schematic_convert_data(
     .map_err(|e: anyhow::Error| e.to_string())
 }
 
+#[tauri::command]
+pub async fn get_schematic_regions(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    be_blocks: State<'_, BeBlocksData>,
+    je_blocks: State<'_, BlocksData>,
+    id: i64,
+) -> anyhow::Result<Vec<Region>, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let version = schematic.version;
+        let sub_version = schematic.sub_type;
+        let v_type = schematic.schematic_type;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let data =
+            file_manager.get_schematic_data(id, version, sub_version, v_type, &read_options)?;
+        Ok(data.regions)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_je_blocks(
     je_blocks: State<'_, BlocksData>,
@@ -59,56 +84,88 @@ pub async fn get_map_arts(
 pub async fn convert(
     db: State<'_, DatabaseState>,
     file_manager: State<'_, FileManager>,
+    be_blocks: State<'_, BeBlocksData>,
+    je_blocks: State<'_, BlocksData>,
+    versions_data: State<'_, VersionData>,
     id: i64,
     schematic_type: i64,
     lm_version: i64,
     we_version: i64,
     bg_version: i64,
     vi_air: bool,
-) -> anyhow::Result<bool, String> {
+    region: Option<String>,
+    game_version: Option<String>,
+    export_path: Option<String>,
+) -> anyhow::Result<ConvertReport, String> {
     async move {
+        let mut report = ConvertReport::default();
         let mut conn = db.0.get()?;
         let schematic = find_schematic(&mut conn, id)?;
         let version = schematic.version;
         let sub_version = schematic.sub_type;
         let v_type = schematic.schematic_type;
-        let data = file_manager.get_schematic_data(id, version, sub_version, v_type)?;
-        match schematic_type {
-            1 => {
-                let data = ToCreateSchematic::new(&data)?.create_schematic(vi_air);
-                file_manager.save_nbt_value(id, data, version, -1, schematic_type as i32, true)?;
-            }
-            2 => {
-                let data = ToLmSchematic::new(&data)?.lm_schematic(lm_version as i32);
-                file_manager.save_nbt_value(id, data, version, -1, schematic_type as i32, true)?;
-            }
-            3 => {
-                let data = ToWeSchematic::new(&data)?.we_schematic(we_version as i32)?;
-                file_manager.save_nbt_value(
-                    id,
-                    data,
-                    version,
-                    we_version as i32,
-                    schematic_type as i32,
-                    true,
-                )?;
-            }
-            4 => {
-                let data = ToBgSchematic::new(&data)?.bg_schematic(bg_version as i32)?;
-                file_manager.save_json_value(
-                    id,
-                    data,
-                    version,
-                    bg_version as i32,
-                    schematic_type as i32,
-                )?;
-            }
-            //5 => {}
-            _ => {
-                anyhow::bail!("unknown schematic type: {}", schematic_type);
-            }
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let mut data =
+            file_manager.get_schematic_data(id, version, sub_version, v_type, &read_options)?;
+        if let Some(name) = &region {
+            data = data.region(name)?;
+        }
+        let format = format_by_code(schematic_type as i32)?;
+        let sub_version = match format.schematic_type() {
+            SchematicType::We => we_version as i32,
+            SchematicType::Bg => bg_version as i32,
+            _ => -1,
+        };
+        let target = game_version
+            .as_deref()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                versions_data
+                    .resolve(v)
+                    .ok_or(anyhow::anyhow!("unknow game version: {}", v))
+            })
+            .transpose()?;
+        // 指定目标版本时先迁移方块名称与属性
+        if let Some(target) = &target {
+            // 游戏版本为空或为 "1.21.5+" 这类推断出的名称时按 DataVersion 取源版本
+            let from = versions_data
+                .resolve(&schematic.game_version)
+                .map(|source| source.release)
+                .or_else(|| {
+                    data.data_version
+                        .and_then(|data_version| versions_data.release_of(data_version))
+                });
+            report.substitutions = data.migrate_version(from, target.release, &je_blocks);
+        }
+        let mut options = WriteOptions::new(sub_version)
+            .with_lm_version(lm_version as i32)
+            .with_air(vi_air)
+            .with_be_blocks(&be_blocks);
+        if let Some(target) = &target {
+            options = options.with_target(target, &je_blocks);
+        }
+        let encoded = format.write(&data, &options)?;
+        report.unmapped_blocks = encoded.unmapped_blocks.clone();
+        report.warnings = encoded.warnings.clone();
+        // 区域转换结果与整体转换共用缓存文件名, 因此直接导出而不缓存
+        if let Some(name) = &region {
+            let export_path =
+                export_path.ok_or(anyhow::anyhow!("region export needs a target path"))?;
+            let stem: String = format!("{}_{}", schematic.name, name)
+                .chars()
+                .map(|c| if r#"\/:*?"<>|"#.contains(c) { '_' } else { c })
+                .collect();
+            let filename = format!("{}.{}", stem, format.extension());
+            let path =
+                file_manager.export_encoded(export_path, &filename, &encoded, format.type_id())?;
+            report.path = Some(path.to_string_lossy().to_string());
+        } else {
+            file_manager.save_encoded(id, encoded, version, schematic_type as i32)?;
         }
-        Ok(true)
+        report.success = true;
+        Ok(report)
     }
     .await
     .map_err(|e: anyhow::Error| e.to_string())
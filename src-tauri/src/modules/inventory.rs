@@ -0,0 +1,59 @@
+use crate::database::db_apis::inventory_api::{find_inventory, save_inventory};
+use crate::database::db_apis::schematic_data_api::find_requirements;
+use crate::database::db_control::DatabaseState;
+use crate::utils::inventory::{
+    compare_inventory, inventory_from_str, inventory_to_string, read_inventory, InventoryComparison,
+};
+use crate::utils::requirements::Requirements;
+use r2d2::PooledConnection;
+use r2d2_sqlite::SqliteConnectionManager;
+use std::path::Path;
+use tauri::State;
+
+// 每次读取时与最新的材料清单重新比较
+fn load_comparison(
+    conn: &PooledConnection<SqliteConnectionManager>,
+    id: i64,
+) -> anyhow::Result<Option<InventoryComparison>> {
+    let Some((source, inventory, updated_at)) = find_inventory(conn, id)? else {
+        return Ok(None);
+    };
+    let need = Requirements::from_requirement_str(&find_requirements(conn, id)?)?;
+    let have = inventory_from_str(&inventory)?;
+    Ok(Some(InventoryComparison {
+        schematic_id: id,
+        source,
+        updated_at,
+        entries: compare_inventory(&need, &have),
+    }))
+}
+
+// 导入背包 (玩家 .dat / 容器 NBT / CSV) 并与蓝图材料比较, 结果随蓝图保存
+#[tauri::command]
+pub async fn compare_schematic_inventory(
+    db: State<'_, DatabaseState>,
+    id: i64,
+    path: String,
+) -> anyhow::Result<InventoryComparison, String> {
+    async move {
+        let inventory = read_inventory(Path::new(&path))?;
+        let mut conn = db.0.get()?;
+        save_inventory(&mut conn, id, &path, &inventory_to_string(&inventory)?)?;
+        load_comparison(&conn, id)?.ok_or(anyhow::anyhow!("inventory not saved"))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_schematic_inventory(
+    db: State<'_, DatabaseState>,
+    id: i64,
+) -> anyhow::Result<Option<InventoryComparison>, String> {
+    async move {
+        let conn = db.0.get()?;
+        load_comparison(&conn, id)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,49 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::modules::modules_data::layer_requirements_data::{LayerMatrix, LayerMode, Partition};
+use crate::schematic_format::ReadOptions;
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::get_requirements_by;
+use tauri::State;
+
+// 按层/区段统计材料, 返回 层 × 方块 的矩阵
+#[tauri::command]
+pub async fn get_layer_requirements(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    be_blocks: State<'_, BeBlocksData>,
+    id: i64,
+    mode: LayerMode,
+    region: Option<String>,
+    locale: Option<String>,
+) -> anyhow::Result<LayerMatrix, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let version = schematic.version;
+        let sub_version = schematic.sub_type;
+        let v_type = schematic.schematic_type;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let mut data =
+            file_manager.get_schematic_data(id, version, sub_version, v_type, &read_options)?;
+        if let Some(name) = &region {
+            data = data.region(name)?;
+        }
+        let partition = Partition::new(&mode, data.blocks.bounds(), &data.size)?;
+        let groups =
+            get_requirements_by(&data.blocks, partition.count, |pos| partition.group_of(pos))?;
+        Ok(LayerMatrix::new(
+            &partition,
+            &groups,
+            &je_blocks,
+            locale.as_deref(),
+        ))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -1,5 +1,4 @@
-use crate::building_gadges::to_bg_schematic::ToBgSchematic;
-use crate::create::to_create_schematic::ToCreateSchematic;
+use crate::data_files::config::get_config_dir;
 use crate::data_files::files::FileManager;
 use crate::database::db_apis::history_api::new_history;
 use crate::database::db_apis::schematic_data_api::new_schematic_data;
@@ -7,232 +6,153 @@ use crate::database::db_apis::schematics_api::new_schematic;
 use crate::database::db_apis::user_api::add_user_schematic;
 use crate::database::db_control::DatabaseState;
 use crate::database::db_data::Schematic;
-use crate::litematica::to_lm_schematic::ToLmSchematic;
 use crate::modules::modules_data::convert_data::get_unique_block_str;
+use crate::schematic_format::{format_by_code, WriteOptions};
 use crate::utils::block_state_pos_list::{BlockStatePos, BlockStatePosList};
+use crate::utils::map_art_image::{self, MapArtOptions, MapArtResult, MapArtSupport};
+use crate::utils::map_art_profile::{
+    find_profile, load_profiles, remove_profile, upsert_profile, PaletteProfile,
+};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::minecraft_data::versions_data::VersionData;
 use crate::utils::requirements::{get_requirements, RequirementStr};
 use crate::utils::schematic_data::{SchematicData, Size};
 use crate::utils::tile_entities::TileEntitiesList;
-use crate::word_edit::to_we_schematic::ToWeSchematic;
 use chrono::Local;
 use rusqlite::version;
-use std::collections::VecDeque;
-use tauri::State;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use tauri::{AppHandle, State};
 
 #[tauri::command]
 pub async fn create_map_art(
-    blocks: Vec<BlockStatePos>,
+    mut blocks: Vec<BlockStatePos>,
     file_name: String,
-    size: Size,
+    mut size: Size,
     schematic_type: i64,
     sub_version: i64,
+    support: Option<MapArtSupport>,
     je_blocks: State<'_, BlocksData>,
+    be_blocks: State<'_, BeBlocksData>,
     db: State<'_, DatabaseState>,
     file_manager: State<'_, FileManager>,
 ) -> Result<bool, String> {
     async move {
         let now = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let mut conn = db.0.get()?;
+        // 支撑方块与参考行在这里添加, 数量以实际添加的为准, 单独列为 support 分类
+        let added = match &support {
+            Some(support) => {
+                let added = support.apply(&mut blocks)?;
+                size = map_art_image::block_bounds(&blocks);
+                added
+            }
+            None => BTreeMap::new(),
+        };
         let block_data = BlockStatePosList {
             elements: VecDeque::from(blocks),
         };
-        let data = SchematicData::new(block_data, TileEntitiesList::default(), size);
-        match schematic_type {
-            1 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToCreateSchematic::new(&data)?.create_schematic(true);
-                let mut schematic = Schematic {
-                    id: 0,
-                    name: format!("map_art_{}", file_name),
-                    description: "".parse()?,
-                    schematic_type: 1,
-                    sub_type: -1,
-                    is_deleted: false,
-                    sizes: size.to_string(),
-                    user: "your".parse()?,
-                    is_upload: false,
-                    version: 0,
-                    version_list: "0".parse()?,
-                    created_at: "".parse()?,
-                    updated_at: now.clone(),
-                    game_version: "".parse()?,
-                };
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(
-                    new_id,
-                    data,
-                    0,
-                    sub_version as i32,
-                    schematic_type as i32,
-                    true,
-                )?;
-            }
-            2 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToLmSchematic::new(&data)?.lm_schematic(6);
-                let mut schematic = Schematic {
-                    id: 0,
-                    name: format!("map_art_{}", file_name),
-                    description: "".parse()?,
-                    schematic_type: 2,
-                    sub_type: -1,
-                    is_deleted: false,
-                    sizes: size.to_string(),
-                    user: "your".parse()?,
-                    is_upload: false,
-                    version: 0,
-                    version_list: "0".parse()?,
-                    created_at: "".parse()?,
-                    updated_at: now.clone(),
-                    game_version: "".parse()?,
-                };
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(
-                    new_id,
-                    data,
-                    0,
-                    sub_version as i32,
-                    schematic_type as i32,
-                    true,
-                )?;
-            }
-            3 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToWeSchematic::new(&data)?.we_schematic(sub_version as i32)?;
-                let mut schematic = Schematic {
-                    id: 0,
-                    name: format!("map_art_{}", file_name),
-                    description: "".parse()?,
-                    schematic_type: 3,
-                    sub_type: sub_version as i32,
-                    is_deleted: false,
-                    sizes: size.to_string(),
-                    user: "your".parse()?,
-                    is_upload: false,
-                    version: 0,
-                    version_list: "0".parse()?,
-                    created_at: "".parse()?,
-                    updated_at: now.clone(),
-                    game_version: "".parse()?,
-                };
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(
-                    new_id,
-                    data,
-                    0,
-                    sub_version as i32,
-                    schematic_type as i32,
-                    true,
-                )?;
-            }
-            4 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToBgSchematic::new(&data)?.bg_schematic(sub_version as i32)?;
-                let mut schematic = Schematic {
-                    id: 0,
-                    name: format!("map_art_{}", file_name),
-                    description: "".parse()?,
-                    schematic_type: 4,
-                    sub_type: sub_version as i32,
-                    is_deleted: false,
-                    sizes: size.to_string(),
-                    user: "your".parse()?,
-                    is_upload: false,
-                    version: 0,
-                    version_list: "0".parse()?,
-                    created_at: "".parse()?,
-                    updated_at: now.clone(),
-                    game_version: "".parse()?,
-                };
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_json_value(
-                    new_id,
-                    data,
-                    0,
-                    sub_version as i32,
-                    schematic_type as i32,
-                )?;
-            }
-            //5 => {}
-            _ => {
-                anyhow::bail!("unknown schematic type: {}", schematic_type);
-            }
-        }
+        let data = SchematicData::new(block_data.into(), TileEntitiesList::default(), size);
+        let format = format_by_code(schematic_type as i32)?;
+        let requirement = get_requirements(&data.blocks)?.with_support(&added);
+        let requirements_str =
+            RequirementStr::from_requirements(&requirement, &je_blocks).export_to_string()?;
+        let unique_blocks = get_unique_block_str(&data.blocks)?;
+        let options = WriteOptions::new(sub_version as i32).with_be_blocks(&be_blocks);
+        let encoded = format.write(&data, &options)?;
+        let schematic = Schematic {
+            id: 0,
+            name: format!("map_art_{}", file_name),
+            description: "".parse()?,
+            schematic_type: format.type_id(),
+            sub_type: encoded.sub_version,
+            is_deleted: false,
+            sizes: size.to_string(),
+            user: "your".parse()?,
+            is_upload: false,
+            version: 0,
+            version_list: "0".parse()?,
+            created_at: "".parse()?,
+            updated_at: now.clone(),
+            game_version: "".parse()?,
+        };
+        let new_id = new_schematic(&mut conn, schematic.clone())?;
+        new_schematic_data(
+            &mut conn,
+            new_id,
+            requirements_str.clone(),
+            unique_blocks.clone(),
+        )?;
+        add_user_schematic(&mut conn, 1)?;
+        let schematic_str = serde_json::to_string(&schematic)?;
+        new_history(
+            &mut conn,
+            new_id,
+            schematic_str,
+            requirements_str,
+            unique_blocks,
+        )?;
+        file_manager.save_encoded(new_id, encoded, 0, format.type_id())?;
         Ok(true)
     }
     .await
     .map_err(|e: anyhow::Error| e.to_string())
 }
+
+// 读取 PNG/JPEG 并量化为地图画方块 (平面或阶梯), 返回方块布局与预览图
+// profile 为调色板配置名, 省略时使用全部方块
+#[tauri::command]
+pub async fn generate_map_art(
+    app: AppHandle,
+    map_arts: State<'_, MapArtsData>,
+    je_blocks: State<'_, BlocksData>,
+    versions_data: State<'_, VersionData>,
+    path: String,
+    options: MapArtOptions,
+    profile: Option<String>,
+) -> Result<MapArtResult, String> {
+    async move {
+        let selection = match &profile {
+            Some(name) => find_profile(&get_config_dir(&app)?, name)?.select(
+                &map_arts,
+                &je_blocks,
+                &versions_data,
+            )?,
+            None => map_art_image::select_all(&map_arts),
+        };
+        let bytes = fs::read(&path)?;
+        map_art_image::generate_map_art(&bytes, &map_arts, &selection, &options)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn list_map_art_profiles(app: AppHandle) -> Result<Vec<PaletteProfile>, String> {
+    async move { load_profiles(&get_config_dir(&app)?) }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
+
+// 新增或按名称覆盖, 返回保存后的配置列表
+#[tauri::command]
+pub async fn save_map_art_profile(
+    app: AppHandle,
+    profile: PaletteProfile,
+) -> Result<Vec<PaletteProfile>, String> {
+    async move { upsert_profile(&get_config_dir(&app)?, profile) }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_map_art_profile(
+    app: AppHandle,
+    name: String,
+) -> Result<Vec<PaletteProfile>, String> {
+    async move { remove_profile(&get_config_dir(&app)?, &name) }
+        .await
+        .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,62 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::schematic_data_api::find_requirements;
+use crate::database::db_apis::schematics_api::find_schematic;
+use crate::database::db_control::DatabaseState;
+use crate::utils::material_export::ExportFormat;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::recipes_data::RecipesData;
+use crate::utils::recipe_resolver::{RecipeBreakdown, RecipeMode, RecipeResolver};
+use crate::utils::requirements::{RequirementStr, Requirements};
+use tauri::State;
+
+// 将材料清单按合成配方展开为基础材料
+#[tauri::command]
+pub async fn get_schematic_materials(
+    db: State<'_, DatabaseState>,
+    recipes_data: State<'_, RecipesData>,
+    id: i64,
+    mode: Option<RecipeMode>,
+) -> anyhow::Result<RecipeBreakdown, String> {
+    async move {
+        if recipes_data.is_empty() {
+            return Err(anyhow::anyhow!("no recipe data found in ./data/recipes"));
+        }
+        let conn = db.0.get()?;
+        let requirements_str = find_requirements(&conn, id)?;
+        let requirements = Requirements::from_requirement_str(&requirements_str)?;
+        let resolver = RecipeResolver::new(&recipes_data, mode.unwrap_or_default());
+        Ok(resolver.resolve(&requirements))
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
+
+// 导出材料清单, 返回实际写入的文件路径
+#[tauri::command]
+pub async fn export_requirements(
+    db: State<'_, DatabaseState>,
+    file_manager: State<'_, FileManager>,
+    je_blocks: State<'_, BlocksData>,
+    id: i64,
+    format: ExportFormat,
+    target: String,
+    locale: Option<String>,
+) -> anyhow::Result<String, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let schematic = find_schematic(&mut conn, id)?;
+        let requirements_str = find_requirements(&conn, id)?;
+        let rows = RequirementStr::material_rows(&requirements_str, &je_blocks, locale.as_deref())?;
+        let content = format.render(&schematic.name, &rows);
+        let stem: String = schematic
+            .name
+            .chars()
+            .map(|c| if r#"\/:*?"<>|"#.contains(c) { '_' } else { c })
+            .collect();
+        let filename = format!("{}_materials.{}", stem, format.extension());
+        let path = file_manager.export_text(target, &filename, &content)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
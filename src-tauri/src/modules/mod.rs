@@ -1,6 +1,11 @@
+pub mod compatibility;
 pub mod convert;
 pub mod history;
+pub mod inventory;
+pub mod layers;
 pub mod map_art;
+pub mod materials;
 pub mod modules_data;
 pub mod replace;
 pub mod schematic;
+pub mod transform;
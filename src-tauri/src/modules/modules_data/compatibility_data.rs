@@ -0,0 +1,177 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::versions_data::TargetVersion;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+// 每个问题最多返回的坐标数量
+const MAX_POSITIONS: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityIssue {
+    pub block: String,
+    // 为 None 时方块本身不存在, 否则为不支持的 "属性=值"
+    pub property: Option<String>,
+    // 涉及的方块状态种数
+    pub states: u64,
+    // 以下两项只在读取蓝图文件时统计
+    pub count: u64,
+    pub positions: Vec<BlockPos>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompatibilityReport {
+    pub schematic_id: i64,
+    pub name: String,
+    pub game_version: String,
+    pub data_version: i32,
+    pub issues: Vec<CompatibilityIssue>,
+    // 记录的方块列表无法解析时的错误信息, 此时 issues 为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// schematic_data.unique_blocks 中的方块状态
+#[derive(Debug, Deserialize)]
+struct UniqueBlock {
+    id: String,
+    #[serde(default)]
+    properties: BTreeMap<Arc<str>, Arc<str>>,
+}
+
+pub struct CompatibilityChecker<'a> {
+    pub je_blocks: &'a BlocksData,
+    pub target: &'a TargetVersion,
+}
+
+type IssueKey = (String, Option<String>);
+
+impl<'a> CompatibilityChecker<'a> {
+    pub fn new(je_blocks: &'a BlocksData, target: &'a TargetVersion) -> Self {
+        Self { je_blocks, target }
+    }
+
+    fn block_issues(&self, block: &BlockData) -> Vec<IssueKey> {
+        let name = block.id.name.to_string();
+        if self.je_blocks.exists_in(&name, self.target.release) == Some(false) {
+            return vec![(name, None)];
+        }
+        self.je_blocks
+            .block_states
+            .unsupported_properties(block, self.target.release)
+            .into_iter()
+            .map(|(key, value)| (name.clone(), Some(format!("{}={}", key, value))))
+            .collect()
+    }
+
+    fn report(
+        &self,
+        schematic_id: i64,
+        name: String,
+        issues: BTreeMap<IssueKey, CompatibilityIssue>,
+    ) -> CompatibilityReport {
+        CompatibilityReport {
+            schematic_id,
+            name,
+            game_version: self.target.name.to_string(),
+            data_version: self.target.data_version,
+            issues: issues.into_values().collect(),
+            error: None,
+        }
+    }
+
+    pub fn failed_report(
+        &self,
+        schematic_id: i64,
+        name: String,
+        error: String,
+    ) -> CompatibilityReport {
+        CompatibilityReport {
+            error: Some(error),
+            ..self.report(schematic_id, name, BTreeMap::new())
+        }
+    }
+
+    fn issue(key: &IssueKey) -> CompatibilityIssue {
+        CompatibilityIssue {
+            block: key.0.clone(),
+            property: key.1.clone(),
+            states: 0,
+            count: 0,
+            positions: Vec::new(),
+        }
+    }
+
+    // 逐个方块统计数量与坐标
+    pub fn check_volume(
+        &self,
+        schematic_id: i64,
+        name: String,
+        blocks: &BlockVolume,
+    ) -> CompatibilityReport {
+        let mut issues: BTreeMap<IssueKey, CompatibilityIssue> = BTreeMap::new();
+        let mut palette_issues: HashMap<u32, Vec<IssueKey>> = HashMap::new();
+        let counts = blocks.count_states();
+        for (index, block) in blocks.palette().iter().enumerate().skip(1) {
+            // 调色板中残留但未被使用的状态不算问题
+            if counts[index] == 0 {
+                continue;
+            }
+            let keys = self.block_issues(block);
+            for key in &keys {
+                issues
+                    .entry(key.clone())
+                    .or_insert_with(|| Self::issue(key))
+                    .states += 1;
+            }
+            if !keys.is_empty() {
+                palette_issues.insert(index as u32, keys);
+            }
+        }
+        if !palette_issues.is_empty() {
+            for (pos, index) in blocks.iter_indices() {
+                let Some(keys) = palette_issues.get(&index) else {
+                    continue;
+                };
+                for key in keys {
+                    let Some(issue) = issues.get_mut(key) else {
+                        continue;
+                    };
+                    issue.count += 1;
+                    if issue.positions.len() < MAX_POSITIONS {
+                        issue.positions.push(pos);
+                    }
+                }
+            }
+        }
+        self.report(schematic_id, name, issues)
+    }
+
+    // 只看数据库中记录的方块状态, 无需解析蓝图文件
+    pub fn check_unique_blocks(
+        &self,
+        schematic_id: i64,
+        name: String,
+        unique_blocks: &str,
+    ) -> anyhow::Result<CompatibilityReport> {
+        let blocks: Vec<UniqueBlock> = serde_json::from_str(unique_blocks)?;
+        let mut issues: BTreeMap<IssueKey, CompatibilityIssue> = BTreeMap::new();
+        for block in blocks {
+            let block = BlockData {
+                id: BlockId {
+                    name: Arc::from(block.id),
+                },
+                properties: block.properties,
+            };
+            for key in self.block_issues(&block) {
+                issues
+                    .entry(key.clone())
+                    .or_insert_with(|| Self::issue(&key))
+                    .states += 1;
+            }
+        }
+        Ok(self.report(schematic_id, name, issues))
+    }
+}
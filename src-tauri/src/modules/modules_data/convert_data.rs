@@ -1,10 +1,11 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockStatePosList};
+use crate::utils::block_state_pos_list::BlockData;
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::schematic_data::SchematicError;
-use dashmap::DashMap;
+use crate::utils::version_migration::Substitution;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Target {
@@ -22,6 +23,23 @@ pub struct ConvertData {
     pub schematics: HashMap<SchematicType, HashMap<i32, Target>>,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConvertReport {
+    pub success: bool,
+    pub unmapped_blocks: BTreeMap<String, i64>,
+    pub warnings: Vec<String>,
+    pub substitutions: Vec<Substitution>,
+    // 按区域转换时导出的文件路径
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UploadReport {
+    pub schematic_id: i64,
+    // 无法映射为 Java 版的方块及数量: 旧版 MCEdit 为 id:meta, 基岩版为方块名
+    pub unmapped_blocks: BTreeMap<String, i64>,
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum SchematicType {
     Create,
@@ -29,6 +47,8 @@ pub enum SchematicType {
     Bg,
     We,
     Be,
+    // 1.13 之前的 MCEdit / Schematica 蓝图, 只读
+    McEdit,
 }
 
 impl SchematicType {
@@ -39,6 +59,7 @@ impl SchematicType {
             3 => Some(Self::We),
             4 => Some(Self::Bg),
             5 => Some(Self::Be),
+            6 => Some(Self::McEdit),
             _ => None,
         }
     }
@@ -58,6 +79,7 @@ impl SchematicType {
             SchematicType::We => "schem",
             SchematicType::Bg => "json",
             SchematicType::Be => "mcstruct",
+            SchematicType::McEdit => "schematic",
         }
     }
     pub fn type_id(&self) -> &'static i32 {
@@ -67,32 +89,25 @@ impl SchematicType {
             SchematicType::We => &3,
             SchematicType::Bg => &4,
             SchematicType::Be => &5,
+            SchematicType::McEdit => &6,
         }
     }
 }
 
-pub fn get_unique_block(blocks: &BlockStatePosList) -> Result<Vec<Arc<BlockData>>, SchematicError> {
-    let seen: DashMap<Arc<BlockData>, usize> = DashMap::new();
-    let unique = Mutex::new(Vec::new());
-
-    blocks.elements.par_iter().for_each(|block_pos| {
-        let block_data = block_pos.block.clone();
-        let entry = seen.entry(block_data.clone());
-        match entry {
-            dashmap::mapref::entry::Entry::Occupied(_) => {}
-            dashmap::mapref::entry::Entry::Vacant(vacant) => {
-                let mut unique_lock = unique.lock().unwrap();
-                let index = unique_lock.len();
-                vacant.insert(index);
-                unique_lock.push(block_data);
-            }
-        }
-    });
-
-    Ok(unique.into_inner().unwrap())
+// 调色板中实际出现过的方块状态, 空气仅在体积内存在空位时返回
+pub fn get_unique_block(blocks: &BlockVolume) -> Result<Vec<Arc<BlockData>>, SchematicError> {
+    let counts = blocks.count_states();
+    let unique = blocks
+        .palette()
+        .par_iter()
+        .zip(counts.par_iter())
+        .filter(|(_, &count)| count > 0)
+        .map(|(block, _)| block.clone())
+        .collect();
+    Ok(unique)
 }
 
-pub fn get_unique_block_str(blocks: &BlockStatePosList) -> Result<String, SchematicError> {
+pub fn get_unique_block_str(blocks: &BlockVolume) -> Result<String, SchematicError> {
     let unique = get_unique_block(blocks)?;
     let str = serde_json::to_string(&unique).map_err(SchematicError::Json)?;
     Ok(str)
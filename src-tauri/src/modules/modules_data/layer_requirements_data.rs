@@ -0,0 +1,198 @@
+use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::Requirements;
+use crate::utils::schematic_data::Size;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 分组方式: 逐层、每 N 层一组、或与蓝图分割相同的区段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum LayerMode {
+    Layer,
+    Slab { layers: i32 },
+    Split { split_type: i64, split_number: i64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerGroup {
+    pub index: usize,
+    // 该组在分组轴上的坐标范围 (含边界)
+    pub min: i32,
+    pub max: i32,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerBlock {
+    pub id: String,
+    pub zh_cn: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerMatrix {
+    pub axis: String,
+    pub layers: Vec<LayerGroup>,
+    pub blocks: Vec<LayerBlock>,
+    // counts[层][方块], 与 layers / blocks 的顺序对应
+    pub counts: Vec<Vec<i64>>,
+}
+
+// 沿一个轴按固定步长划分, 超出范围的坐标归入首尾两组
+#[derive(Debug, Clone)]
+pub struct Partition {
+    pub axis: usize,
+    pub origin: i32,
+    pub step: i32,
+    pub end: i32,
+    pub count: usize,
+}
+
+impl Partition {
+    pub fn new(
+        mode: &LayerMode,
+        bounds: Option<(BlockPos, BlockPos)>,
+        size: &Size,
+    ) -> Result<Self> {
+        match *mode {
+            LayerMode::Layer => Self::by_layers(bounds, 1),
+            LayerMode::Slab { layers } => {
+                if layers < 1 {
+                    return Err(anyhow!("Layer count must be at least 1"));
+                }
+                Self::by_layers(bounds, layers)
+            }
+            // 与 schematic_split 的划分保持一致, 最后一段包含余数
+            LayerMode::Split {
+                split_type,
+                split_number,
+            } => {
+                if split_number < 1 {
+                    return Err(anyhow!("Split number must be at least 1"));
+                }
+                let (dim_size, axis) = match split_type {
+                    1 => (size.width, 0),
+                    2 => (size.height, 1),
+                    _ => return Err(anyhow!("Invalid split type: {}", split_type)),
+                };
+                if dim_size < split_number as i32 {
+                    return Err(anyhow!(
+                        "Dimension size {} is smaller than split count {}",
+                        dim_size,
+                        split_number
+                    ));
+                }
+                Ok(Self {
+                    axis,
+                    origin: 0,
+                    step: dim_size / split_number as i32,
+                    end: dim_size - 1,
+                    count: split_number as usize,
+                })
+            }
+        }
+    }
+
+    fn by_layers(bounds: Option<(BlockPos, BlockPos)>, step: i32) -> Result<Self> {
+        let (min, max) = bounds.ok_or(anyhow!("schematic is empty"))?;
+        let height = max.y - min.y + 1;
+        Ok(Self {
+            axis: 1,
+            origin: min.y,
+            step,
+            end: max.y,
+            count: ((height + step - 1) / step) as usize,
+        })
+    }
+
+    pub fn group_of(&self, pos: &BlockPos) -> Option<usize> {
+        let coord = if self.axis == 0 { pos.x } else { pos.y };
+        let index = (coord - self.origin).div_euclid(self.step);
+        Some(index.clamp(0, self.count as i32 - 1) as usize)
+    }
+
+    pub fn range(&self, index: usize) -> (i32, i32) {
+        let min = self.origin + index as i32 * self.step;
+        let max = if index + 1 == self.count {
+            self.end
+        } else {
+            min + self.step - 1
+        };
+        (min, max)
+    }
+
+    pub fn axis_name(&self) -> &'static str {
+        if self.axis == 0 {
+            "x"
+        } else {
+            "y"
+        }
+    }
+}
+
+impl LayerMatrix {
+    pub fn new(
+        partition: &Partition,
+        groups: &[Requirements],
+        je_blocks: &BlocksData,
+        locale: Option<&str>,
+    ) -> Self {
+        let mut totals: HashMap<String, i64> = HashMap::new();
+        for requirements in groups {
+            for (id, &count) in requirements.get_requirements() {
+                *totals.entry(id.name.to_string()).or_insert(0) += count as i64;
+            }
+        }
+        // 列按总数从多到少排列
+        let mut ids: Vec<(String, i64)> = totals.into_iter().filter(|(_, n)| *n > 0).collect();
+        ids.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let columns: HashMap<&str, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| (id.as_str(), i))
+            .collect();
+
+        let mut layers = Vec::with_capacity(groups.len());
+        let mut counts = Vec::with_capacity(groups.len());
+        for (index, requirements) in groups.iter().enumerate() {
+            let mut row = vec![0i64; ids.len()];
+            for (id, &count) in requirements.get_requirements() {
+                if let Some(&column) = columns.get(id.name.as_ref()) {
+                    row[column] += count as i64;
+                }
+            }
+            let (min, max) = partition.range(index);
+            layers.push(LayerGroup {
+                index,
+                min,
+                max,
+                total: row.iter().sum(),
+            });
+            counts.push(row);
+        }
+
+        let blocks = ids
+            .iter()
+            .map(|(id, total)| LayerBlock {
+                id: id.clone(),
+                zh_cn: je_blocks
+                    .get_zh_cn(&id.replace("minecraft:", ""))
+                    .map(|s| s.to_owned())
+                    .unwrap_or_else(|| id.clone()),
+                name: locale.map(|locale| je_blocks.get_locale_name(id, locale)),
+                total: *total,
+            })
+            .collect();
+
+        Self {
+            axis: partition.axis_name().to_string(),
+            layers,
+            blocks,
+            counts,
+        }
+    }
+}
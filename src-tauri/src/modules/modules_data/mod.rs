@@ -1,3 +1,5 @@
+pub mod compatibility_data;
 pub mod convert_data;
 pub mod history_data;
+pub mod layer_requirements_data;
 pub mod replace_data;
@@ -1,27 +1,27 @@
-use crate::building_gadges::to_bg_schematic::ToBgSchematic;
-use crate::create::to_create_schematic::ToCreateSchematic;
 use crate::data_files::files::FileManager;
 use crate::database::db_apis::history_api::new_history;
 use crate::database::db_apis::schematic_data_api::new_schematic_data;
 use crate::database::db_apis::schematics_api::{find_schematic, new_schematic};
 use crate::database::db_apis::user_api::add_user_schematic;
 use crate::database::db_control::DatabaseState;
-use crate::litematica::to_lm_schematic::ToLmSchematic;
 use crate::modules::modules_data::convert_data::get_unique_block_str;
 use crate::modules::modules_data::replace_data::{ReplacementRule, RuleMatcher};
+use crate::schematic_format::{format_by_code, ReadOptions, WriteOptions};
 use crate::utils::block_state_pos_list::{BlockData, BlockId};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
 use crate::utils::requirements::{get_requirements, RequirementStr};
-use crate::word_edit::to_we_schematic::ToWeSchematic;
-use rayon::prelude::*;
 use std::sync::Arc;
 use tauri::State;
 
 #[tauri::command]
 pub async fn schematic_replacement(
     rules: Vec<ReplacementRule>,
+    region: Option<String>,
+    include_contents: Option<bool>,
     db: State<'_, DatabaseState>,
     je_blocks: State<'_, BlocksData>,
+    be_blocks: State<'_, BeBlocksData>,
     file_manager: State<'_, FileManager>,
 ) -> Result<bool, String> {
     async move {
@@ -31,8 +31,16 @@ pub async fn schematic_replacement(
         let version = schematic.version;
         let sub_version = schematic.sub_type;
         let v_type = schematic.schematic_type;
-        let mut data =
-            file_manager.get_schematic_data(schematic_id, version, sub_version, v_type)?;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let mut data = file_manager.get_schematic_data(
+            schematic_id,
+            version,
+            sub_version,
+            v_type,
+            &read_options,
+        )?;
         let mut rule_cache = Vec::with_capacity(rules.len());
         for rule in &rules {
             let matcher = match rule.mode {
@@ -69,16 +77,16 @@ pub async fn schematic_replacement(
             rule_cache.push((matcher, rule.global));
         }
 
-        data.blocks.elements.par_iter_mut().for_each(|block| {
+        // 规则作用于调色板, 每种方块状态只需匹配一次
+        let replace_block = |palette_block: &Arc<BlockData>| {
+            let mut block = palette_block.clone();
             for (matcher, global) in &rule_cache {
                 let matches = match matcher {
-                    RuleMatcher::IdMatch { original, .. } => {
-                        block.block.id.name.as_ref() == *original
-                    }
+                    RuleMatcher::IdMatch { original, .. } => block.id.name.as_ref() == *original,
                     RuleMatcher::FullMatch { original, .. } => {
-                        block.block.id == original.id
-                            && block.block.properties.len() == original.properties.len()
-                            && block.block.properties.iter().all(|(k, v)| {
+                        block.id == original.id
+                            && block.properties.len() == original.properties.len()
+                            && block.properties.iter().all(|(k, v)| {
                                 original
                                     .properties
                                     .get(k.as_ref())
@@ -93,120 +101,57 @@ pub async fn schematic_replacement(
                             id: BlockId {
                                 name: Arc::from(replacement.as_str()),
                             },
-                            properties: block.block.properties.clone(),
+                            properties: block.properties.clone(),
                         },
                         RuleMatcher::FullMatch { replacement, .. } => replacement.clone(),
                     };
 
-                    let old = Arc::make_mut(&mut block.block);
-                    *old = new_block;
+                    block = Arc::new(new_block);
                 }
             }
-        });
-        match v_type {
-            1 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToCreateSchematic::new(&data)?.create_schematic(true);
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
-            }
-            2 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToLmSchematic::new(&data)?.lm_schematic(6);
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
-            }
-            3 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToWeSchematic::new(&data)?.we_schematic(sub_version)?;
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_nbt_value(new_id, data, 0, sub_version, v_type, true)?;
+            block
+        };
+        match &region {
+            Some(name) => {
+                let region = data
+                    .regions
+                    .iter()
+                    .find(|r| &r.name == name)
+                    .ok_or(anyhow::anyhow!("unknow region: {}", name))?;
+                let (min, max) = (region.min(), region.max());
+                data.blocks.map_palette_in(min, max, replace_block);
             }
-            4 => {
-                let requirement = get_requirements(&data.blocks)?;
-                let requirements_str = RequirementStr::from_requirements(&requirement, &je_blocks)
-                    .export_to_string()?;
-                let unique_blocks = get_unique_block_str(&data.blocks)?;
-                let data = ToBgSchematic::new(&data)?.bg_schematic(sub_version)?;
-                schematic.name = format!("replace_schematic_{}", schematic_id);
-                let new_id = new_schematic(&mut conn, schematic.clone())?;
-                new_schematic_data(
-                    &mut conn,
-                    new_id,
-                    requirements_str.clone(),
-                    unique_blocks.clone(),
-                )?;
-                add_user_schematic(&mut conn, 1)?;
-                let schematic_str = serde_json::to_string(&schematic)?;
-                new_history(
-                    &mut conn,
-                    new_id,
-                    schematic_str,
-                    requirements_str,
-                    unique_blocks,
-                )?;
-                file_manager.save_json_value(new_id, data, 0, sub_version, v_type)?;
-            }
-            //5 => {}
-            _ => return Err(anyhow::anyhow!("unknow type: {}", v_type)),
+            None => data.blocks.map_palette(replace_block),
+        }
+        let format = format_by_code(v_type)?;
+        let mut requirement = get_requirements(&data.blocks)?.with_entities(&data.entities);
+        // 容器内物品默认计入材料
+        if include_contents.unwrap_or(true) {
+            requirement = requirement.with_contents(&data.tile_entities_list);
         }
+        let requirements_str =
+            RequirementStr::from_requirements(&requirement, &je_blocks).export_to_string()?;
+        let unique_blocks = get_unique_block_str(&data.blocks)?;
+        let options = WriteOptions::new(sub_version).with_be_blocks(&be_blocks);
+        let encoded = format.write(&data, &options)?;
+        schematic.name = format!("replace_schematic_{}", schematic_id);
+        let new_id = new_schematic(&mut conn, schematic.clone())?;
+        new_schematic_data(
+            &mut conn,
+            new_id,
+            requirements_str.clone(),
+            unique_blocks.clone(),
+        )?;
+        add_user_schematic(&mut conn, 1)?;
+        let schematic_str = serde_json::to_string(&schematic)?;
+        new_history(
+            &mut conn,
+            new_id,
+            schematic_str,
+            requirements_str,
+            unique_blocks,
+        )?;
+        file_manager.save_encoded(new_id, encoded, 0, v_type)?;
         Ok(true)
     }
     .await
@@ -0,0 +1,84 @@
+use crate::data_files::files::FileManager;
+use crate::database::db_apis::history_api::update_history;
+use crate::database::db_apis::schematic_data_api::update_schematic_data;
+use crate::database::db_apis::schematics_api::{
+    find_schematic, get_schematic_version, update_schematic,
+};
+use crate::database::db_control::DatabaseState;
+use crate::modules::modules_data::convert_data::get_unique_block_str;
+use crate::schematic_format::{format_by_code, ReadOptions, WriteOptions};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::requirements::{get_requirements, RequirementStr};
+use crate::utils::transform::Transform;
+use chrono::Local;
+use tauri::State;
+
+#[tauri::command]
+pub async fn schematic_transform(
+    schematic_id: i64,
+    transforms: Vec<Transform>,
+    include_contents: Option<bool>,
+    db: State<'_, DatabaseState>,
+    je_blocks: State<'_, BlocksData>,
+    be_blocks: State<'_, BeBlocksData>,
+    file_manager: State<'_, FileManager>,
+) -> Result<bool, String> {
+    async move {
+        let mut conn = db.0.get()?;
+        let mut schematic = find_schematic(&mut conn, schematic_id)?;
+        let version = schematic.version;
+        let sub_version = schematic.sub_type;
+        let v_type = schematic.schematic_type;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let data = file_manager.get_schematic_data(
+            schematic_id,
+            version,
+            sub_version,
+            v_type,
+            &read_options,
+        )?;
+        let data = transforms
+            .into_iter()
+            .try_fold(data, |data, transform| data.transform(transform))?;
+
+        let mut requirement = get_requirements(&data.blocks)?.with_entities(&data.entities);
+        // 容器内物品默认计入材料
+        if include_contents.unwrap_or(true) {
+            requirement = requirement.with_contents(&data.tile_entities_list);
+        }
+        let requirements_str =
+            RequirementStr::from_requirements(&requirement, &je_blocks).export_to_string()?;
+        let unique_blocks = get_unique_block_str(&data.blocks)?;
+
+        // 变换结果作为原蓝图的新版本保存
+        let new_version = get_schematic_version(&mut conn, schematic_id)? + 1;
+        let options = WriteOptions::new(sub_version).with_be_blocks(&be_blocks);
+        let encoded = format_by_code(v_type)?.write(&data, &options)?;
+        file_manager.save_encoded(schematic_id, encoded, new_version, v_type)?;
+
+        schematic.version = new_version;
+        schematic.sizes = data.size.to_string();
+        schematic.updated_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        update_schematic(&mut conn, schematic.clone())?;
+        update_schematic_data(
+            &mut conn,
+            schematic_id,
+            requirements_str.clone(),
+            unique_blocks.clone(),
+        )?;
+        let schematic_str = serde_json::to_string(&schematic)?;
+        update_history(
+            &mut conn,
+            schematic_id,
+            schematic_str,
+            requirements_str,
+            unique_blocks,
+        )?;
+        Ok(true)
+    }
+    .await
+    .map_err(|e: anyhow::Error| e.to_string())
+}
@@ -0,0 +1,76 @@
+use crate::be_schematic::be_schematic::BeSchematic;
+use crate::be_schematic::le_nbt::{from_bytes_le, to_bytes_le};
+use crate::be_schematic::to_be_schematic::ToBeSchematic;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+
+pub struct BedrockFormat;
+
+fn be_blocks(be_blocks: Option<&BeBlocksData>) -> Result<&BeBlocksData> {
+    be_blocks.ok_or_else(|| anyhow!("bedrock block mapping is not loaded"))
+}
+
+impl SchematicFormat for BedrockFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::Be
+    }
+
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        match probe {
+            Probe::BedrockNbt(root)
+                if root.contains_key("structure") && root.contains_key("size") =>
+            {
+                Some(-1)
+            }
+            _ => None,
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, options: &ReadOptions) -> Result<SchematicData> {
+        let (data, _) =
+            BeSchematic::new_from_bytes(data)?.get_blocks_pos(be_blocks(options.be_blocks)?)?;
+        Ok(data)
+    }
+
+    fn import(
+        &self,
+        data: Vec<u8>,
+        _sub_type: i32,
+        options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let schematic = BeSchematic::new_from_bytes(data)?;
+        let (data, unmapped) = schematic.get_blocks_pos(be_blocks(options.be_blocks)?)?;
+        let mut imported = ImportedSchematic::new(data, -1, schematic.get_size()?.to_string());
+        imported.unmapped_blocks = unmapped;
+        imported.game_version = Some(
+            schematic
+                .get_game_version()
+                .unwrap_or_else(|_| "unknown_version".to_string()),
+        );
+        Ok(imported)
+    }
+
+    // 基岩版结构不压缩, 保存时按小端序写出
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic> {
+        let schematic = ToBeSchematic::new(data, be_blocks(options.be_blocks)?)?;
+        let value = schematic.be_schematic()?;
+        let mut encoded = EncodedSchematic::nbt(value, -1, false);
+        encoded.unmapped_blocks = schematic.unmapped;
+        Ok(encoded)
+    }
+
+    // 基岩版结构为未压缩的小端序 NBT
+    fn decode_nbt(&self, data: &[u8]) -> Result<Value> {
+        Ok(from_bytes_le(data)?)
+    }
+
+    fn encode_nbt(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(to_bytes_le(value)?)
+    }
+}
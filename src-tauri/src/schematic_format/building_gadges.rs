@@ -0,0 +1,86 @@
+use crate::building_gadges::bg_schematic::BgSchematic;
+use crate::building_gadges::to_bg_schematic::ToBgSchematic;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+
+pub struct BgFormat;
+
+impl SchematicFormat for BgFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::Bg
+    }
+
+    // 与 BgSchematic::get_type 保持一致的判断顺序
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        let has_key = |key: &str| match probe {
+            Probe::Json(root) => root.contains_key(key),
+            Probe::Snbt(root) => root.contains_key(key),
+            _ => false,
+        };
+        if has_key("statePosArrayList") {
+            Some(0)
+        } else if has_key("body") {
+            Some(1)
+        } else if has_key("mapIntState") {
+            Some(2)
+        } else {
+            None
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, _options: &ReadOptions) -> Result<SchematicData> {
+        Ok(BgSchematic::new_from_data(data)?.get_blocks_pos()?)
+    }
+
+    // 建筑小帮手蓝图不记录游戏版本
+    fn import(
+        &self,
+        data: Vec<u8>,
+        sub_type: i32,
+        _options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let schematic = BgSchematic::new_from_data(data)?;
+        let sub_type = if sub_type >= 0 {
+            sub_type
+        } else {
+            schematic.get_type()?
+        };
+        Ok(ImportedSchematic::new(
+            schematic.get_blocks_pos()?,
+            sub_type,
+            schematic.get_size()?.to_string(),
+        ))
+    }
+
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic> {
+        let json = ToBgSchematic::new(data)?.bg_schematic(options.sub_version)?;
+        Ok(EncodedSchematic::json(json, options.sub_version)
+            .with_warnings(options.version_warnings(data)))
+    }
+
+    fn read_text(&self, data: Vec<u8>) -> Result<String> {
+        Ok(String::from_utf8(data)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(text: &str) -> Option<i32> {
+        BgFormat.detect(&Probe::from_bytes(text.as_bytes()))
+    }
+
+    #[test]
+    fn detects_by_top_level_keys() {
+        assert_eq!(detect(r#"{"statePosArrayList": "{}"}"#), Some(0));
+        assert_eq!(detect("\u{feff}{\n  \"body\": \"H4sI\"\n}"), Some(1));
+        assert_eq!(detect(r#"{"mapIntState": []}"#), Some(2));
+        // 键名只出现在字符串值中时不算
+        assert_eq!(detect(r#"{"name": "body", "note": "mapIntState"}"#), None);
+    }
+}
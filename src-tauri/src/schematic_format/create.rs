@@ -0,0 +1,59 @@
+use crate::create::create_schematic::CreateSchematic;
+use crate::create::to_create_schematic::ToCreateSchematic;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+use fastnbt::Value;
+
+pub struct CreateFormat;
+
+impl SchematicFormat for CreateFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::Create
+    }
+
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        match probe {
+            Probe::JavaNbt(root) if root.contains_key("palette") && root.contains_key("blocks") => {
+                Some(-1)
+            }
+            _ => None,
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, _options: &ReadOptions) -> Result<SchematicData> {
+        Ok(CreateSchematic::new_from_bytes(data)?.get_blocks_pos()?)
+    }
+
+    fn import(
+        &self,
+        data: Vec<u8>,
+        _sub_type: i32,
+        _options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let schematic = CreateSchematic::new_from_bytes(data)?;
+        let sizes = schematic
+            .get_size()?
+            .iter()
+            .filter_map(|v| match v {
+                Value::Int(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        Ok(ImportedSchematic::new(
+            schematic.get_blocks_pos()?,
+            -1,
+            sizes,
+        ))
+    }
+
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic> {
+        let value = ToCreateSchematic::new(data)?
+            .create_schematic(options.keep_air, options.data_version(data));
+        Ok(EncodedSchematic::nbt(value, -1, true).with_warnings(options.version_warnings(data)))
+    }
+}
@@ -0,0 +1,59 @@
+use crate::litematica::lm_schematic::LmSchematic;
+use crate::litematica::to_lm_schematic::ToLmSchematic;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::schematic_data::SchematicData;
+use anyhow::Result;
+
+pub struct LitematicFormat;
+
+impl SchematicFormat for LitematicFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::Litematic
+    }
+
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        match probe {
+            Probe::JavaNbt(root) if root.contains_key("Regions") => Some(-1),
+            _ => None,
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, _options: &ReadOptions) -> Result<SchematicData> {
+        Ok(LmSchematic::new_from_bytes(data)?.get_blocks_pos()?)
+    }
+
+    fn import(
+        &self,
+        data: Vec<u8>,
+        _sub_type: i32,
+        _options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let schematic = LmSchematic::new_from_bytes(data)?;
+        let metadata = schematic.read_metadata()?;
+        let mut imported = ImportedSchematic::new(
+            schematic.get_blocks_pos()?,
+            -1,
+            metadata.enclosing_size.to_string(),
+        );
+        // 投影默认名称不作为蓝图名
+        if metadata.name.trim() != "Unnamed" {
+            imported.name = Some(metadata.name);
+        }
+        imported.description = Some(metadata.description);
+        imported.author = Some(metadata.author);
+        Ok(imported)
+    }
+
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic> {
+        let value =
+            ToLmSchematic::new(data)?.lm_schematic(options.lm_version, options.data_version(data));
+        Ok(EncodedSchematic::nbt(value, -1, true).with_warnings(options.version_warnings(data)))
+    }
+
+    fn preview_limit(&self) -> usize {
+        512 * 1024
+    }
+}
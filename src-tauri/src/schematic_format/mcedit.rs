@@ -0,0 +1,87 @@
+use crate::mcedit::mcedit_schematic::McEditSchematic;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::{anyhow, Result};
+
+pub struct McEditFormat;
+
+fn je_blocks(je_blocks: Option<&BlocksData>) -> Result<&BlocksData> {
+    je_blocks.ok_or_else(|| anyhow!("java block table is not loaded"))
+}
+
+impl SchematicFormat for McEditFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::McEdit
+    }
+
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        match probe {
+            Probe::JavaNbt(root) if McEditSchematic::is_legacy(root) => Some(-1),
+            _ => None,
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, options: &ReadOptions) -> Result<SchematicData> {
+        let (data, _) =
+            McEditSchematic::new_from_bytes(data)?.get_blocks_pos(je_blocks(options.je_blocks)?)?;
+        Ok(data)
+    }
+
+    // 旧版 ID 映射为方块表中的最新名称, 游戏版本记为方块表收录的最新正式版
+    fn import(
+        &self,
+        data: Vec<u8>,
+        _sub_type: i32,
+        options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let je_blocks = je_blocks(options.je_blocks)?;
+        let schematic = McEditSchematic::new_from_bytes(data)?;
+        let (data, unmapped) = schematic.get_blocks_pos(je_blocks)?;
+        let mut imported = ImportedSchematic::new(data, -1, schematic.get_size()?.to_string());
+        imported.game_version = Some(match je_blocks.latest_release() {
+            (minor, 0) => format!("1.{}", minor),
+            (minor, patch) => format!("1.{}.{}", minor, patch),
+        });
+        imported.unmapped_blocks = unmapped;
+        Ok(imported)
+    }
+
+    // 数字 ID 无法表示 1.13 之后的方块状态, 只读
+    fn write(&self, _data: &SchematicData, _options: &WriteOptions) -> Result<EncodedSchematic> {
+        Err(anyhow!(
+            "legacy MCEdit schematics cannot be written, convert to another format first"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schematic_format::format_by_extension;
+    use fastnbt::{ByteArray, Value};
+    use std::collections::HashMap;
+
+    #[test]
+    fn detects_legacy_schematic() {
+        let mut root = HashMap::new();
+        root.insert(
+            "Blocks".to_string(),
+            Value::ByteArray(ByteArray::new(vec![1])),
+        );
+        root.insert(
+            "Data".to_string(),
+            Value::ByteArray(ByteArray::new(vec![0])),
+        );
+        assert_eq!(McEditFormat.detect(&Probe::JavaNbt(root.clone())), Some(-1));
+        root.remove("Data");
+        assert_eq!(McEditFormat.detect(&Probe::JavaNbt(root)), None);
+        assert_eq!(
+            format_by_extension("SCHEMATIC").map(|format| format.type_id()),
+            Some(6)
+        );
+    }
+}
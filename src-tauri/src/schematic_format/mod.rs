@@ -0,0 +1,373 @@
+pub mod bedrock;
+pub mod building_gadges;
+pub mod create;
+pub mod litematica;
+pub mod mcedit;
+pub mod world_edit;
+
+use crate::be_schematic::le_nbt::from_bytes_le;
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::versions_data::TargetVersion;
+use crate::utils::schematic_data::SchematicData;
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Write};
+
+use bedrock::BedrockFormat;
+use building_gadges::BgFormat;
+use create::CreateFormat;
+use litematica::LitematicFormat;
+use mcedit::McEditFormat;
+use world_edit::WorldEditFormat;
+
+// 未指定目标版本且源蓝图没有 DataVersion 时写入 1.20.1
+pub const DEFAULT_DATA_VERSION: i32 = 3465;
+
+// 写出参数, 各格式只读取自己关心的字段
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions<'a> {
+    pub sub_version: i32,
+    pub lm_version: i32,
+    pub keep_air: bool,
+    pub be_blocks: Option<&'a BeBlocksData>,
+    pub target: Option<&'a TargetVersion>,
+    pub je_blocks: Option<&'a BlocksData>,
+}
+
+impl<'a> WriteOptions<'a> {
+    pub fn new(sub_version: i32) -> Self {
+        Self {
+            sub_version,
+            lm_version: 6,
+            keep_air: true,
+            be_blocks: None,
+            target: None,
+            je_blocks: None,
+        }
+    }
+
+    pub fn with_lm_version(mut self, lm_version: i32) -> Self {
+        self.lm_version = lm_version;
+        self
+    }
+
+    pub fn with_air(mut self, keep_air: bool) -> Self {
+        self.keep_air = keep_air;
+        self
+    }
+
+    pub fn with_be_blocks(mut self, be_blocks: &'a BeBlocksData) -> Self {
+        self.be_blocks = Some(be_blocks);
+        self
+    }
+
+    pub fn with_target(mut self, target: &'a TargetVersion, je_blocks: &'a BlocksData) -> Self {
+        self.target = Some(target);
+        self.je_blocks = Some(je_blocks);
+        self
+    }
+
+    // 未指定目标版本时沿用源蓝图的 DataVersion
+    pub fn data_version(&self, data: &SchematicData) -> i32 {
+        self.target
+            .map(|target| target.data_version)
+            .or(data.data_version)
+            .unwrap_or(DEFAULT_DATA_VERSION)
+    }
+
+    // 目标版本中不存在的方块, 未指定目标版本时不检查
+    pub fn version_warnings(&self, data: &SchematicData) -> Vec<String> {
+        let (Some(target), Some(je_blocks)) = (self.target, self.je_blocks) else {
+            return Vec::new();
+        };
+        let counts = data.blocks.count_states();
+        data.blocks
+            .palette()
+            .iter()
+            .zip(counts.iter())
+            .filter(|(_, &count)| count > 0)
+            .filter(|(block, _)| je_blocks.exists_in(&block.id.name, target.release) == Some(false))
+            .map(|(block, count)| {
+                format!(
+                    "{} 在 {} 中不存在 ({} 个)",
+                    block.id.name, target.name, count
+                )
+            })
+            .collect()
+    }
+}
+
+// 读取参数, 需要映射表的格式 (基岩版, 旧版 MCEdit) 从这里取
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions<'a> {
+    pub be_blocks: Option<&'a BeBlocksData>,
+    pub je_blocks: Option<&'a BlocksData>,
+}
+
+impl<'a> ReadOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_be_blocks(mut self, be_blocks: &'a BeBlocksData) -> Self {
+        self.be_blocks = Some(be_blocks);
+        self
+    }
+
+    pub fn with_je_blocks(mut self, je_blocks: &'a BlocksData) -> Self {
+        self.je_blocks = Some(je_blocks);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SchematicOutput {
+    Nbt { value: Value, compress: bool },
+    Json(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct EncodedSchematic {
+    pub output: SchematicOutput,
+    // 文件名中记录的子版本
+    pub sub_version: i32,
+    pub unmapped_blocks: BTreeMap<String, i64>,
+    pub warnings: Vec<String>,
+}
+
+impl EncodedSchematic {
+    pub fn nbt(value: Value, sub_version: i32, compress: bool) -> Self {
+        Self {
+            output: SchematicOutput::Nbt { value, compress },
+            sub_version,
+            unmapped_blocks: BTreeMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn json(data: String, sub_version: i32) -> Self {
+        Self {
+            output: SchematicOutput::Json(data),
+            sub_version,
+            unmapped_blocks: BTreeMap::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn with_warnings(mut self, warnings: Vec<String>) -> Self {
+        self.warnings = warnings;
+        self
+    }
+}
+
+// 导入蓝图时除方块外还需要的信息, 格式中没有的字段为 None
+#[derive(Debug, Clone)]
+pub struct ImportedSchematic {
+    pub data: SchematicData,
+    pub sub_type: i32,
+    pub sizes: String,
+    pub game_version: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    // 无法映射为 Java 版的方块及数量
+    pub unmapped_blocks: BTreeMap<String, i64>,
+}
+
+impl ImportedSchematic {
+    pub fn new(data: SchematicData, sub_type: i32, sizes: String) -> Self {
+        Self {
+            data,
+            sub_type,
+            sizes,
+            game_version: None,
+            name: None,
+            description: None,
+            author: None,
+            unmapped_blocks: BTreeMap::new(),
+        }
+    }
+}
+
+// 识别格式时只解析一次文件, 各格式共用解析结果
+#[derive(Debug)]
+pub enum Probe {
+    JavaNbt(HashMap<String, Value>),
+    BedrockNbt(HashMap<String, Value>),
+    // 顶层为对象的 JSON
+    Json(serde_json::Map<String, serde_json::Value>),
+    // 不是合法 JSON 的 SNBT 文本, 如建筑小帮手的旧版导出
+    Snbt(HashMap<String, Value>),
+    Unknown,
+}
+
+impl Probe {
+    pub fn from_bytes(data: &[u8]) -> Self {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            return fastnbt::from_reader(GzDecoder::new(Cursor::new(data)))
+                .map(Probe::JavaNbt)
+                .unwrap_or(Probe::Unknown);
+        }
+        if let Ok(text) = std::str::from_utf8(data) {
+            let text = text.trim_start_matches('\u{feff}');
+            if text.trim_start().starts_with('{') {
+                if let Ok(serde_json::Value::Object(root)) =
+                    serde_json::from_slice::<serde_json::Value>(text.as_bytes())
+                {
+                    return Probe::Json(root);
+                }
+                if let Ok(Value::Compound(root)) = fastsnbt::from_str(&text.replace("\r\n", "")) {
+                    return Probe::Snbt(root);
+                }
+                return Probe::Unknown;
+            }
+        }
+        // 基岩版结构为未压缩的小端 NBT
+        if data.first() == Some(&0x0a) {
+            if let Ok(Value::Compound(root)) = from_bytes_le(data) {
+                return Probe::BedrockNbt(root);
+            }
+        }
+        Probe::Unknown
+    }
+}
+
+pub trait SchematicFormat: Send + Sync {
+    fn schematic_type(&self) -> SchematicType;
+
+    fn extension(&self) -> &'static str {
+        self.schematic_type().file_extension()
+    }
+
+    fn sub_versions(&self) -> Vec<i32> {
+        self.schematic_type().get_sub_versions()
+    }
+
+    fn type_id(&self) -> i32 {
+        *self.schematic_type().type_id()
+    }
+
+    // 识别成功时返回子版本
+    fn detect(&self, probe: &Probe) -> Option<i32>;
+
+    fn read(&self, data: Vec<u8>, options: &ReadOptions) -> Result<SchematicData>;
+
+    // 上传时读取, sub_type 为 -1 时由读取器自行判断子版本
+    fn import(
+        &self,
+        data: Vec<u8>,
+        sub_type: i32,
+        options: &ReadOptions,
+    ) -> Result<ImportedSchematic>;
+
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic>;
+
+    fn decode_nbt(&self, data: &[u8]) -> Result<Value> {
+        Ok(fastnbt::from_reader(GzDecoder::new(Cursor::new(data)))?)
+    }
+
+    fn encode_nbt(&self, value: &Value) -> Result<Vec<u8>> {
+        Ok(fastnbt::to_bytes(value)?)
+    }
+
+    // 写出结果转为文件内容
+    fn encode(&self, encoded: &EncodedSchematic) -> Result<Vec<u8>> {
+        match &encoded.output {
+            SchematicOutput::Nbt { value, compress } => {
+                let bytes = self.encode_nbt(value)?;
+                if !compress {
+                    return Ok(bytes);
+                }
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&bytes)?;
+                Ok(encoder.finish()?)
+            }
+            SchematicOutput::Json(json) => Ok(json.clone().into_bytes()),
+        }
+    }
+
+    // 预览文本的文件大小上限, 超出时不生成预览
+    fn preview_limit(&self) -> usize {
+        8 * 1024 * 1024
+    }
+
+    fn read_text(&self, data: Vec<u8>) -> Result<String> {
+        Ok(fastsnbt::to_string(&self.decode_nbt(&data)?)?)
+    }
+}
+
+static FORMATS: [&dyn SchematicFormat; 6] = [
+    &CreateFormat,
+    &LitematicFormat,
+    &WorldEditFormat,
+    &BgFormat,
+    &BedrockFormat,
+    &McEditFormat,
+];
+
+pub fn formats() -> &'static [&'static dyn SchematicFormat] {
+    &FORMATS
+}
+
+pub fn get_format(schematic_type: &SchematicType) -> &'static dyn SchematicFormat {
+    FORMATS
+        .iter()
+        .copied()
+        .find(|format| &format.schematic_type() == schematic_type)
+        .expect("every schematic type has a registered format")
+}
+
+pub fn format_by_code(code: i32) -> Result<&'static dyn SchematicFormat> {
+    SchematicType::from_code(code)
+        .map(|t| get_format(&t))
+        .ok_or_else(|| anyhow!("unknown schematic type: {}", code))
+}
+
+pub fn format_by_extension(ext: &str) -> Option<&'static dyn SchematicFormat> {
+    let ext = ext.to_lowercase();
+    let ext = if ext == "mcstructure" {
+        "mcstruct"
+    } else {
+        ext.as_str()
+    };
+    FORMATS
+        .iter()
+        .copied()
+        .find(|format| format.extension() == ext)
+}
+
+pub fn file_extension(code: i32) -> &'static str {
+    format_by_code(code)
+        .map(|format| format.extension())
+        .unwrap_or("unknown")
+}
+
+pub struct DetectedFormat {
+    pub format: &'static dyn SchematicFormat,
+    pub sub_type: i32,
+}
+
+// 按文件内容判断格式, 内容无法识别时才退回扩展名
+pub fn detect_format(data: &[u8], ext_hint: &str) -> Option<DetectedFormat> {
+    let probe = Probe::from_bytes(data);
+    FORMATS
+        .iter()
+        .copied()
+        .find_map(|format| {
+            format
+                .detect(&probe)
+                .map(|sub_type| DetectedFormat { format, sub_type })
+        })
+        .or_else(|| {
+            format_by_extension(ext_hint).map(|format| DetectedFormat {
+                format,
+                sub_type: -1,
+            })
+        })
+}
@@ -0,0 +1,61 @@
+use crate::modules::modules_data::convert_data::SchematicType;
+use crate::schematic_format::{
+    EncodedSchematic, ImportedSchematic, Probe, ReadOptions, SchematicFormat, WriteOptions,
+};
+use crate::utils::schematic_data::SchematicData;
+use crate::word_edit::to_we_schematic::ToWeSchematic;
+use crate::word_edit::we_schematic::WeSchematic;
+use anyhow::Result;
+use fastnbt::Value;
+
+pub struct WorldEditFormat;
+
+impl SchematicFormat for WorldEditFormat {
+    fn schematic_type(&self) -> SchematicType {
+        SchematicType::We
+    }
+
+    // Sponge v3 包在 Schematic 标签下, v2 直接在根上
+    fn detect(&self, probe: &Probe) -> Option<i32> {
+        let Probe::JavaNbt(root) = probe else {
+            return None;
+        };
+        if let Some(Value::Compound(_)) = root.get("Schematic") {
+            Some(1)
+        } else if root.contains_key("Palette") && root.contains_key("BlockData") {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn read(&self, data: Vec<u8>, _options: &ReadOptions) -> Result<SchematicData> {
+        Ok(WeSchematic::new_from_bytes(data)?.get_blocks_pos()?)
+    }
+
+    fn import(
+        &self,
+        data: Vec<u8>,
+        sub_type: i32,
+        _options: &ReadOptions,
+    ) -> Result<ImportedSchematic> {
+        let schematic = WeSchematic::new_from_bytes(data)?;
+        let type_version = if sub_type >= 0 {
+            sub_type
+        } else {
+            schematic.get_type()?
+        };
+        Ok(ImportedSchematic::new(
+            schematic.get_blocks_pos()?,
+            type_version,
+            schematic.get_size(type_version)?.to_string(),
+        ))
+    }
+
+    fn write(&self, data: &SchematicData, options: &WriteOptions) -> Result<EncodedSchematic> {
+        let value = ToWeSchematic::new(data)?
+            .we_schematic(options.sub_version, options.data_version(data))?;
+        Ok(EncodedSchematic::nbt(value, options.sub_version, true)
+            .with_warnings(options.version_warnings(data)))
+    }
+}
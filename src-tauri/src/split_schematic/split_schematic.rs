@@ -1,26 +1,27 @@
-use std::collections::VecDeque;
 use std::io::Read;
 use std::path::PathBuf;
 use tauri::State;
 use crate::data_files::files::FileManager;
 use crate::database::db_apis::schematics_api::find_schematic;
 use crate::database::db_control::DatabaseState;
-use crate::utils::block_state_pos_list::{BlockStatePos, BlockStatePosList};
+use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::schematic_data::{SchematicData, Size};
 use anyhow::{anyhow, Result};
-use crate::building_gadges::to_bg_schematic::ToBgSchematic;
-use crate::create::to_create_schematic::ToCreateSchematic;
-use crate::litematica::to_lm_schematic::ToLmSchematic;
-use crate::utils::tile_entities::TileEntitiesList;
-use crate::word_edit::to_we_schematic::ToWeSchematic;
+use crate::schematic_format::{format_by_code, ReadOptions, WriteOptions};
+use crate::utils::minecraft_data::be_blocks_data::BeBlocksData;
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
 
 #[tauri::command]
 pub async fn schematic_split(
     db: State<'_, DatabaseState>,
     file_manager: State<'_, FileManager>,
+    be_blocks: State<'_, BeBlocksData>,
+    je_blocks: State<'_, BlocksData>,
     schematic_id: i64,
     split_type: i64,
-    split_number: i64
+    split_number: i64,
+    region: Option<String>,
 ) -> Result<Vec<(i64, Size, Vec<u8>)>, String> {
     async move {
         let mut conn = db.0.get()?;
@@ -28,43 +29,43 @@ pub async fn schematic_split(
         let version = schematic.version;
         let sub_version = schematic.sub_type;
         let v_type = schematic.schematic_type;
-        let mut data = file_manager.get_schematic_data(schematic_id, version, sub_version, v_type)?;
+        let read_options = ReadOptions::new()
+            .with_be_blocks(&be_blocks)
+            .with_je_blocks(&je_blocks);
+        let mut data = file_manager.get_schematic_data(
+            schematic_id,
+            version,
+            sub_version,
+            v_type,
+            &read_options,
+        )?;
+        if let Some(name) = &region {
+            data = data.region(name)?;
+        }
 
         let size = &data.size;
         let blocks = &data.blocks;
 
         let split_parts = split_block_positions(
-            &blocks.elements,
+            blocks,
             size,
             split_type,
             split_number as usize
         )?;
+        let format = format_by_code(v_type)?;
+        let options = WriteOptions::new(sub_version)
+            .with_air(false)
+            .with_be_blocks(&be_blocks);
         let mut results = Vec::new();
         for (index, (blocks, part_size)) in split_parts.into_iter().enumerate() {
 
-            let schematic = SchematicData::new(blocks, TileEntitiesList::new(), part_size.clone());
-            let temp_file = match v_type {
-                1 => {
-                    let data = ToCreateSchematic::new(&schematic)?.create_schematic(false);
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                2 => {
-                    let data = ToLmSchematic::new(&schematic)?.lm_schematic(sub_version);
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                3 => {
-                    let data = ToWeSchematic::new(&schematic)?.we_schematic(sub_version)?;
-                    file_manager.save_nbt_value_temp(data, v_type, true)?
-                }
-                4 => {
-                    let data = ToBgSchematic::new(&schematic)?.bg_schematic(sub_version)?;
-                    file_manager.save_json_value_temp(data)?
-                }
-                _ => {
-                    anyhow::bail!("unknown schematic type: {}", v_type);
-                }
-            };
-
+            let tile_entities = data.tile_entities_list.filter_by_blocks(&blocks);
+            let entities = data.entities.filter_by_blocks(&blocks);
+            let schematic = SchematicData::new(blocks, tile_entities, part_size.clone())
+                .with_entities(entities)
+                .with_data_version(data.data_version);
+            let encoded = format.write(&schematic, &options)?;
+            let temp_file = file_manager.save_encoded_temp(encoded, v_type)?;
 
             let mut file = temp_file.into_file();
             let mut bytes = Vec::new();
@@ -80,11 +81,11 @@ pub async fn schematic_split(
 }
 
 fn split_block_positions(
-    blocks: &VecDeque<BlockStatePos>,
+    blocks: &BlockVolume,
     size: &Size,
     split_type: i64,
     split_number: usize,
-) -> Result<Vec<(BlockStatePosList, Size)>> {
+) -> Result<Vec<(BlockVolume, Size)>> {
     if split_number == 0 {
         return Err(anyhow!("Split number must be at least 1"));
     }
@@ -129,46 +130,44 @@ fn split_block_positions(
         };
         part_sizes.push(size);
     }
-    let mut result: Vec<VecDeque<BlockStatePos>> = vec![VecDeque::new(); split_number];
-
-    for block in blocks {
-        let pos = match axis_idx {
-            0 => block.pos.x,
-            1 => block.pos.y,
-            _ => unreachable!(),
+    // 按轴截取各部分, 首尾两段向外延伸以包含越界坐标
+    let mut parts_with_size = Vec::with_capacity(split_number);
+    let mut cumulative = 0;
+    for (i, part_size) in part_sizes.into_iter().enumerate() {
+        let part_length = match axis_idx {
+            0 => part_size.width,
+            _ => part_size.height,
         };
+        let lower = if i == 0 { i32::MIN } else { cumulative };
+        let upper = if i == split_number - 1 {
+            i32::MAX
+        } else {
+            cumulative + part_length - 1
+        };
+        cumulative += part_length;
 
-        let mut idx = 0;
-        let mut cumulative = 0;
-        for i in 0..split_number {
-            cumulative += if i == split_number - 1 {
-                step + remainder
-            } else {
-                step
-            };
-
-            if pos < cumulative {
-                idx = i;
-                break;
+        let mut min = BlockPos {
+            x: i32::MIN,
+            y: i32::MIN,
+            z: i32::MIN,
+        };
+        let mut max = BlockPos {
+            x: i32::MAX,
+            y: i32::MAX,
+            z: i32::MAX,
+        };
+        match axis_idx {
+            0 => {
+                min.x = lower;
+                max.x = upper;
             }
-
-            if i == split_number - 1 {
-                idx = split_number - 1;
+            _ => {
+                min.y = lower;
+                max.y = upper;
             }
         }
-
-        if idx < result.len() {
-            result[idx].push_back(block.clone());
-        }
-    }
-
-    let mut parts_with_size = Vec::with_capacity(split_number);
-    for (i, part) in result.into_iter().enumerate() {
-        parts_with_size.push((
-            BlockStatePosList { elements: part },
-            part_sizes[i].clone()
-        ));
+        parts_with_size.push((blocks.sub_volume(min, max), part_size));
     }
 
     Ok(parts_with_size)
-}
\ No newline at end of file
+}
@@ -0,0 +1,239 @@
+use crate::utils::block_state_pos_list::BlockData;
+
+// 无法在生存中获得的技术性方块, 不计入材料
+const TECHNICAL_BLOCKS: [&str; 13] = [
+    "air",
+    "cave_air",
+    "void_air",
+    "fire",
+    "soul_fire",
+    "piston_head",
+    "moving_piston",
+    "nether_portal",
+    "end_portal",
+    "end_gateway",
+    "bubble_column",
+    "frosted_ice",
+    "structure_void",
+];
+
+// 方块与对应物品名称不同的情况
+const BLOCK_TO_ITEM: [(&str, &str); 27] = [
+    ("redstone_wire", "redstone"),
+    ("tripwire", "string"),
+    ("cocoa", "cocoa_beans"),
+    ("carrots", "carrot"),
+    ("potatoes", "potato"),
+    ("beetroots", "beetroot_seeds"),
+    ("wheat", "wheat_seeds"),
+    ("melon_stem", "melon_seeds"),
+    ("attached_melon_stem", "melon_seeds"),
+    ("pumpkin_stem", "pumpkin_seeds"),
+    ("attached_pumpkin_stem", "pumpkin_seeds"),
+    ("sweet_berry_bush", "sweet_berries"),
+    ("cave_vines", "glow_berries"),
+    ("cave_vines_plant", "glow_berries"),
+    ("kelp_plant", "kelp"),
+    ("twisting_vines_plant", "twisting_vines"),
+    ("weeping_vines_plant", "weeping_vines"),
+    ("bamboo_sapling", "bamboo"),
+    ("tall_seagrass", "seagrass"),
+    ("big_dripleaf_stem", "big_dripleaf"),
+    ("pitcher_crop", "pitcher_pod"),
+    ("torchflower_crop", "torchflower_seeds"),
+    ("water_cauldron", "cauldron"),
+    ("lava_cauldron", "cauldron"),
+    ("powder_snow_cauldron", "cauldron"),
+    ("powder_snow", "powder_snow_bucket"),
+    ("wall_torch", "torch"),
+];
+
+// 一个方块状态中包含多个物品的属性
+const COUNT_PROPERTIES: [(&str, &str); 6] = [
+    ("candle", "candles"),
+    ("sea_pickle", "pickles"),
+    ("turtle_egg", "eggs"),
+    ("pink_petals", "flower_amount"),
+    ("wildflowers", "flower_amount"),
+    ("snow", "layers"),
+];
+
+fn property<'a>(block: &'a BlockData, key: &str) -> Option<&'a str> {
+    block.properties.get(key).map(|v| v.as_ref())
+}
+
+// 墙上/倒挂的变种合并到放置用的物品
+fn standing_variant(name: &str) -> String {
+    if let Some(prefix) = name.strip_suffix("_wall_torch") {
+        return format!("{}_torch", prefix);
+    }
+    for (wall, standing) in [
+        ("_wall_hanging_sign", "_hanging_sign"),
+        ("_wall_sign", "_sign"),
+        ("_wall_banner", "_banner"),
+        ("_wall_skull", "_skull"),
+        ("_wall_head", "_head"),
+        ("_wall_fan", "_fan"),
+    ] {
+        if let Some(prefix) = name.strip_suffix(wall) {
+            return format!("{}{}", prefix, standing);
+        }
+    }
+    name.to_string()
+}
+
+// 多格结构只在其中一格计数
+fn is_secondary_part(name: &str, block: &BlockData) -> bool {
+    if name.ends_with("_bed") {
+        return property(block, "part") == Some("head");
+    }
+    // 门、高花、高草等上半部分
+    property(block, "half") == Some("upper")
+}
+
+// 方块状态对应的物品及数量, 空列表表示不计入材料
+pub fn block_items(block: &BlockData) -> Vec<(String, i32)> {
+    let full_name = block.id.name.as_ref();
+    let (namespace, name) = full_name
+        .split_once(':')
+        .unwrap_or(("minecraft", full_name));
+    if namespace != "minecraft" {
+        return vec![(full_name.to_string(), 1)];
+    }
+    if TECHNICAL_BLOCKS.contains(&name) || is_secondary_part(name, block) {
+        return Vec::new();
+    }
+    let item = |name: &str| format!("minecraft:{}", name);
+
+    // 液体只统计源方块, 折算成桶
+    if name == "water" || name == "lava" {
+        return match property(block, "level") {
+            None | Some("0") => vec![(item(&format!("{}_bucket", name)), 1)],
+            _ => Vec::new(),
+        };
+    }
+    if let Some(plant) = name.strip_prefix("potted_") {
+        let plant = match plant {
+            "azalea_bush" => "azalea",
+            "flowering_azalea_bush" => "flowering_azalea",
+            other => other,
+        };
+        return vec![(item("flower_pot"), 1), (item(plant), 1)];
+    }
+    if let Some(candle) = name.strip_suffix("_cake").filter(|c| c.ends_with("candle")) {
+        return vec![(item("cake"), 1), (item(candle), 1)];
+    }
+
+    let name = standing_variant(name);
+    let name = BLOCK_TO_ITEM
+        .iter()
+        .find(|(block_name, _)| *block_name == name)
+        .map(|(_, item_name)| item_name.to_string())
+        .unwrap_or(name);
+
+    let mut count = 1;
+    if name.ends_with("_slab") && property(block, "type") == Some("double") {
+        count = 2;
+    }
+    if let Some((_, key)) = COUNT_PROPERTIES
+        .iter()
+        .find(|(suffix, _)| name == *suffix || name.ends_with(&format!("_{}", suffix)))
+    {
+        count = property(block, key)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
+    }
+    vec![(item(&name), count)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockId;
+    use std::sync::Arc;
+
+    fn items(name: &str, properties: &[(&str, &str)]) -> Vec<(String, i32)> {
+        block_items(&BlockData {
+            id: BlockId {
+                name: Arc::from(name),
+            },
+            properties: properties
+                .iter()
+                .map(|(k, v)| (Arc::from(*k), Arc::from(*v)))
+                .collect(),
+        })
+    }
+
+    fn one(name: &str) -> Vec<(String, i32)> {
+        vec![(name.to_string(), 1)]
+    }
+
+    #[test]
+    fn renamed_and_wall_variants() {
+        assert_eq!(
+            items("minecraft:redstone_wire", &[]),
+            one("minecraft:redstone")
+        );
+        assert_eq!(items("minecraft:wall_torch", &[]), one("minecraft:torch"));
+        assert_eq!(
+            items("minecraft:soul_wall_torch", &[]),
+            one("minecraft:soul_torch")
+        );
+        assert_eq!(
+            items("minecraft:oak_wall_hanging_sign", &[]),
+            one("minecraft:oak_hanging_sign")
+        );
+        assert_eq!(
+            items("minecraft:dead_tube_coral_wall_fan", &[]),
+            one("minecraft:dead_tube_coral_fan")
+        );
+    }
+
+    #[test]
+    fn counted_states() {
+        assert_eq!(
+            items("minecraft:stone_slab", &[("type", "double")]),
+            vec![("minecraft:stone_slab".to_string(), 2)]
+        );
+        assert_eq!(
+            items("minecraft:red_candle", &[("candles", "3")]),
+            vec![("minecraft:red_candle".to_string(), 3)]
+        );
+        assert_eq!(
+            items("minecraft:snow", &[("layers", "5")]),
+            vec![("minecraft:snow".to_string(), 5)]
+        );
+        assert_eq!(
+            items("minecraft:potted_azalea_bush", &[]),
+            vec![
+                ("minecraft:flower_pot".to_string(), 1),
+                ("minecraft:azalea".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            items("minecraft:white_candle_cake", &[]),
+            vec![
+                ("minecraft:cake".to_string(), 1),
+                ("minecraft:white_candle".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn skipped_states() {
+        assert!(items("minecraft:air", &[]).is_empty());
+        assert!(items("minecraft:piston_head", &[]).is_empty());
+        assert!(items("minecraft:oak_door", &[("half", "upper")]).is_empty());
+        assert_eq!(
+            items("minecraft:oak_door", &[("half", "lower")]),
+            one("minecraft:oak_door")
+        );
+        assert!(items("minecraft:red_bed", &[("part", "head")]).is_empty());
+        assert!(items("minecraft:water", &[("level", "3")]).is_empty());
+        assert_eq!(
+            items("minecraft:water", &[("level", "0")]),
+            one("minecraft:water_bucket")
+        );
+        assert_eq!(items("create:shaft", &[]), one("create:shaft"));
+    }
+}
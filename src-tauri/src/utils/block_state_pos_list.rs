@@ -4,7 +4,7 @@ use std::collections::BTreeMap;
 use std::collections::VecDeque;
 use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Hash, Eq, Serialize, Deserialize, PartialEq)]
 pub struct BlockPos {
     pub x: i32,
     pub y: i32,
@@ -0,0 +1,656 @@
+use crate::utils::block_state_pos_list::{
+    BlockData, BlockId, BlockPos, BlockStatePos, BlockStatePosList,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+const CHUNK_SHIFT: i32 = 4;
+const CHUNK_SIZE: i32 = 1 << CHUNK_SHIFT;
+const CHUNK_MASK: i32 = CHUNK_SIZE - 1;
+const CHUNK_VOLUME: usize = (CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE) as usize;
+const MIN_BITS: u32 = 4;
+
+pub type ChunkKey = (i32, i32, i32);
+
+// 定长位压缩数组, 条目不跨 long (与原版 1.16+ 区块格式一致)
+#[derive(Debug, Clone)]
+pub struct PackedArray {
+    bits: u32,
+    per_long: usize,
+    mask: u64,
+    data: Vec<u64>,
+}
+
+impl PackedArray {
+    pub fn new(bits: u32, len: usize) -> Self {
+        let per_long = (64 / bits) as usize;
+        Self {
+            bits,
+            per_long,
+            mask: (1u64 << bits) - 1,
+            data: vec![0; len.div_ceil(per_long)],
+        }
+    }
+
+    pub fn get(&self, index: usize) -> u32 {
+        let long = self.data[index / self.per_long];
+        let offset = (index % self.per_long) as u32 * self.bits;
+        ((long >> offset) & self.mask) as u32
+    }
+
+    pub fn set(&mut self, index: usize, value: u32) {
+        let offset = (index % self.per_long) as u32 * self.bits;
+        let slot = &mut self.data[index / self.per_long];
+        *slot = (*slot & !(self.mask << offset)) | ((value as u64 & self.mask) << offset);
+    }
+
+    pub fn repack(&self, bits: u32, len: usize) -> Self {
+        let mut packed = Self::new(bits, len);
+        for i in 0..len {
+            packed.set(i, self.get(i));
+        }
+        packed
+    }
+}
+
+fn bits_for(palette_len: usize) -> u32 {
+    let max = palette_len.saturating_sub(1).max(1) as u32;
+    (32 - max.leading_zeros()).max(MIN_BITS)
+}
+
+fn split_pos(pos: BlockPos) -> (ChunkKey, usize) {
+    let key = (
+        pos.x >> CHUNK_SHIFT,
+        pos.y >> CHUNK_SHIFT,
+        pos.z >> CHUNK_SHIFT,
+    );
+    let index = (((pos.y & CHUNK_MASK) * CHUNK_SIZE + (pos.z & CHUNK_MASK)) * CHUNK_SIZE
+        + (pos.x & CHUNK_MASK)) as usize;
+    (key, index)
+}
+
+fn join_pos(key: &ChunkKey, index: usize) -> BlockPos {
+    let index = index as i32;
+    BlockPos {
+        x: (key.0 << CHUNK_SHIFT) + (index & CHUNK_MASK),
+        y: (key.1 << CHUNK_SHIFT) + (index >> (CHUNK_SHIFT * 2)),
+        z: (key.2 << CHUNK_SHIFT) + ((index >> CHUNK_SHIFT) & CHUNK_MASK),
+    }
+}
+
+fn in_box(pos: &BlockPos, min: &BlockPos, max: &BlockPos) -> bool {
+    pos.x >= min.x
+        && pos.y >= min.y
+        && pos.z >= min.z
+        && pos.x <= max.x
+        && pos.y <= max.y
+        && pos.z <= max.z
+}
+
+/// 调色板 + 位压缩索引的方块体积, 以 16³ 区块稀疏存储.
+/// 调色板 0 号固定为空气, 未分配的区块视为全空气;
+/// bounds 记录所有写入过的位置 (包括显式写入的空气), 决定导出时的尺寸.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "BlockStatePosList", into = "BlockStatePosList")]
+pub struct BlockVolume {
+    palette: Vec<Arc<BlockData>>,
+    palette_index: HashMap<Arc<BlockData>, u32>,
+    bits: u32,
+    chunks: HashMap<ChunkKey, PackedArray>,
+    bounds: Option<(BlockPos, BlockPos)>,
+}
+
+impl Default for BlockVolume {
+    fn default() -> Self {
+        let air = Self::air();
+        let mut palette_index = HashMap::new();
+        palette_index.insert(air.clone(), 0);
+        Self {
+            palette: vec![air],
+            palette_index,
+            bits: MIN_BITS,
+            chunks: HashMap::new(),
+            bounds: None,
+        }
+    }
+}
+
+impl BlockVolume {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn air() -> Arc<BlockData> {
+        Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from("minecraft:air"),
+            },
+            properties: BTreeMap::new(),
+        })
+    }
+
+    pub fn palette(&self) -> &[Arc<BlockData>] {
+        &self.palette
+    }
+
+    pub fn bounds(&self) -> Option<(BlockPos, BlockPos)> {
+        self.bounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_none()
+    }
+
+    pub fn contains(&self, pos: &BlockPos) -> bool {
+        self.bounds
+            .map(|(min, max)| in_box(pos, &min, &max))
+            .unwrap_or(false)
+    }
+
+    pub fn volume(&self) -> usize {
+        self.bounds
+            .map(|(min, max)| {
+                ((max.x - min.x + 1) as usize)
+                    * ((max.y - min.y + 1) as usize)
+                    * ((max.z - min.z + 1) as usize)
+            })
+            .unwrap_or(0)
+    }
+
+    fn extend_bounds(&mut self, pos: BlockPos) {
+        self.bounds = Some(match self.bounds {
+            None => (pos, pos),
+            Some((mut min, mut max)) => {
+                min.x = min.x.min(pos.x);
+                min.y = min.y.min(pos.y);
+                min.z = min.z.min(pos.z);
+                max.x = max.x.max(pos.x);
+                max.y = max.y.max(pos.y);
+                max.z = max.z.max(pos.z);
+                (min, max)
+            }
+        });
+    }
+
+    pub fn state_index(&mut self, block: &Arc<BlockData>) -> u32 {
+        if let Some(index) = self.palette_index.get(block) {
+            return *index;
+        }
+        let index = self.palette.len() as u32;
+        self.palette.push(block.clone());
+        self.palette_index.insert(block.clone(), index);
+        let bits = bits_for(self.palette.len());
+        if bits > self.bits {
+            self.bits = bits;
+            self.chunks
+                .par_iter_mut()
+                .for_each(|(_, chunk)| *chunk = chunk.repack(bits, CHUNK_VOLUME));
+        }
+        index
+    }
+
+    pub fn set_index(&mut self, pos: BlockPos, index: u32) {
+        self.extend_bounds(pos);
+        let (key, offset) = split_pos(pos);
+        if index == 0 {
+            if let Some(chunk) = self.chunks.get_mut(&key) {
+                chunk.set(offset, 0);
+            }
+            return;
+        }
+        let bits = self.bits;
+        self.chunks
+            .entry(key)
+            .or_insert_with(|| PackedArray::new(bits, CHUNK_VOLUME))
+            .set(offset, index);
+    }
+
+    pub fn add(&mut self, pos: BlockPos, block: Arc<BlockData>) {
+        let index = self.state_index(&block);
+        self.set_index(pos, index);
+    }
+
+    pub fn add_by_pos(&mut self, x: i32, y: i32, z: i32, block: Arc<BlockData>) {
+        self.add(BlockPos { x, y, z }, block);
+    }
+
+    pub fn get_index(&self, pos: BlockPos) -> u32 {
+        let (key, offset) = split_pos(pos);
+        self.chunks
+            .get(&key)
+            .map(|chunk| chunk.get(offset))
+            .unwrap_or(0)
+    }
+
+    pub fn get(&self, pos: BlockPos) -> &Arc<BlockData> {
+        &self.palette[self.get_index(pos) as usize]
+    }
+
+    /// 按整块区域批量写入, 区块并行构建; index_at 传入相对 origin 的坐标, 返回 palette 下标
+    pub fn fill_region<F>(
+        &mut self,
+        origin: BlockPos,
+        size: BlockPos,
+        palette: &[Arc<BlockData>],
+        index_at: F,
+    ) where
+        F: Fn(i32, i32, i32) -> usize + Sync,
+    {
+        if size.x <= 0 || size.y <= 0 || size.z <= 0 {
+            return;
+        }
+        let remap: Vec<u32> = palette.iter().map(|b| self.state_index(b)).collect();
+        let max = BlockPos {
+            x: origin.x + size.x - 1,
+            y: origin.y + size.y - 1,
+            z: origin.z + size.z - 1,
+        };
+        self.extend_bounds(origin);
+        self.extend_bounds(max);
+
+        let (min_key, _) = split_pos(origin);
+        let (max_key, _) = split_pos(max);
+        let mut keys = Vec::new();
+        for cx in min_key.0..=max_key.0 {
+            for cy in min_key.1..=max_key.1 {
+                for cz in min_key.2..=max_key.2 {
+                    keys.push((cx, cy, cz));
+                }
+            }
+        }
+        let bits = self.bits;
+        let built: Vec<(ChunkKey, PackedArray)> = keys
+            .into_par_iter()
+            .filter_map(|key| {
+                let mut chunk = PackedArray::new(bits, CHUNK_VOLUME);
+                let mut filled = false;
+                for offset in 0..CHUNK_VOLUME {
+                    let pos = join_pos(&key, offset);
+                    if !in_box(&pos, &origin, &max) {
+                        continue;
+                    }
+                    let local = index_at(pos.x - origin.x, pos.y - origin.y, pos.z - origin.z);
+                    let index = remap.get(local).copied().unwrap_or(0);
+                    if index != 0 {
+                        chunk.set(offset, index);
+                        filled = true;
+                    }
+                }
+                filled.then_some((key, chunk))
+            })
+            .collect();
+
+        for (key, chunk) in built {
+            match self.chunks.get_mut(&key) {
+                Some(existing) => {
+                    for offset in 0..CHUNK_VOLUME {
+                        let index = chunk.get(offset);
+                        if index != 0 {
+                            existing.set(offset, index);
+                        }
+                    }
+                }
+                None => {
+                    self.chunks.insert(key, chunk);
+                }
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        let remap: Vec<u32> = other.palette.iter().map(|b| self.state_index(b)).collect();
+        if let Some((min, max)) = other.bounds {
+            self.extend_bounds(min);
+            self.extend_bounds(max);
+        }
+        for (key, chunk) in other.chunks {
+            for offset in 0..CHUNK_VOLUME {
+                let index = chunk.get(offset);
+                if index != 0 {
+                    self.set_index(join_pos(&key, offset), remap[index as usize]);
+                }
+            }
+        }
+    }
+
+    /// 遍历所有非空气方块的位置与调色板下标
+    pub fn iter_indices(&self) -> impl Iterator<Item = (BlockPos, u32)> + '_ {
+        self.chunks.iter().flat_map(|(key, chunk)| {
+            (0..CHUNK_VOLUME).filter_map(move |offset| match chunk.get(offset) {
+                0 => None,
+                index => Some((join_pos(key, offset), index)),
+            })
+        })
+    }
+
+    /// 遍历所有非空气方块
+    pub fn iter(&self) -> impl Iterator<Item = (BlockPos, &Arc<BlockData>)> + '_ {
+        self.iter_indices()
+            .map(move |(pos, index)| (pos, &self.palette[index as usize]))
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (BlockPos, &Arc<BlockData>)> + '_ {
+        self.chunks.par_iter().flat_map_iter(move |(key, chunk)| {
+            (0..CHUNK_VOLUME).filter_map(move |offset| match chunk.get(offset) {
+                0 => None,
+                index => Some((join_pos(key, offset), &self.palette[index as usize])),
+            })
+        })
+    }
+
+    /// 各调色板条目在 bounds 内的数量, 空气包含未分配的区域
+    pub fn count_states(&self) -> Vec<u64> {
+        let Some((min, max)) = self.bounds else {
+            return vec![0; self.palette.len()];
+        };
+        let len = self.palette.len();
+        let mut counts = self
+            .chunks
+            .par_iter()
+            .fold(
+                || vec![0u64; len],
+                |mut acc, (key, chunk)| {
+                    for offset in 0..CHUNK_VOLUME {
+                        let index = chunk.get(offset) as usize;
+                        if index != 0 && in_box(&join_pos(key, offset), &min, &max) {
+                            acc[index] += 1;
+                        }
+                    }
+                    acc
+                },
+            )
+            .reduce(
+                || vec![0u64; len],
+                |mut a, b| {
+                    for (x, y) in a.iter_mut().zip(b) {
+                        *x += y;
+                    }
+                    a
+                },
+            );
+        let solid: u64 = counts.iter().sum();
+        counts[0] = self.volume() as u64 - solid;
+        counts
+    }
+
+    /// 按分组统计各调色板条目的数量, 不含空气, group 返回 None 的方块不计入
+    pub fn count_states_by<F>(&self, groups: usize, group: F) -> Vec<Vec<u64>>
+    where
+        F: Fn(&BlockPos) -> Option<usize> + Sync,
+    {
+        let len = self.palette.len();
+        let Some((min, max)) = self.bounds else {
+            return vec![vec![0; len]; groups];
+        };
+        self.chunks
+            .par_iter()
+            .fold(
+                || vec![vec![0u64; len]; groups],
+                |mut acc, (key, chunk)| {
+                    for offset in 0..CHUNK_VOLUME {
+                        let index = chunk.get(offset) as usize;
+                        if index == 0 {
+                            continue;
+                        }
+                        let pos = join_pos(key, offset);
+                        if !in_box(&pos, &min, &max) {
+                            continue;
+                        }
+                        if let Some(g) = group(&pos).filter(|g| *g < groups) {
+                            acc[g][index] += 1;
+                        }
+                    }
+                    acc
+                },
+            )
+            .reduce(
+                || vec![vec![0u64; len]; groups],
+                |mut a, b| {
+                    for (x, y) in a.iter_mut().zip(b) {
+                        for (p, q) in x.iter_mut().zip(y) {
+                            *p += q;
+                        }
+                    }
+                    a
+                },
+            )
+    }
+
+    pub fn len(&self) -> usize {
+        self.count_states().iter().skip(1).sum::<u64>() as usize
+    }
+
+    /// 按调色板改写方块, 相同结果会合并; 空气被替换时会填满 bounds 内所有空位
+    pub fn map_palette<F>(&mut self, f: F)
+    where
+        F: Fn(&Arc<BlockData>) -> Arc<BlockData> + Sync,
+    {
+        let mapped: Vec<Arc<BlockData>> = self.palette.par_iter().map(&f).collect();
+        let air = mapped[0].clone();
+        let mut palette = vec![Self::air()];
+        let mut palette_index = HashMap::new();
+        palette_index.insert(palette[0].clone(), 0u32);
+        let table: Vec<u32> = mapped
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                if i == 0 {
+                    return 0;
+                }
+                *palette_index.entry(block.clone()).or_insert_with(|| {
+                    palette.push(block.clone());
+                    (palette.len() - 1) as u32
+                })
+            })
+            .collect();
+        self.palette = palette;
+        self.palette_index = palette_index;
+        self.chunks.par_iter_mut().for_each(|(_, chunk)| {
+            for offset in 0..CHUNK_VOLUME {
+                let index = chunk.get(offset);
+                if index != 0 {
+                    chunk.set(offset, table[index as usize]);
+                }
+            }
+        });
+
+        if air.id.name.as_ref() != "minecraft:air" {
+            let Some((min, max)) = self.bounds else {
+                return;
+            };
+            let index = self.state_index(&air);
+            for y in min.y..=max.y {
+                for z in min.z..=max.z {
+                    for x in min.x..=max.x {
+                        let pos = BlockPos { x, y, z };
+                        if self.get_index(pos) == 0 {
+                            self.set_index(pos, index);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 只对 [min, max] 范围 (含边界) 内的方块应用调色板映射, 范围外保持不变
+    pub fn map_palette_in<F>(&mut self, min: BlockPos, max: BlockPos, f: F)
+    where
+        F: Fn(&Arc<BlockData>) -> Arc<BlockData> + Sync,
+    {
+        let Some((self_min, self_max)) = self.bounds else {
+            return;
+        };
+        let mapped: Vec<Arc<BlockData>> = self.palette.par_iter().map(&f).collect();
+        let table: Vec<u32> = mapped.iter().map(|b| self.state_index(b)).collect();
+        for y in min.y.max(self_min.y)..=max.y.min(self_max.y) {
+            for z in min.z.max(self_min.z)..=max.z.min(self_max.z) {
+                for x in min.x.max(self_min.x)..=max.x.min(self_max.x) {
+                    let pos = BlockPos { x, y, z };
+                    let index = self.get_index(pos);
+                    if table[index as usize] != index {
+                        self.set_index(pos, table[index as usize]);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 截取 [min, max] 范围 (含边界) 内的方块, 坐标保持不变
+    pub fn sub_volume(&self, min: BlockPos, max: BlockPos) -> Self {
+        let mut volume = Self {
+            palette: self.palette.clone(),
+            palette_index: self.palette_index.clone(),
+            bits: self.bits,
+            chunks: HashMap::new(),
+            bounds: None,
+        };
+        let Some((self_min, self_max)) = self.bounds else {
+            return volume;
+        };
+        let min = BlockPos {
+            x: min.x.max(self_min.x),
+            y: min.y.max(self_min.y),
+            z: min.z.max(self_min.z),
+        };
+        let max = BlockPos {
+            x: max.x.min(self_max.x),
+            y: max.y.min(self_max.y),
+            z: max.z.min(self_max.z),
+        };
+        if min.x > max.x || min.y > max.y || min.z > max.z {
+            return volume;
+        }
+        volume.bounds = Some((min, max));
+        volume.chunks = self
+            .chunks
+            .par_iter()
+            .filter_map(|(key, chunk)| {
+                let mut sub = PackedArray::new(self.bits, CHUNK_VOLUME);
+                let mut filled = false;
+                for offset in 0..CHUNK_VOLUME {
+                    let index = chunk.get(offset);
+                    if index != 0 && in_box(&join_pos(key, offset), &min, &max) {
+                        sub.set(offset, index);
+                        filled = true;
+                    }
+                }
+                filled.then_some((*key, sub))
+            })
+            .collect();
+        volume
+    }
+
+    pub fn to_list(&self) -> BlockStatePosList {
+        BlockStatePosList {
+            elements: self
+                .iter()
+                .map(|(pos, block)| BlockStatePos::new(pos, block.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl From<BlockStatePosList> for BlockVolume {
+    fn from(list: BlockStatePosList) -> Self {
+        let mut volume = Self::default();
+        for block in list.elements {
+            volume.add(block.pos, block.block);
+        }
+        volume
+    }
+}
+
+impl From<BlockVolume> for BlockStatePosList {
+    fn from(volume: BlockVolume) -> Self {
+        volume.to_list()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(name: &str) -> Arc<BlockData> {
+        Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from(format!("minecraft:{}", name)),
+            },
+            properties: BTreeMap::new(),
+        })
+    }
+
+    fn pos(x: i32, y: i32, z: i32) -> BlockPos {
+        BlockPos { x, y, z }
+    }
+
+    // 跨越区块边界的 20x2x3 区域, 下层为石头, 上层 x 为偶数时为泥土, 其余为空气
+    fn filled() -> BlockVolume {
+        let mut volume = BlockVolume::new();
+        let palette = [BlockVolume::air(), block("stone"), block("dirt")];
+        volume.fill_region(pos(-4, 0, 0), pos(20, 2, 3), &palette, |x, y, _| {
+            match (y, x % 2) {
+                (0, _) => 1,
+                (_, 0) => 2,
+                _ => 0,
+            }
+        });
+        volume
+    }
+
+    #[test]
+    fn fill_region_places_blocks() {
+        let volume = filled();
+        assert_eq!(volume.bounds(), Some((pos(-4, 0, 0), pos(15, 1, 2))));
+        assert_eq!(
+            volume.get(pos(-4, 0, 2)).id.name.as_ref(),
+            "minecraft:stone"
+        );
+        assert_eq!(volume.get(pos(-4, 1, 0)).id.name.as_ref(), "minecraft:dirt");
+        assert_eq!(volume.get_index(pos(-3, 1, 0)), 0);
+        assert_eq!(volume.len(), 60 + 30);
+    }
+
+    #[test]
+    fn count_states_includes_air() {
+        let mut volume = filled();
+        let counts = volume.count_states();
+        let index = |volume: &BlockVolume, name: &str| {
+            volume
+                .palette()
+                .iter()
+                .position(|b| b.id.name.as_ref() == format!("minecraft:{}", name))
+                .unwrap()
+        };
+        assert_eq!(counts[index(&volume, "stone")], 60);
+        assert_eq!(counts[index(&volume, "dirt")], 30);
+        assert_eq!(counts[0], 30);
+        assert_eq!(counts.iter().sum::<u64>(), volume.volume() as u64);
+
+        // 调色板中不再使用的状态计为 0
+        volume.add(pos(0, 5, 0), block("glass"));
+        volume.set_index(pos(0, 5, 0), 0);
+        let counts = volume.count_states();
+        assert_eq!(counts[index(&volume, "glass")], 0);
+        assert_eq!(counts[0], volume.volume() as u64 - 90);
+    }
+
+    #[test]
+    fn count_states_by_groups() {
+        let volume = filled();
+        let stone = 1;
+        let dirt = 2;
+        let by_layer = volume.count_states_by(2, |p| Some(p.y as usize));
+        assert_eq!(by_layer[0][stone], 60);
+        assert_eq!(by_layer[0][dirt], 0);
+        assert_eq!(by_layer[1][dirt], 30);
+        assert_eq!(by_layer[0][0] + by_layer[1][0], 0);
+
+        // 返回 None 或越界的分组不计入
+        let west = volume.count_states_by(1, |p| (p.x < 0).then_some(0));
+        assert_eq!(west[0][stone], 4 * 3);
+        let none = volume.count_states_by(1, |_| Some(3));
+        assert!(none[0].iter().all(|n| *n == 0));
+    }
+}
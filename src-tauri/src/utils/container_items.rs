@@ -0,0 +1,310 @@
+use crate::utils::tile_entities::TileEntitiesList;
+use fastnbt::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 方块实体中存放物品的字段: 箱子类容器、唱片机、讲台、饰纹陶罐
+const ITEM_LIST_KEYS: [&str; 1] = ["Items"];
+const SINGLE_ITEM_KEYS: [&str; 3] = ["RecordItem", "Book", "item"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContentItem {
+    pub id: String,
+    // 附魔、自定义名称等物品 NBT, 不同 NBT 的物品分开统计
+    pub nbt: Option<String>,
+    pub count: i64,
+}
+
+type ItemKey = (String, Option<String>);
+
+fn compound_get<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    match value {
+        Value::Compound(c) => c.get(key),
+        _ => None,
+    }
+}
+
+fn as_list(value: Option<&Value>) -> &[Value] {
+    match value {
+        Some(Value::List(list)) => list,
+        _ => &[],
+    }
+}
+
+fn item_count(item: &Value) -> i64 {
+    // 1.20.5 前为 Count (byte), 之后为 count (int)
+    compound_get(item, "count")
+        .or_else(|| compound_get(item, "Count"))
+        .and_then(|v| match v {
+            Value::Byte(n) => Some(*n as i64),
+            Value::Short(n) => Some(*n as i64),
+            Value::Int(n) => Some(*n as i64),
+            Value::Long(n) => Some(*n),
+            _ => None,
+        })
+        .unwrap_or(1)
+}
+
+// 物品内嵌的物品 (潜影盒、收纳袋), 返回去掉内嵌内容后的 NBT
+fn split_nested(item: &Value) -> (Option<Value>, Vec<Value>) {
+    let mut nested = Vec::new();
+    if let Some(Value::Compound(components)) = compound_get(item, "components") {
+        let mut components = components.clone();
+        if let Some(Value::List(slots)) = components.remove("minecraft:container") {
+            nested.extend(
+                slots
+                    .iter()
+                    .filter_map(|slot| compound_get(slot, "item"))
+                    .cloned(),
+            );
+        }
+        if let Some(Value::List(items)) = components.remove("minecraft:bundle_contents") {
+            nested.extend(items);
+        }
+        let tag = (!components.is_empty()).then_some(Value::Compound(components));
+        return (tag, nested);
+    }
+    if let Some(Value::Compound(tag)) = compound_get(item, "tag") {
+        let mut tag = tag.clone();
+        if let Some(Value::List(items)) = tag.remove("Items") {
+            nested.extend(items);
+        }
+        if let Some(Value::Compound(mut block_entity)) = tag.remove("BlockEntityTag") {
+            if let Some(Value::List(items)) = block_entity.remove("Items") {
+                nested.extend(items);
+            }
+            block_entity.remove("id");
+            if !block_entity.is_empty() {
+                tag.insert("BlockEntityTag".to_string(), Value::Compound(block_entity));
+            }
+        }
+        let tag = (!tag.is_empty()).then_some(Value::Compound(tag));
+        return (tag, nested);
+    }
+    (None, nested)
+}
+
+fn add_item(item: &Value, multiplier: i64, acc: &mut HashMap<ItemKey, i64>) {
+    let Some(Value::String(id)) = compound_get(item, "id") else {
+        return;
+    };
+    if id == "minecraft:air" {
+        return;
+    }
+    let count = item_count(item) * multiplier;
+    let (tag, nested) = split_nested(item);
+    let nbt = tag.as_ref().map(canonical_snbt);
+    *acc.entry((id.clone(), nbt)).or_insert(0) += count;
+    for inner in &nested {
+        add_item(inner, count, acc);
+    }
+}
+
+fn into_contents(acc: HashMap<ItemKey, i64>) -> Vec<ContentItem> {
+    let mut contents: Vec<ContentItem> = acc
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|((id, nbt), count)| ContentItem { id, nbt, count })
+        .collect();
+    contents.sort_by(|a, b| (&a.id, &a.nbt).cmp(&(&b.id, &b.nbt)));
+    contents
+}
+
+// 玩家数据 / 容器 / 物品 NBT 中的物品: 玩家背包与末影箱、容器 Items、
+// 结构文件中各方块实体的 Items, 根节点本身是物品 (如潜影盒) 时计入它与内嵌物品
+fn collect_items<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    let Value::Compound(c) = value else {
+        return;
+    };
+    if c.contains_key("id") && (c.contains_key("Count") || c.contains_key("count")) {
+        out.push(value);
+        return;
+    }
+    for key in ["Inventory", "EnderItems", "Items"] {
+        out.extend(as_list(c.get(key)));
+    }
+    if let Some(player) = c.get("Data").and_then(|data| compound_get(data, "Player")) {
+        collect_items(player, out);
+    }
+    for block in as_list(c.get("blocks")) {
+        if let Some(nbt) = compound_get(block, "nbt") {
+            collect_items(nbt, out);
+        }
+    }
+}
+
+pub fn nbt_inventory(root: &Value) -> Vec<ContentItem> {
+    let mut items = Vec::new();
+    collect_items(root, &mut items);
+    let mut acc = HashMap::new();
+    for item in items {
+        add_item(item, 1, &mut acc);
+    }
+    into_contents(acc)
+}
+
+// 统计所有容器方块实体中的物品, 包括潜影盒内的物品
+pub fn container_contents(tiles: &TileEntitiesList) -> Vec<ContentItem> {
+    let mut acc = HashMap::new();
+    for tile in &tiles.elements {
+        for key in ITEM_LIST_KEYS {
+            for item in as_list(compound_get(&tile.nbt, key)) {
+                add_item(item, 1, &mut acc);
+            }
+        }
+        for key in SINGLE_ITEM_KEYS {
+            if let Some(item @ Value::Compound(_)) = compound_get(&tile.nbt, key) {
+                add_item(item, 1, &mut acc);
+            }
+        }
+    }
+    into_contents(acc)
+}
+
+// 键按字典序排列的 SNBT, 保证相同 NBT 得到相同字符串
+pub fn canonical_snbt(value: &Value) -> String {
+    fn join<T>(items: impl Iterator<Item = T>, f: impl Fn(T) -> String) -> String {
+        items.map(f).collect::<Vec<_>>().join(",")
+    }
+    match value {
+        Value::Byte(n) => format!("{}b", n),
+        Value::Short(n) => format!("{}s", n),
+        Value::Int(n) => n.to_string(),
+        Value::Long(n) => format!("{}L", n),
+        Value::Float(n) => format!("{}f", n),
+        Value::Double(n) => format!("{}d", n),
+        Value::String(s) => serde_json::to_string(s).unwrap_or_default(),
+        Value::ByteArray(a) => format!("[B;{}]", join(a.iter(), |n| format!("{}b", n))),
+        Value::IntArray(a) => format!("[I;{}]", join(a.iter(), |n| n.to_string())),
+        Value::LongArray(a) => format!("[L;{}]", join(a.iter(), |n| format!("{}L", n))),
+        Value::List(list) => format!("[{}]", join(list.iter(), canonical_snbt)),
+        Value::Compound(c) => {
+            let mut keys: Vec<&String> = c.keys().collect();
+            keys.sort();
+            format!(
+                "{{{}}}",
+                join(keys.into_iter(), |k| format!(
+                    "{}:{}",
+                    k,
+                    canonical_snbt(&c[k])
+                ))
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockPos;
+
+    fn compound(entries: Vec<(&str, Value)>) -> Value {
+        Value::Compound(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    fn item(id: &str, count: i8) -> Value {
+        compound(vec![
+            ("id", Value::String(id.to_string())),
+            ("Count", Value::Byte(count)),
+        ])
+    }
+
+    fn contents(tiles: Vec<Value>) -> Vec<(String, Option<String>, i64)> {
+        let mut list = TileEntitiesList::new();
+        for (x, nbt) in tiles.into_iter().enumerate() {
+            list.add(
+                BlockPos {
+                    x: x as i32,
+                    y: 0,
+                    z: 0,
+                },
+                nbt,
+            );
+        }
+        container_contents(&list)
+            .into_iter()
+            .map(|item| (item.id, item.nbt, item.count))
+            .collect()
+    }
+
+    #[test]
+    fn legacy_shulker_box_contents() {
+        let shulker = compound(vec![
+            ("id", Value::String("minecraft:red_shulker_box".to_string())),
+            ("Count", Value::Byte(2)),
+            (
+                "tag",
+                compound(vec![(
+                    "BlockEntityTag",
+                    compound(vec![
+                        ("id", Value::String("minecraft:shulker_box".to_string())),
+                        ("Items", Value::List(vec![item("minecraft:stone", 64)])),
+                    ]),
+                )]),
+            ),
+        ]);
+        let chest = compound(vec![
+            ("id", Value::String("minecraft:chest".to_string())),
+            (
+                "Items",
+                Value::List(vec![shulker, item("minecraft:stone", 3)]),
+            ),
+        ]);
+        assert_eq!(
+            contents(vec![chest]),
+            vec![
+                ("minecraft:red_shulker_box".to_string(), None, 2),
+                ("minecraft:stone".to_string(), None, 131),
+            ]
+        );
+    }
+
+    #[test]
+    fn component_container_contents() {
+        let slot = |item: Value| compound(vec![("slot", Value::Int(0)), ("item", item)]);
+        let inner = compound(vec![
+            ("id", Value::String("minecraft:bundle".to_string())),
+            ("count", Value::Int(1)),
+            (
+                "components",
+                compound(vec![(
+                    "minecraft:bundle_contents",
+                    Value::List(vec![item("minecraft:dirt", 5)]),
+                )]),
+            ),
+        ]);
+        let shulker = compound(vec![
+            ("id", Value::String("minecraft:shulker_box".to_string())),
+            ("count", Value::Int(3)),
+            (
+                "components",
+                compound(vec![(
+                    "minecraft:container",
+                    Value::List(vec![slot(inner), slot(item("minecraft:dirt", 1))]),
+                )]),
+            ),
+        ]);
+        let barrel = compound(vec![
+            ("id", Value::String("minecraft:barrel".to_string())),
+            ("Items", Value::List(vec![shulker])),
+        ]);
+        let jukebox = compound(vec![
+            ("id", Value::String("minecraft:jukebox".to_string())),
+            ("RecordItem", item("minecraft:music_disc_cat", 1)),
+        ]);
+        assert_eq!(
+            contents(vec![barrel, jukebox]),
+            vec![
+                ("minecraft:bundle".to_string(), None, 3),
+                ("minecraft:dirt".to_string(), None, 18),
+                ("minecraft:music_disc_cat".to_string(), None, 1),
+                ("minecraft:shulker_box".to_string(), None, 3),
+            ]
+        );
+    }
+}
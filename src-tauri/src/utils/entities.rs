@@ -0,0 +1,179 @@
+use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::block_volume::BlockVolume;
+use fastnbt::Value;
+use serde::{Deserialize, Serialize};
+
+// TileX/Y/Z 与实体所在方块相差超过此距离时, 视为世界坐标
+const TILE_RANGE: i32 = 2;
+
+// 实体坐标为浮点, 与方块处于同一坐标系; nbt 保留 id, 去掉 Pos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entity {
+    pub pos: [f64; 3],
+    pub nbt: Value,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EntitiesList {
+    pub elements: Vec<Entity>,
+}
+
+impl Entity {
+    pub fn new(pos: [f64; 3], nbt: Value) -> Self {
+        Self { pos, nbt }
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        match &self.nbt {
+            Value::Compound(c) => c.get("id").and_then(Value::as_str),
+            _ => None,
+        }
+    }
+
+    pub fn block_pos(&self) -> BlockPos {
+        BlockPos {
+            x: self.pos[0].floor() as i32,
+            y: self.pos[1].floor() as i32,
+            z: self.pos[2].floor() as i32,
+        }
+    }
+
+    pub fn relative_to(&self, origin: &BlockPos) -> [f64; 3] {
+        [
+            self.pos[0] - origin.x as f64,
+            self.pos[1] - origin.y as f64,
+            self.pos[2] - origin.z as f64,
+        ]
+    }
+}
+
+impl EntitiesList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, pos: [f64; 3], nbt: Value) {
+        self.elements.push(Entity::new(pos, nbt));
+    }
+
+    // 读取文件时使用: raw_pos 为文件中的 Pos, origin 为其坐标系原点;
+    // 悬挂实体的 TileX/Y/Z 一并换算到与 pos 相同的坐标系, 之后的变换才能正确移动
+    pub fn add_read(&mut self, raw_pos: [f64; 3], origin: BlockPos, nbt: Value) {
+        let pos = [
+            raw_pos[0] + origin.x as f64,
+            raw_pos[1] + origin.y as f64,
+            raw_pos[2] + origin.z as f64,
+        ];
+        let nbt = normalize_tile(nbt, raw_pos, origin);
+        self.add(pos, nbt);
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.elements.extend(other.elements);
+    }
+
+    pub fn filter_by_blocks(&self, blocks: &BlockVolume) -> Self {
+        Self {
+            elements: self
+                .elements
+                .iter()
+                .filter(|e| blocks.contains(&e.block_pos()))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+pub fn read_pos(value: Option<&Value>) -> Option<[f64; 3]> {
+    let Some(Value::List(list)) = value else {
+        return None;
+    };
+    let coords: Vec<f64> = list
+        .iter()
+        .filter_map(|v| match v {
+            Value::Double(n) => Some(*n),
+            Value::Float(n) => Some(*n as f64),
+            Value::Int(n) => Some(*n as f64),
+            _ => None,
+        })
+        .collect();
+    if coords.len() != 3 {
+        return None;
+    }
+    Some([coords[0], coords[1], coords[2]])
+}
+
+// TileX/Y/Z 与 Pos 同一坐标系时按原点平移, 否则 (世界坐标) 取实体所在方块
+fn normalize_tile(nbt: Value, raw_pos: [f64; 3], origin: BlockPos) -> Value {
+    let Value::Compound(mut compound) = nbt else {
+        return nbt;
+    };
+    let (Some(Value::Int(x)), Some(Value::Int(y)), Some(Value::Int(z))) = (
+        compound.get("TileX"),
+        compound.get("TileY"),
+        compound.get("TileZ"),
+    ) else {
+        return Value::Compound(compound);
+    };
+    let tile = [*x, *y, *z];
+    let block = raw_pos.map(|n| n.floor() as i32);
+    let same_frame = (0..3).all(|i| (tile[i] - block[i]).abs() <= TILE_RANGE);
+    let base = if same_frame { tile } else { block };
+    compound.insert("TileX".to_string(), Value::Int(base[0] + origin.x));
+    compound.insert("TileY".to_string(), Value::Int(base[1] + origin.y));
+    compound.insert("TileZ".to_string(), Value::Int(base[2] + origin.z));
+    Value::Compound(compound)
+}
+
+pub fn pos_value(pos: [f64; 3]) -> Value {
+    Value::List(pos.iter().map(|n| Value::Double(*n)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn frame(tile: [i32; 3]) -> Value {
+        let mut nbt = HashMap::new();
+        nbt.insert("TileX".to_string(), Value::Int(tile[0]));
+        nbt.insert("TileY".to_string(), Value::Int(tile[1]));
+        nbt.insert("TileZ".to_string(), Value::Int(tile[2]));
+        Value::Compound(nbt)
+    }
+
+    fn tile(entity: &Entity) -> [i32; 3] {
+        let Value::Compound(nbt) = &entity.nbt else {
+            panic!("entity nbt is not a compound");
+        };
+        ["TileX", "TileY", "TileZ"].map(|key| match nbt.get(key) {
+            Some(Value::Int(n)) => *n,
+            _ => panic!("missing {}", key),
+        })
+    }
+
+    #[test]
+    fn tile_follows_pos() {
+        let origin = BlockPos {
+            x: 10,
+            y: 64,
+            z: -5,
+        };
+        let mut entities = EntitiesList::new();
+        // 与 Pos 同一坐标系
+        entities.add_read([1.5, 2.5, 0.25], origin, frame([1, 2, 0]));
+        // 世界坐标, 取实体所在方块
+        entities.add_read([1.5, 2.5, 0.25], origin, frame([301, 70, -200]));
+        assert_eq!(entities.elements[0].pos, [11.5, 66.5, -4.75]);
+        assert_eq!(tile(&entities.elements[0]), [11, 66, -5]);
+        assert_eq!(tile(&entities.elements[1]), [11, 66, -5]);
+    }
+}
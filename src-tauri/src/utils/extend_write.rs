@@ -17,3 +17,10 @@ pub fn to_writer_gzip(
 
     Ok(())
 }
+
+pub fn to_bytes_gzip(data: &impl serde::Serialize) -> Result<Vec<u8>, SchematicError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    let bytes = fastnbt::to_bytes(&data)?;
+    encoder.write_all(&bytes)?;
+    Ok(encoder.finish()?)
+}
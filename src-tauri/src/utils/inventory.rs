@@ -0,0 +1,231 @@
+use crate::utils::block_state_pos_list::BlockId;
+use crate::utils::container_items::nbt_inventory;
+use crate::utils::material_export::csv_records;
+use crate::utils::requirements::Requirements;
+use anyhow::{anyhow, Context, Result};
+use fastnbt::Value;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    pub id: String,
+    // 与材料清单相同的分类: block / support / contents
+    pub category: String,
+    pub have: i64,
+    pub need: i64,
+    pub missing: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryComparison {
+    pub schematic_id: i64,
+    // 导入的文件路径
+    pub source: String,
+    pub updated_at: String,
+    pub entries: Vec<InventoryEntry>,
+}
+
+fn item_id(id: &str) -> BlockId {
+    let id = id.trim();
+    let name = if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    };
+    BlockId {
+        name: Arc::from(name),
+    }
+}
+
+// 支持玩家 .dat、容器/潜影盒 NBT (可为 gzip 压缩) 与物品数量 CSV
+pub fn read_inventory(path: &Path) -> Result<Requirements> {
+    let data = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let is_csv = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        let text = String::from_utf8_lossy(&data);
+        return parse_csv(text.trim_start_matches('\u{feff}'));
+    }
+    let root: Value = if data.starts_with(&[0x1f, 0x8b]) {
+        fastnbt::from_reader(GzDecoder::new(Cursor::new(&data)))?
+    } else {
+        fastnbt::from_bytes(&data)?
+    };
+    let mut inventory = Requirements::new();
+    // 物品 NBT 不影响能否用于建造, 按 ID 合并
+    for item in nbt_inventory(&root) {
+        inventory.add_requirement(item_id(&item.id), item.count as i32);
+    }
+    Ok(inventory)
+}
+
+// 有表头时按 id / count 列读取 (兼容材料清单导出的 CSV), 否则取前两列
+pub fn parse_csv(text: &str) -> Result<Requirements> {
+    let mut records = csv_records(text).into_iter().peekable();
+    let mut columns = (0, 1);
+    if let Some(header) = records.peek() {
+        let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+        let id = header.iter().position(|h| h == "id" || h == "item");
+        let count = header
+            .iter()
+            .position(|h| h == "count" || h == "total" || h == "have");
+        if let (Some(id), Some(count)) = (id, count) {
+            columns = (id, count);
+            records.next();
+        }
+    }
+    let mut inventory = Requirements::new();
+    for (index, fields) in records.enumerate() {
+        let (Some(id), Some(count)) = (fields.get(columns.0), fields.get(columns.1)) else {
+            continue;
+        };
+        let id = id.trim();
+        let Ok(count) = count.trim().parse::<i32>() else {
+            // 第一行为无法识别的表头时跳过
+            if index == 0 {
+                continue;
+            }
+            return Err(anyhow!(
+                "invalid count on row {}: {}",
+                index + 1,
+                fields.join(",")
+            ));
+        };
+        if id.is_empty() {
+            continue;
+        }
+        inventory.add_requirement(item_id(id), count);
+    }
+    Ok(inventory)
+}
+
+pub fn inventory_to_string(inventory: &Requirements) -> Result<String> {
+    let map: HashMap<&str, i32> = inventory
+        .get_requirements()
+        .iter()
+        .map(|(id, &count)| (id.name.as_ref(), count))
+        .collect();
+    Ok(serde_json::to_string(&map)?)
+}
+
+pub fn inventory_from_str(json: &str) -> Result<Requirements> {
+    let map: HashMap<String, i32> = serde_json::from_str(json)?;
+    let mut inventory = Requirements::new();
+    for (id, count) in map {
+        inventory.add_requirement(item_id(&id), count);
+    }
+    Ok(inventory)
+}
+
+// 只列出蓝图需要的物品, 缺少最多的排在前面
+// 背包物品依次分给方块、辅助材料与容器物品, 同一物品不会被重复计入
+pub fn compare_inventory(need: &Requirements, have: &Requirements) -> Vec<InventoryEntry> {
+    let mut remaining: HashMap<BlockId, i64> = have
+        .get_requirements()
+        .iter()
+        .map(|(id, &count)| (id.clone(), count.max(0) as i64))
+        .collect();
+    // 容器物品按 ID 合并, 与读取背包时一致
+    let mut contents: HashMap<BlockId, i32> = HashMap::new();
+    for item in need.get_contents() {
+        let count = item.count.clamp(0, i32::MAX as i64) as i32;
+        let total = contents.entry(item_id(&item.id)).or_insert(0);
+        *total = total.saturating_add(count);
+    }
+    let categories = [
+        ("block", need.get_requirements()),
+        ("support", need.get_support()),
+        ("contents", &contents),
+    ];
+    let mut entries = Vec::new();
+    for (category, items) in categories {
+        let mut items: Vec<(&BlockId, &i32)> =
+            items.iter().filter(|(_, &count)| count > 0).collect();
+        items.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        for (id, &count) in items {
+            let need = count as i64;
+            let left = remaining.get_mut(id);
+            let have = left.as_ref().map_or(0, |left| need.min(**left));
+            if let Some(left) = left {
+                *left -= have;
+            }
+            entries.push(InventoryEntry {
+                id: id.name.to_string(),
+                category: category.to_string(),
+                have,
+                need,
+                missing: need - have,
+            });
+        }
+    }
+    entries.sort_by(|a, b| b.missing.cmp(&a.missing).then_with(|| a.id.cmp(&b.id)));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quoted_fields() {
+        let text = "id,name,count\r\n\
+                    minecraft:stone,\"Stone, smooth\",64\r\n\
+                    \r\n\
+                    \"oak_log\",\"a \"\"log\"\"\nsecond line\",\"12\"\n";
+        let inventory = parse_csv(text).unwrap();
+        assert_eq!(inventory.get_requirements().len(), 2);
+        assert_eq!(
+            inventory.get_requirement(&item_id("minecraft:stone")),
+            Some(&64)
+        );
+        assert_eq!(inventory.get_requirement(&item_id("oak_log")), Some(&12));
+    }
+
+    #[test]
+    fn csv_without_header() {
+        let inventory = parse_csv("dirt,3\ndirt,4").unwrap();
+        assert_eq!(inventory.get_requirement(&item_id("dirt")), Some(&7));
+        assert!(parse_csv("dirt,3\nstone,x").is_err());
+    }
+
+    #[test]
+    fn compares_support_and_contents() {
+        let need = Requirements::from_requirement_str(
+            r#"{
+                "minecraft:stone": {"id": "minecraft:stone", "zh_cn": "", "num": 50},
+                "support:minecraft:stone":
+                    {"id": "minecraft:stone", "zh_cn": "", "num": 20, "category": "support"},
+                "contents:minecraft:diamond":
+                    {"id": "minecraft:diamond", "zh_cn": "", "num": 3, "category": "contents"},
+                "contents:minecraft:diamond{a}": {
+                    "id": "minecraft:diamond", "zh_cn": "", "num": 2,
+                    "category": "contents", "nbt": "{a}"
+                },
+                "raw:minecraft:stone":
+                    {"id": "minecraft:stone", "zh_cn": "", "num": 70, "category": "raw"}
+            }"#,
+        )
+        .unwrap();
+        let have = parse_csv("stone,64\ndiamond,9").unwrap();
+        let entries = compare_inventory(&need, &have);
+        let entry = |category: &str, id: &str| {
+            entries
+                .iter()
+                .find(|e| e.category == category && e.id == id)
+                .map(|e| (e.have, e.need, e.missing))
+        };
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entry("block", "minecraft:stone"), Some((50, 50, 0)));
+        // 方块已用去 50 个, 辅助材料只剩 14 个
+        assert_eq!(entry("support", "minecraft:stone"), Some((14, 20, 6)));
+        assert_eq!(entry("contents", "minecraft:diamond"), Some((5, 5, 0)));
+    }
+}
@@ -0,0 +1,799 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos};
+use crate::utils::minecraft_data::map_art_data::{BlockColorData, MapArtsData};
+use crate::utils::schematic_data::Size;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{ImageFormat, Rgb, RgbImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Cursor;
+use std::sync::Arc;
+
+// 一张地图的边长
+pub const MAP_SIZE: u32 = 128;
+// 单边最多的地图数量, 避免超大图片占满内存
+const MAX_MAPS: u32 = 16;
+// 低于该透明度的像素不放置方块
+const ALPHA_THRESHOLD: u8 = 128;
+// 有序抖动的幅度 (RGB)
+const ORDERED_SPREAD: f32 = 48.0;
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+// 顶部参考行使用的方块
+const NOOBLINE_BLOCK: &str = "minecraft:stone";
+// 透明像素之后的方块北侧没有更高的方块, 游戏中总是渲染为亮色
+const SEGMENT_START_SHADE: Shade = Shade::High;
+// 受重力影响的方块
+const GRAVITY_BLOCKS: [&str; 11] = [
+    "sand",
+    "red_sand",
+    "suspicious_sand",
+    "gravel",
+    "suspicious_gravel",
+    "anvil",
+    "chipped_anvil",
+    "damaged_anvil",
+    "dragon_egg",
+    "pointed_dripstone",
+    "scaffolding",
+];
+// 不能悬空放置的方块
+const ATTACHED_BLOCKS: [&str; 1] = ["snow"];
+const ATTACHED_SUFFIXES: [&str; 2] = ["_carpet", "_pressure_plate"];
+
+// 数据表中的方块名 -> 实际放置的方块 id
+pub type BlockSelection = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MapArtMode {
+    // 平面, 只使用 normal 亮度
+    #[default]
+    Flat,
+    // 阶梯, 相邻方块高度差固定为 1
+    Staircase,
+    // 阶梯的最低高度版本, 下降段可任意落差
+    Valley,
+}
+
+// 地图上的亮度由北侧 (z - 1) 方块的高度决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Shade {
+    // 比北侧方块低
+    Low,
+    // 与北侧方块等高
+    Normal,
+    // 比北侧方块高
+    High,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dithering {
+    #[default]
+    None,
+    FloydSteinberg,
+    Atkinson,
+    Ordered,
+}
+
+fn default_support_block() -> String {
+    "minecraft:cobblestone".to_string()
+}
+
+fn default_noobline() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportOptions {
+    // 支撑方块 id, 可省略命名空间
+    #[serde(default = "default_support_block")]
+    pub block: String,
+    // 在北侧边缘添加参考行, 使第一行的亮度可控
+    #[serde(default = "default_noobline")]
+    pub noobline: bool,
+}
+
+impl Default for SupportOptions {
+    fn default() -> Self {
+        Self {
+            block: default_support_block(),
+            noobline: default_noobline(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapArtOptions {
+    // 横向 / 纵向的地图数量
+    pub maps_x: u32,
+    pub maps_y: u32,
+    #[serde(default)]
+    pub mode: MapArtMode,
+    #[serde(default)]
+    pub dithering: Dithering,
+    // 可用方块 (数据表中的方块名), 为空时使用调色板配置中的全部方块
+    #[serde(default)]
+    pub blocks: Vec<String>,
+    #[serde(default)]
+    pub support: SupportOptions,
+}
+
+// 创建蓝图时由后端添加支撑方块, 参考行位置取自 generate_map_art 的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MapArtSupport {
+    #[serde(default)]
+    pub mode: MapArtMode,
+    #[serde(default)]
+    pub options: SupportOptions,
+    #[serde(default)]
+    pub references: Vec<BlockPos>,
+}
+
+impl MapArtSupport {
+    // 阶梯模式下全部方块都要垫支撑
+    pub fn apply(&self, blocks: &mut Vec<BlockStatePos>) -> Result<BTreeMap<String, u64>> {
+        apply_support(
+            blocks,
+            &self.references,
+            &self.options,
+            self.mode != MapArtMode::Flat,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapArtResult {
+    // 地图画方块, 不含支撑方块与参考行
+    pub blocks: Vec<BlockStatePos>,
+    // 参考行位置, 与 blocks 使用同一坐标
+    pub references: Vec<BlockPos>,
+    pub size: Size,
+    // 各方块数量, 不含支撑方块与参考行
+    pub counts: BTreeMap<String, u64>,
+    // 预计添加的支撑方块与参考行数量, 创建蓝图时由后端重新添加
+    pub support: BTreeMap<String, u64>,
+    // data:image/png;base64 预览图
+    pub preview: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Lab {
+    pub l: f64,
+    pub a: f64,
+    pub b: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub block: Arc<BlockData>,
+    pub shade: Shade,
+    pub rgb: [u8; 3],
+    pub lab: Lab,
+}
+
+fn srgb_to_linear(c: f64) -> f64 {
+    let c = c / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn rgb_to_lab(rgb: [f64; 3]) -> Lab {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    // D65 白点
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    Lab {
+        l: 116.0 * fy - 16.0,
+        a: 500.0 * (fx - fy),
+        b: 200.0 * (fy - fz),
+    }
+}
+
+// CIEDE2000 色差
+pub fn ciede2000(lab1: &Lab, lab2: &Lab) -> f64 {
+    let pow25_7 = 25f64.powi(7);
+    let c1 = lab1.a.hypot(lab1.b);
+    let c2 = lab2.a.hypot(lab2.b);
+    let c_bar7 = ((c1 + c2) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c_bar7 / (c_bar7 + pow25_7)).sqrt());
+    let a1 = (1.0 + g) * lab1.a;
+    let a2 = (1.0 + g) * lab2.a;
+    let c1p = a1.hypot(lab1.b);
+    let c2p = a2.hypot(lab2.b);
+    let hue = |b: f64, a: f64| {
+        if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees().rem_euclid(360.0)
+        }
+    };
+    let h1p = hue(lab1.b, a1);
+    let h2p = hue(lab2.b, a2);
+
+    let dl = lab2.l - lab1.l;
+    let dc = c2p - c1p;
+    let dh = if c1p * c2p == 0.0 {
+        0.0
+    } else if (h2p - h1p).abs() <= 180.0 {
+        h2p - h1p
+    } else if h2p - h1p > 180.0 {
+        h2p - h1p - 360.0
+    } else {
+        h2p - h1p + 360.0
+    };
+    let dh_big = 2.0 * (c1p * c2p).sqrt() * (dh / 2.0).to_radians().sin();
+
+    let l_bar = (lab1.l + lab2.l) / 2.0;
+    let c_bar_p = (c1p + c2p) / 2.0;
+    let h_bar = if c1p * c2p == 0.0 {
+        h1p + h2p
+    } else if (h1p - h2p).abs() <= 180.0 {
+        (h1p + h2p) / 2.0
+    } else if h1p + h2p < 360.0 {
+        (h1p + h2p + 360.0) / 2.0
+    } else {
+        (h1p + h2p - 360.0) / 2.0
+    };
+    let t = 1.0 - 0.17 * (h_bar - 30.0).to_radians().cos()
+        + 0.24 * (2.0 * h_bar).to_radians().cos()
+        + 0.32 * (3.0 * h_bar + 6.0).to_radians().cos()
+        - 0.20 * (4.0 * h_bar - 63.0).to_radians().cos();
+    let d_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let c_bar_p7 = c_bar_p.powi(7);
+    let rc = 2.0 * (c_bar_p7 / (c_bar_p7 + pow25_7)).sqrt();
+    let l50 = (l_bar - 50.0).powi(2);
+    let sl = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+    let sc = 1.0 + 0.045 * c_bar_p;
+    let sh = 1.0 + 0.015 * c_bar_p * t;
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    let (l, c, h) = (dl / sl, dc / sc, dh_big / sh);
+    (l * l + c * c + h * h + rt * c * h).max(0.0).sqrt()
+}
+
+fn block_state(id: &str) -> Arc<BlockData> {
+    let name = if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    };
+    Arc::new(BlockData {
+        id: BlockId {
+            name: Arc::from(name),
+        },
+        properties: BTreeMap::new(),
+    })
+}
+
+impl Shade {
+    fn color(self, color: &BlockColorData) -> &[u8] {
+        match self {
+            Shade::Low => &color.low,
+            Shade::Normal => &color.normal,
+            Shade::High => &color.high,
+        }
+    }
+
+    // 相对北侧方块的高度变化 (阶梯模式)
+    fn step(self) -> i32 {
+        match self {
+            Shade::Low => -1,
+            Shade::Normal => 0,
+            Shade::High => 1,
+        }
+    }
+}
+
+impl MapArtMode {
+    fn shades(self) -> &'static [Shade] {
+        match self {
+            MapArtMode::Flat => &[Shade::Normal],
+            MapArtMode::Staircase | MapArtMode::Valley => &[Shade::Low, Shade::Normal, Shade::High],
+        }
+    }
+}
+
+// 数据表中的全部方块, 按名称放置
+pub fn select_all(data: &MapArtsData) -> BlockSelection {
+    data.categories
+        .values()
+        .flat_map(|items| items.keys())
+        .map(|name| (name.clone(), name.clone()))
+        .collect()
+}
+
+// 每个方块按可用亮度展开, 颜色相同的条目只保留一个
+pub fn build_palette(
+    data: &MapArtsData,
+    selection: &BlockSelection,
+    shades: &[Shade],
+) -> Vec<PaletteEntry> {
+    let mut candidates: Vec<(&String, &String, Shade, [u8; 3])> = data
+        .categories
+        .iter()
+        .flat_map(|(category, items)| {
+            items.iter().flat_map(move |(name, color)| {
+                shades.iter().filter_map(move |&shade| {
+                    let rgb: [u8; 3] = shade.color(color).get(..3)?.try_into().ok()?;
+                    Some((category, selection.get(name)?, shade, rgb))
+                })
+            })
+        })
+        .collect();
+    // 同色时优先 normal, 减少高度变化
+    candidates.sort_by(|a, b| {
+        (a.2 != Shade::Normal, a.0, a.1, a.2).cmp(&(b.2 != Shade::Normal, b.0, b.1, b.2))
+    });
+    let mut palette: Vec<PaletteEntry> = Vec::new();
+    for (_, id, shade, rgb) in candidates {
+        if palette.iter().any(|entry| entry.rgb == rgb) {
+            continue;
+        }
+        palette.push(PaletteEntry {
+            block: block_state(id),
+            shade,
+            rgb,
+            lab: rgb_to_lab(rgb.map(f64::from)),
+        });
+    }
+    palette
+}
+
+// 只在指定亮度的条目中匹配, 返回调色板下标
+struct Matcher<'a> {
+    palette: &'a [PaletteEntry],
+    candidates: Vec<usize>,
+    cache: HashMap<[u8; 3], usize>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(palette: &'a [PaletteEntry], shades: &[Shade]) -> Self {
+        Self {
+            palette,
+            candidates: (0..palette.len())
+                .filter(|&i| shades.contains(&palette[i].shade))
+                .collect(),
+            cache: HashMap::new(),
+        }
+    }
+
+    fn nearest(&mut self, rgb: [f32; 3]) -> usize {
+        let key = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+        if let Some(&index) = self.cache.get(&key) {
+            return index;
+        }
+        let lab = rgb_to_lab(key.map(f64::from));
+        let index = self
+            .candidates
+            .iter()
+            .map(|&i| (i, ciede2000(&lab, &self.palette[i].lab)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        self.cache.insert(key, index);
+        index
+    }
+}
+
+fn diffuse(buffer: &mut [[f32; 3]], width: usize, x: i64, y: usize, error: [f32; 3], factor: f32) {
+    if x < 0 || x as usize >= width {
+        return;
+    }
+    let index = y * width + x as usize;
+    let Some(pixel) = buffer.get_mut(index) else {
+        return;
+    };
+    for c in 0..3 {
+        pixel[c] += error[c] * factor;
+    }
+}
+
+// 逐像素选取调色板下标, 透明像素为 None; 紧跟透明像素的方块只能用亮色
+pub fn quantize(
+    image: &RgbaImage,
+    palette: &[PaletteEntry],
+    shades: &[Shade],
+    dithering: Dithering,
+) -> Vec<Option<usize>> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut buffer: Vec<[f32; 3]> = image
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    let mut matcher = Matcher::new(palette, shades);
+    let mut start_matcher = Matcher::new(palette, &[SEGMENT_START_SHADE]);
+    let transparent = |index: usize| image.as_raw()[index * 4 + 3] < ALPHA_THRESHOLD;
+    let mut result = vec![None; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            if transparent(index) {
+                continue;
+            }
+            let mut color = buffer[index];
+            if dithering == Dithering::Ordered {
+                let offset = (BAYER_4X4[y % 4][x % 4] / 16.0 - 0.5) * ORDERED_SPREAD;
+                color = color.map(|c| c + offset);
+            }
+            let chosen = if y > 0 && transparent(index - width) {
+                start_matcher.nearest(color)
+            } else {
+                matcher.nearest(color)
+            };
+            result[index] = Some(chosen);
+            let target = palette[chosen].rgb;
+            let error = [0, 1, 2].map(|c| color[c].clamp(0.0, 255.0) - target[c] as f32);
+            let x = x as i64;
+            match dithering {
+                Dithering::FloydSteinberg => {
+                    diffuse(&mut buffer, width, x + 1, y, error, 7.0 / 16.0);
+                    diffuse(&mut buffer, width, x - 1, y + 1, error, 3.0 / 16.0);
+                    diffuse(&mut buffer, width, x, y + 1, error, 5.0 / 16.0);
+                    diffuse(&mut buffer, width, x + 1, y + 1, error, 1.0 / 16.0);
+                }
+                // Atkinson 只扩散 3/4 的误差, 对比度更高
+                Dithering::Atkinson => {
+                    for (dx, dy) in [(1, 0), (2, 0), (-1, 1), (0, 1), (1, 1), (0, 2)] {
+                        diffuse(&mut buffer, width, x + dx, y + dy, error, 1.0 / 8.0);
+                    }
+                }
+                Dithering::None | Dithering::Ordered => {}
+            }
+        }
+    }
+    result
+}
+
+pub fn load_image(bytes: &[u8], options: &MapArtOptions) -> Result<RgbaImage> {
+    if !(1..=MAX_MAPS).contains(&options.maps_x) || !(1..=MAX_MAPS).contains(&options.maps_y) {
+        return Err(anyhow!(
+            "map count must be between 1 and {}: {}x{}",
+            MAX_MAPS,
+            options.maps_x,
+            options.maps_y
+        ));
+    }
+    let image = image::load_from_memory(bytes)?.to_rgba8();
+    Ok(image::imageops::resize(
+        &image,
+        options.maps_x * MAP_SIZE,
+        options.maps_y * MAP_SIZE,
+        FilterType::Lanczos3,
+    ))
+}
+
+pub fn preview_png(image: &RgbImage) -> Result<String> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+// 一段连续方块的相对高度, steps[i] 为第 i + 1 个方块相对第 i 个的亮度
+pub fn segment_heights(steps: &[Shade], mode: MapArtMode) -> Vec<i32> {
+    let mut heights = vec![0; steps.len() + 1];
+    match mode {
+        MapArtMode::Flat => {}
+        MapArtMode::Staircase => {
+            for (i, shade) in steps.iter().enumerate() {
+                heights[i + 1] = heights[i] + shade.step();
+            }
+        }
+        // 每个方块取满足两侧约束的最低高度: 左侧连续上升的长度与右侧连续下降的长度取大
+        MapArtMode::Valley => {
+            let mut rise = vec![0; heights.len()];
+            for (i, shade) in steps.iter().enumerate() {
+                rise[i + 1] = match shade {
+                    Shade::High => rise[i] + 1,
+                    Shade::Normal => rise[i],
+                    Shade::Low => 0,
+                };
+            }
+            let mut fall = vec![0; heights.len()];
+            for (i, shade) in steps.iter().enumerate().rev() {
+                fall[i] = match shade {
+                    Shade::Low => fall[i + 1] + 1,
+                    Shade::Normal => fall[i + 1],
+                    Shade::High => 0,
+                };
+            }
+            for i in 0..heights.len() {
+                heights[i] = rise[i].max(fall[i]);
+            }
+        }
+    }
+    let min = heights.iter().copied().min().unwrap_or(0);
+    heights.iter_mut().for_each(|h| *h -= min);
+    heights
+}
+
+// 一列 (由北向南) 的高度, 下标 0 为参考行; 透明像素处断开, 之后的方块重新起算,
+// 每段第一个方块的亮度不参与计算 (见 SEGMENT_START_SHADE)
+pub fn column_heights(shades: &[Option<Shade>], mode: MapArtMode) -> Vec<Option<i32>> {
+    let mut heights = vec![None; shades.len() + 1];
+    let mut start = 0;
+    while start < heights.len() {
+        let mut end = start + 1;
+        while end < heights.len() && shades[end - 1].is_some() {
+            end += 1;
+        }
+        let steps: Vec<Shade> = shades[start..end - 1].iter().flatten().copied().collect();
+        for (offset, h) in segment_heights(&steps, mode).into_iter().enumerate() {
+            heights[start + offset] = Some(h);
+        }
+        // 跳过透明像素
+        start = end;
+        while start < heights.len() && shades[start - 1].is_none() {
+            start += 1;
+        }
+    }
+    heights
+}
+
+pub fn is_gravity_block(id: &str) -> bool {
+    let name = id.rsplit(':').next().unwrap_or(id);
+    GRAVITY_BLOCKS.contains(&name) || name.ends_with("_concrete_powder")
+}
+
+pub fn needs_support(id: &str) -> bool {
+    let name = id.rsplit(':').next().unwrap_or(id);
+    is_gravity_block(name)
+        || ATTACHED_BLOCKS.contains(&name)
+        || ATTACHED_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+// 后处理: 添加参考行, 并在需要支撑的方块下方 (阶梯模式下为全部方块) 垫上支撑方块,
+// 结果整体平移使各轴最小值为 0 (不加参考行时 z 从 0 开始), 返回新增方块的数量
+pub fn apply_support(
+    blocks: &mut Vec<BlockStatePos>,
+    references: &[BlockPos],
+    options: &SupportOptions,
+    support_all: bool,
+) -> Result<BTreeMap<String, u64>> {
+    let support = block_state(&options.block);
+    if needs_support(&support.id.name) {
+        return Err(anyhow!(
+            "support block needs support itself: {}",
+            support.id.name
+        ));
+    }
+    let mut occupied: HashSet<BlockPos> = blocks.iter().map(|b| b.pos).collect();
+    let mut added: BTreeMap<String, u64> = BTreeMap::new();
+    if options.noobline {
+        let noobline = block_state(NOOBLINE_BLOCK);
+        for pos in references {
+            if occupied.insert(*pos) {
+                blocks.push(BlockStatePos::new(*pos, noobline.clone()));
+                *added.entry(NOOBLINE_BLOCK.to_string()).or_insert(0) += 1;
+            }
+        }
+    }
+    for i in 0..blocks.len() {
+        if !support_all && !needs_support(&blocks[i].block.id.name) {
+            continue;
+        }
+        let below = BlockPos {
+            y: blocks[i].pos.y - 1,
+            ..blocks[i].pos
+        };
+        if occupied.insert(below) {
+            blocks.push(BlockStatePos::new(below, support.clone()));
+            *added.entry(support.id.name.to_string()).or_insert(0) += 1;
+        }
+    }
+    let min = |axis: fn(&BlockPos) -> i32| blocks.iter().map(|b| axis(&b.pos)).min().unwrap_or(0);
+    let (min_x, min_y, min_z) = (min(|p| p.x), min(|p| p.y), min(|p| p.z));
+    for block in blocks.iter_mut() {
+        block.pos.x -= min_x;
+        block.pos.y -= min_y;
+        block.pos.z -= min_z;
+    }
+    Ok(added)
+}
+
+// 由方块的实际范围得到蓝图尺寸, 方块坐标需已从 0 开始
+pub fn block_bounds(blocks: &[BlockStatePos]) -> Size {
+    let max =
+        |axis: fn(&BlockPos) -> i32| blocks.iter().map(|b| axis(&b.pos) + 1).max().unwrap_or(0);
+    Size {
+        width: max(|p| p.x),
+        height: max(|p| p.y),
+        length: max(|p| p.z),
+    }
+}
+
+// 图片上方为北 (z 较小), z = 0 为顶部参考行; selection 为调色板配置筛选后的方块
+pub fn generate_map_art(
+    bytes: &[u8],
+    data: &MapArtsData,
+    selection: &BlockSelection,
+    options: &MapArtOptions,
+) -> Result<MapArtResult> {
+    let mut selection = selection.clone();
+    if !options.blocks.is_empty() {
+        selection.retain(|name, _| options.blocks.contains(name));
+    }
+    let mut shades = options.mode.shades().to_vec();
+    if !shades.contains(&SEGMENT_START_SHADE) {
+        shades.push(SEGMENT_START_SHADE);
+    }
+    let palette = build_palette(data, &selection, &shades);
+    if palette.is_empty() {
+        return Err(anyhow!("no blocks available for map art"));
+    }
+    let image = load_image(bytes, options)?;
+    let (width, height) = (image.width(), image.height());
+    let indices = quantize(&image, &palette, options.mode.shades(), options.dithering);
+
+    let mut blocks = Vec::with_capacity(indices.len());
+    let mut references = Vec::with_capacity(width as usize);
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let mut preview = RgbImage::new(width, height);
+    for x in 0..width {
+        let column: Vec<Option<&PaletteEntry>> = (0..height)
+            .map(|y| indices[(y * width + x) as usize].map(|i| &palette[i]))
+            .collect();
+        let shades: Vec<Option<Shade>> = column.iter().map(|e| e.map(|e| e.shade)).collect();
+        let heights = column_heights(&shades, options.mode);
+        for (z, h) in heights.iter().enumerate() {
+            let Some(y) = *h else {
+                continue;
+            };
+            let pos = BlockPos {
+                x: x as i32,
+                y,
+                z: z as i32,
+            };
+            if z == 0 {
+                references.push(pos);
+                continue;
+            }
+            let entry = column[z - 1].ok_or(anyhow!("missing map art pixel"))?;
+            preview.put_pixel(x, z as u32 - 1, Rgb(entry.rgb));
+            *counts.entry(entry.block.id.name.to_string()).or_insert(0) += 1;
+            blocks.push(BlockStatePos::new(pos, entry.block.clone()));
+        }
+    }
+    let support = MapArtSupport {
+        mode: options.mode,
+        options: options.support.clone(),
+        references,
+    };
+    // 尺寸取添加支撑方块与参考行之后的范围, 与 create_map_art 生成的蓝图一致
+    let mut supported = blocks.clone();
+    let estimate = support.apply(&mut supported)?;
+    Ok(MapArtResult {
+        blocks,
+        references: support.references,
+        size: block_bounds(&supported),
+        counts,
+        support: estimate,
+        preview: preview_png(&preview)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn staircase_heights_follow_shades() {
+        let steps = [Shade::High, Shade::High, Shade::Low, Shade::Normal];
+        assert_eq!(
+            segment_heights(&steps, MapArtMode::Staircase),
+            vec![0, 1, 2, 1, 1]
+        );
+        assert_eq!(segment_heights(&steps, MapArtMode::Flat), vec![0; 5]);
+    }
+
+    #[test]
+    fn staircase_heights_are_shifted_to_zero() {
+        let steps = [Shade::Low, Shade::Low];
+        assert_eq!(
+            segment_heights(&steps, MapArtMode::Staircase),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn valley_heights_use_lowest_valid_height() {
+        let steps = [Shade::High, Shade::Low, Shade::Low, Shade::Normal];
+        let heights = segment_heights(&steps, MapArtMode::Valley);
+        assert_eq!(heights, vec![0, 2, 1, 0, 0]);
+        for (i, shade) in steps.iter().enumerate() {
+            let (north, south) = (heights[i], heights[i + 1]);
+            match shade {
+                Shade::High => assert!(south > north),
+                Shade::Normal => assert_eq!(south, north),
+                Shade::Low => assert!(south < north),
+            }
+        }
+    }
+
+    #[test]
+    fn column_heights_restart_after_gap() {
+        let shades = [Some(Shade::High), None, Some(Shade::Low), Some(Shade::High)];
+        assert_eq!(
+            column_heights(&shades, MapArtMode::Staircase),
+            vec![Some(0), Some(1), None, Some(0), Some(1)]
+        );
+    }
+
+    #[test]
+    fn column_heights_skip_leading_gap() {
+        let shades = [None, None, Some(Shade::Normal), Some(Shade::Low)];
+        assert_eq!(
+            column_heights(&shades, MapArtMode::Staircase),
+            vec![Some(0), None, None, Some(1), Some(0)]
+        );
+    }
+
+    fn entry(shade: Shade, rgb: [u8; 3]) -> PaletteEntry {
+        PaletteEntry {
+            block: block_state("minecraft:stone"),
+            shade,
+            rgb,
+            lab: rgb_to_lab(rgb.map(f64::from)),
+        }
+    }
+
+    #[test]
+    fn segment_start_uses_high_shade() {
+        let palette = [
+            entry(Shade::Normal, [100, 100, 100]),
+            entry(Shade::High, [160, 160, 160]),
+        ];
+        let mut image = RgbaImage::new(1, 3);
+        image.put_pixel(0, 0, Rgba([100, 100, 100, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 0, 0]));
+        image.put_pixel(0, 2, Rgba([100, 100, 100, 255]));
+        let indices = quantize(&image, &palette, &[Shade::Normal], Dithering::None);
+        assert_eq!(indices, vec![Some(0), None, Some(1)]);
+    }
+
+    #[test]
+    fn size_follows_supported_bounds() {
+        let stone = block_state("stone");
+        let blocks: Vec<BlockStatePos> = (1..=2)
+            .map(|z| BlockStatePos::new(BlockPos { x: 0, y: 0, z }, stone.clone()))
+            .collect();
+        let references = [BlockPos { x: 0, y: 0, z: 0 }];
+        let mut options = SupportOptions::default();
+        let mut with_line = blocks.clone();
+        apply_support(&mut with_line, &references, &options, false).unwrap();
+        let size = block_bounds(&with_line);
+        assert_eq!((size.width, size.height, size.length), (1, 1, 3));
+        // 不加参考行时第一行方块移到 z = 0
+        options.noobline = false;
+        let mut without_line = blocks.clone();
+        apply_support(&mut without_line, &references, &options, true).unwrap();
+        let size = block_bounds(&without_line);
+        assert_eq!((size.width, size.height, size.length), (1, 2, 2));
+        assert!(without_line
+            .iter()
+            .any(|b| b.pos == BlockPos { x: 0, y: 0, z: 0 }));
+    }
+}
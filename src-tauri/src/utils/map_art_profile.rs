@@ -0,0 +1,195 @@
+use crate::utils::map_art_image::{is_gravity_block, BlockSelection};
+use crate::utils::minecraft_data::je_blocks_data::BlocksData;
+use crate::utils::minecraft_data::map_art_data::MapArtsData;
+use crate::utils::minecraft_data::versions_data::VersionData;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const PROFILES_FILE: &str = "map_art_profiles.json";
+
+// 生存模式中无法获得的方块
+const UNOBTAINABLE_BLOCKS: [&str; 3] = ["farmland", "suspicious_sand", "suspicious_gravel"];
+
+// 带方块实体的方块
+const BLOCK_ENTITY_BLOCKS: [&str; 11] = [
+    "furnace",
+    "blast_furnace",
+    "smoker",
+    "dispenser",
+    "dropper",
+    "barrel",
+    "jukebox",
+    "bee_nest",
+    "beehive",
+    "sculk_catalyst",
+    "shulker_box",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteProfile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // 只使用地毯, 颜色与对应的羊毛/苔藓块相同
+    #[serde(default)]
+    pub carpet_only: bool,
+    #[serde(default)]
+    pub survival_only: bool,
+    #[serde(default)]
+    pub no_gravity: bool,
+    #[serde(default)]
+    pub no_block_entity: bool,
+    // 限定游戏版本 (版本名或 DataVersion), 只使用该版本中存在的方块
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<String>,
+    // 额外排除的方块 (数据表中的方块名)
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl PaletteProfile {
+    fn preset(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            carpet_only: false,
+            survival_only: false,
+            no_gravity: false,
+            no_block_entity: false,
+            game_version: None,
+            exclude: Vec::new(),
+        }
+    }
+
+    // 首次使用时写入配置目录的预设
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self {
+                carpet_only: true,
+                ..Self::preset("carpet", "仅地毯")
+            },
+            Self {
+                survival_only: true,
+                ..Self::preset("survival", "生存可获得")
+            },
+            Self {
+                no_gravity: true,
+                ..Self::preset("no_gravity", "无重力方块")
+            },
+            Self {
+                no_block_entity: true,
+                ..Self::preset("no_block_entity", "无方块实体")
+            },
+        ]
+    }
+
+    fn placed_id(&self, name: &str) -> Option<String> {
+        if !self.carpet_only {
+            return Some(format!("minecraft:{}", name));
+        }
+        match name {
+            "moss_block" => Some("minecraft:moss_carpet".to_string()),
+            _ => name
+                .strip_suffix("_wool")
+                .map(|color| format!("minecraft:{}_carpet", color)),
+        }
+    }
+
+    fn allows(&self, name: &str) -> bool {
+        if self.exclude.iter().any(|e| e == name) {
+            return false;
+        }
+        if self.survival_only && UNOBTAINABLE_BLOCKS.contains(&name) {
+            return false;
+        }
+        if self.no_gravity && is_gravity_block(name) {
+            return false;
+        }
+        if self.no_block_entity
+            && (BLOCK_ENTITY_BLOCKS.contains(&name) || name.ends_with("_shulker_box"))
+        {
+            return false;
+        }
+        true
+    }
+
+    // 按配置筛选数据表中的方块, 地毯配置会替换为对应的地毯
+    pub fn select(
+        &self,
+        data: &MapArtsData,
+        je_blocks: &BlocksData,
+        versions_data: &VersionData,
+    ) -> Result<BlockSelection> {
+        let release = match &self.game_version {
+            Some(version) => Some(
+                versions_data
+                    .resolve(version)
+                    .ok_or(anyhow!("unknow game version: {}", version))?
+                    .release,
+            ),
+            None => None,
+        };
+        let selection: BlockSelection = data
+            .categories
+            .values()
+            .flat_map(|items| items.keys())
+            .filter(|name| self.allows(name))
+            .filter_map(|name| Some((name.clone(), self.placed_id(name)?)))
+            // 方块表中没有的方块不做限制
+            .filter(|(_, id)| release.is_none_or(|r| je_blocks.exists_in(id, r) != Some(false)))
+            .collect();
+        if selection.is_empty() {
+            return Err(anyhow!("palette profile has no blocks: {}", self.name));
+        }
+        Ok(selection)
+    }
+}
+
+// 配置文件不存在时返回预设
+pub fn load_profiles(dir: &Path) -> Result<Vec<PaletteProfile>> {
+    let path = dir.join(PROFILES_FILE);
+    if !path.exists() {
+        return Ok(PaletteProfile::builtin());
+    }
+    let str = fs::read_to_string(&path).context("Failed to read palette profiles")?;
+    serde_json::from_str(&str).context("Failed to parse palette profiles")
+}
+
+pub fn save_profiles(dir: &Path, profiles: &[PaletteProfile]) -> Result<()> {
+    let json = serde_json::to_string_pretty(profiles)?;
+    fs::write(dir.join(PROFILES_FILE), json).context("Failed to save palette profiles")
+}
+
+pub fn find_profile(dir: &Path, name: &str) -> Result<PaletteProfile> {
+    load_profiles(dir)?
+        .into_iter()
+        .find(|profile| profile.name == name)
+        .ok_or(anyhow!("palette profile not found: {}", name))
+}
+
+// 同名配置整体替换, 否则追加
+pub fn upsert_profile(dir: &Path, profile: PaletteProfile) -> Result<Vec<PaletteProfile>> {
+    if profile.name.trim().is_empty() {
+        return Err(anyhow!("palette profile name is empty"));
+    }
+    let mut profiles = load_profiles(dir)?;
+    match profiles.iter_mut().find(|p| p.name == profile.name) {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    save_profiles(dir, &profiles)?;
+    Ok(profiles)
+}
+
+pub fn remove_profile(dir: &Path, name: &str) -> Result<Vec<PaletteProfile>> {
+    let mut profiles = load_profiles(dir)?;
+    let len = profiles.len();
+    profiles.retain(|p| p.name != name);
+    if profiles.len() == len {
+        return Err(anyhow!("palette profile not found: {}", name));
+    }
+    save_profiles(dir, &profiles)?;
+    Ok(profiles)
+}
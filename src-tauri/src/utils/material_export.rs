@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+
+// 一个潜影盒的格数
+const SHULKER_SLOTS: i64 = 27;
+
+const STACK_16_ITEMS: [&str; 9] = [
+    "ender_pearl",
+    "snowball",
+    "egg",
+    "blue_egg",
+    "brown_egg",
+    "bucket",
+    "honey_bottle",
+    "armor_stand",
+    "written_book",
+];
+const STACK_16_SUFFIXES: [&str; 2] = ["_sign", "_banner"];
+
+const STACK_1_ITEMS: [&str; 19] = [
+    "cake",
+    "saddle",
+    "totem_of_undying",
+    "shulker_box",
+    "enchanted_book",
+    "writable_book",
+    "bow",
+    "crossbow",
+    "trident",
+    "shield",
+    "elytra",
+    "flint_and_steel",
+    "shears",
+    "fishing_rod",
+    "mace",
+    "bundle",
+    "spyglass",
+    "brush",
+    "potion",
+];
+const STACK_1_SUFFIXES: [&str; 16] = [
+    "_bed",
+    "_bucket",
+    "_boat",
+    "_raft",
+    "minecart",
+    "_shulker_box",
+    "_sword",
+    "_pickaxe",
+    "_axe",
+    "_shovel",
+    "_hoe",
+    "_helmet",
+    "_chestplate",
+    "_leggings",
+    "_boots",
+    "_potion",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    // Litematica 材料列表导出的文本表格
+    Litematica,
+    // 按组与潜影盒拆分
+    Stacks,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaterialRow {
+    pub id: String,
+    pub name: String,
+    pub count: i64,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Litematica | ExportFormat::Stacks => "txt",
+        }
+    }
+
+    pub fn render(&self, title: &str, rows: &[MaterialRow]) -> String {
+        match self {
+            ExportFormat::Csv => to_csv(rows),
+            ExportFormat::Litematica => to_litematica(title, rows),
+            ExportFormat::Stacks => to_stacks(title, rows),
+        }
+    }
+}
+
+pub fn max_stack_size(id: &str) -> i64 {
+    let name = id.rsplit(':').next().unwrap_or(id);
+    if STACK_16_ITEMS.contains(&name) || STACK_16_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+        return 16;
+    }
+    if STACK_1_ITEMS.contains(&name)
+        || STACK_1_SUFFIXES.iter().any(|s| name.ends_with(s))
+        || name.starts_with("music_disc_")
+    {
+        return 1;
+    }
+    64
+}
+
+// (潜影盒, 组, 余数)
+pub fn split_stacks(count: i64, stack_size: i64) -> (i64, i64, i64) {
+    let per_box = stack_size * SHULKER_SLOTS;
+    let boxes = count / per_box;
+    let rest = count % per_box;
+    (boxes, rest / stack_size, rest % stack_size)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// csv_field 的逆过程: 引号内的逗号/换行属于字段内容, "" 为转义的引号; 跳过空行
+pub fn csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                let row = std::mem::take(&mut record);
+                if row.iter().any(|f| !f.trim().is_empty()) {
+                    records.push(row);
+                }
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.trim().is_empty()) {
+        records.push(record);
+    }
+    records
+}
+
+pub fn to_csv(rows: &[MaterialRow]) -> String {
+    let mut out = String::from("id,name,count,stack_size,shulker_boxes,stacks,remainder\n");
+    for row in rows {
+        let stack_size = max_stack_size(&row.id);
+        let (boxes, stacks, rest) = split_stacks(row.count, stack_size);
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{}\n",
+            csv_field(&row.id),
+            csv_field(&row.name),
+            row.count,
+            stack_size,
+            boxes,
+            stacks,
+            rest
+        ));
+    }
+    out
+}
+
+// 与 Litematica 材料列表 "写入文件" 的表格格式一致, 没有背包数据时缺少数等于总数
+pub fn to_litematica(title: &str, rows: &[MaterialRow]) -> String {
+    let header = ["Item", "Total", "Missing", "Available"];
+    let title = format!("Material List for schematic '{}'", title);
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.chars().count())
+        .chain([header[0].len()])
+        .max()
+        .unwrap_or(0);
+    let number_width = rows
+        .iter()
+        .map(|row| row.count.to_string().len())
+        .chain(header[1..].iter().map(|h| h.len()))
+        .max()
+        .unwrap_or(0);
+    let border = format!(
+        "+-{}-+-{}-+-{}-+-{}-+\n",
+        "-".repeat(name_width),
+        "-".repeat(number_width),
+        "-".repeat(number_width),
+        "-".repeat(number_width)
+    );
+    let inner_width = name_width + number_width * 3 + 9;
+    let header_line = format!(
+        "| {:<nw$} | {:<w$} | {:<w$} | {:<w$} |\n",
+        header[0],
+        header[1],
+        header[2],
+        header[3],
+        nw = name_width,
+        w = number_width
+    );
+
+    let mut out = String::new();
+    out.push_str(&format!("+-{}-+\n", "-".repeat(inner_width)));
+    out.push_str(&format!("| {:<w$} |\n", title, w = inner_width));
+    out.push_str(&border);
+    out.push_str(&header_line);
+    out.push_str(&border);
+    for row in rows {
+        let padding = name_width.saturating_sub(row.name.chars().count());
+        out.push_str(&format!(
+            "| {}{} | {:>w$} | {:>w$} | {:>w$} |\n",
+            row.name,
+            " ".repeat(padding),
+            row.count,
+            row.count,
+            0,
+            w = number_width
+        ));
+    }
+    out.push_str(&border);
+    out.push_str(&header_line);
+    out.push_str(&border);
+    out
+}
+
+pub fn to_stacks(title: &str, rows: &[MaterialRow]) -> String {
+    let mut out = format!("{}\n\n", title);
+    let mut total_slots = 0;
+    for row in rows {
+        let stack_size = max_stack_size(&row.id);
+        let (boxes, stacks, rest) = split_stacks(row.count, stack_size);
+        let mut parts = Vec::new();
+        if boxes > 0 {
+            parts.push(format!("{} SB", boxes));
+        }
+        if stacks > 0 {
+            parts.push(format!("{} x {}", stacks, stack_size));
+        }
+        if rest > 0 || parts.is_empty() {
+            parts.push(rest.to_string());
+        }
+        total_slots += (row.count + stack_size - 1) / stack_size;
+        out.push_str(&format!(
+            "{} ({}): {} = {}\n",
+            row.name,
+            row.id,
+            row.count,
+            parts.join(" + ")
+        ));
+    }
+    out.push_str(&format!(
+        "\nslots: {}, shulker boxes: {}\n",
+        total_slots,
+        (total_slots + SHULKER_SLOTS - 1) / SHULKER_SLOTS
+    ));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stack_sizes() {
+        assert_eq!(max_stack_size("minecraft:stone"), 64);
+        assert_eq!(max_stack_size("minecraft:ender_pearl"), 16);
+        assert_eq!(max_stack_size("minecraft:oak_sign"), 16);
+        assert_eq!(max_stack_size("minecraft:white_banner"), 16);
+        assert_eq!(max_stack_size("minecraft:red_bed"), 1);
+        assert_eq!(max_stack_size("minecraft:water_bucket"), 1);
+        assert_eq!(max_stack_size("minecraft:blue_shulker_box"), 1);
+        assert_eq!(max_stack_size("minecraft:music_disc_cat"), 1);
+        assert_eq!(max_stack_size("stone"), 64);
+    }
+
+    #[test]
+    fn split_into_boxes_and_stacks() {
+        assert_eq!(split_stacks(0, 64), (0, 0, 0));
+        assert_eq!(split_stacks(63, 64), (0, 0, 63));
+        assert_eq!(split_stacks(64 * 27, 64), (1, 0, 0));
+        assert_eq!(split_stacks(64 * 27 + 64 * 2 + 5, 64), (1, 2, 5));
+        assert_eq!(split_stacks(16 * 27 + 17, 16), (1, 1, 1));
+        assert_eq!(split_stacks(28, 1), (1, 1, 0));
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateType {
+    String,
+    Int,
+    Bool,
+}
+
+impl Default for StateType {
+    fn default() -> Self {
+        StateType::String
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateRule {
+    pub state: String,
+    #[serde(default, rename = "type")]
+    pub state_type: StateType,
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    #[serde(default)]
+    pub offset: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockVariant {
+    pub when: HashMap<String, String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BeBlockMapping {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub variants: Vec<BlockVariant>,
+    #[serde(default)]
+    pub states: BTreeMap<String, String>,
+}
+
+// 规则为 null 表示基岩版没有对应状态, 直接丢弃
+pub type StateProfile = HashMap<String, Option<StateRule>>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BeBlocksData {
+    pub version: i32,
+    pub blocks: HashMap<String, BeBlockMapping>,
+    pub profiles: HashMap<String, StateProfile>,
+    // 基岩版名称 -> (Java 名称, 变体序号), 按 Java 名称排序
+    #[serde(skip)]
+    pub java_names: HashMap<String, Vec<(String, Option<usize>)>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BeStateValue {
+    String(String),
+    Int(i32),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone)]
+pub struct BeBlockState {
+    pub name: String,
+    pub states: BTreeMap<String, BeStateValue>,
+}
+
+impl BeBlocksData {
+    pub fn new() -> Result<Self> {
+        let path = "./data/be_blocks.json";
+        let str = fs::read_to_string(path)?;
+        Self::parse(str.as_str())
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let mut data: BeBlocksData = serde_json::from_str(json)?;
+        let mut java_names: HashMap<String, Vec<(String, Option<usize>)>> = HashMap::new();
+        for (java_name, mapping) in &data.blocks {
+            let be_name = mapping.name.clone().unwrap_or_else(|| java_name.clone());
+            java_names
+                .entry(be_name)
+                .or_default()
+                .push((java_name.clone(), None));
+            for (i, variant) in mapping.variants.iter().enumerate() {
+                java_names
+                    .entry(variant.name.clone())
+                    .or_default()
+                    .push((java_name.clone(), Some(i)));
+            }
+        }
+        for candidates in java_names.values_mut() {
+            candidates.sort();
+        }
+        data.java_names = java_names;
+        Ok(data)
+    }
+
+    fn find_rule(&self, profile: Option<&str>, key: &str) -> Option<&Option<StateRule>> {
+        profile
+            .and_then(|p| self.profiles.get(p))
+            .and_then(|p| p.get(key))
+            .or_else(|| self.profiles.get("default").and_then(|p| p.get(key)))
+    }
+
+    fn typed_value(rule: &StateRule, value: &str) -> Option<BeStateValue> {
+        let value = rule.values.get(value).map(|s| s.as_str()).unwrap_or(value);
+        match rule.state_type {
+            StateType::String => Some(BeStateValue::String(value.to_string())),
+            StateType::Bool => Some(BeStateValue::Bool(value == "true")),
+            StateType::Int => value
+                .parse::<i32>()
+                .ok()
+                .map(|n| BeStateValue::Int(n + rule.offset)),
+        }
+    }
+
+    fn guess_value(value: &str) -> BeStateValue {
+        match value {
+            "true" => BeStateValue::Bool(true),
+            "false" => BeStateValue::Bool(false),
+            _ => value
+                .parse::<i32>()
+                .map(BeStateValue::Int)
+                .unwrap_or_else(|_| BeStateValue::String(value.to_string())),
+        }
+    }
+
+    // 返回 None 表示该方块在基岩版中没有对应
+    pub fn to_bedrock(
+        &self,
+        name: &str,
+        properties: &BTreeMap<impl AsRef<str>, impl AsRef<str>>,
+    ) -> Option<BeBlockState> {
+        if let Some(mapping) = self.blocks.get(name) {
+            let matches = |when: &HashMap<String, String>| {
+                when.iter().all(|(k, v)| {
+                    properties
+                        .iter()
+                        .any(|(pk, pv)| pk.as_ref() == k && pv.as_ref() == v)
+                })
+            };
+            let be_name = mapping
+                .variants
+                .iter()
+                .find(|variant| matches(&variant.when))
+                .map(|variant| variant.name.clone())
+                .or_else(|| mapping.name.clone())
+                .unwrap_or_else(|| name.to_string());
+
+            let mut states = BTreeMap::new();
+            for (k, v) in &mapping.states {
+                states.insert(k.clone(), Self::guess_value(v));
+            }
+            for (key, value) in properties {
+                let rule = match self.find_rule(mapping.profile.as_deref(), key.as_ref()) {
+                    Some(Some(rule)) => rule,
+                    Some(None) => continue,
+                    None => {
+                        states
+                            .entry(key.as_ref().to_string())
+                            .or_insert_with(|| Self::guess_value(value.as_ref()));
+                        continue;
+                    }
+                };
+                if let Some(state) = Self::typed_value(rule, value.as_ref()) {
+                    states.entry(rule.state.clone()).or_insert(state);
+                }
+            }
+            return Some(BeBlockState {
+                name: be_name,
+                states,
+            });
+        }
+        // 基岩版原生方块 (如从 mcstructure 读取) 原样写回
+        if self.java_names.contains_key(name) {
+            let states = properties
+                .iter()
+                .filter(|(k, _)| k.as_ref() != "waterlogged")
+                .map(|(k, v)| (k.as_ref().to_string(), Self::guess_value(v.as_ref())))
+                .collect();
+            return Some(BeBlockState {
+                name: name.to_string(),
+                states,
+            });
+        }
+        None
+    }
+
+    fn java_value(rule: &StateRule, value: &str) -> Option<String> {
+        let value = match rule.state_type {
+            StateType::Int => (value.parse::<i32>().ok()? - rule.offset).to_string(),
+            _ => value.to_string(),
+        };
+        if rule.values.is_empty() {
+            return Some(value);
+        }
+        // 多个 Java 值对应同一基岩版值时优先同名值, 其次取字典序最小
+        if rule.values.get(&value) == Some(&value) {
+            return Some(value);
+        }
+        rule.values
+            .iter()
+            .filter(|(_, v)| **v == value)
+            .map(|(k, _)| k)
+            .min()
+            .cloned()
+    }
+
+    // 基岩版方块转为 Java 版名称与状态, 返回 None 表示没有对应
+    pub fn to_java(
+        &self,
+        name: &str,
+        states: &BTreeMap<impl AsRef<str>, impl AsRef<str>>,
+    ) -> Option<(String, BTreeMap<String, String>)> {
+        let state = |key: &str| {
+            states
+                .iter()
+                .find(|(k, _)| k.as_ref() == key)
+                .map(|(_, v)| v.as_ref())
+        };
+        // 固定状态必须一致; 固定状态越多越具体, 其次优先同名方块
+        let (java_name, variant) = self
+            .java_names
+            .get(name)?
+            .iter()
+            .filter(|(java_name, _)| {
+                self.blocks[java_name]
+                    .states
+                    .iter()
+                    .all(|(k, v)| state(k) == Some(v.as_str()))
+            })
+            .fold(None, |best: Option<&(String, Option<usize>)>, candidate| {
+                let rank = |(java_name, _): &(String, Option<usize>)| {
+                    (self.blocks[java_name].states.len(), java_name == name)
+                };
+                match best {
+                    Some(best) if rank(best) >= rank(candidate) => Some(best),
+                    _ => Some(candidate),
+                }
+            })?;
+        let mapping = &self.blocks[java_name];
+
+        let mut properties: BTreeMap<String, String> = variant
+            .map(|i| mapping.variants[i].when.clone().into_iter().collect())
+            .unwrap_or_default();
+        let mut java_keys: Vec<&String> = mapping
+            .profile
+            .as_deref()
+            .and_then(|p| self.profiles.get(p))
+            .into_iter()
+            .chain(self.profiles.get("default"))
+            .flat_map(|profile| profile.keys())
+            .collect();
+        java_keys.sort();
+        java_keys.dedup();
+        for (key, value) in states {
+            if mapping.states.contains_key(key.as_ref()) {
+                continue;
+            }
+            // 没有对应规则的基岩版状态直接丢弃
+            for java_key in &java_keys {
+                let Some(Some(rule)) = self.find_rule(mapping.profile.as_deref(), java_key) else {
+                    continue;
+                };
+                if rule.state != key.as_ref() || properties.contains_key(*java_key) {
+                    continue;
+                }
+                if let Some(java_value) = Self::java_value(rule, value.as_ref()) {
+                    properties.insert((*java_key).clone(), java_value);
+                }
+            }
+        }
+        Some((java_name.clone(), properties))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_java(data: &BeBlocksData, name: &str, states: &[(&str, &str)]) -> Option<String> {
+        let states: BTreeMap<&str, &str> = states.iter().copied().collect();
+        data.to_java(name, &states).map(|(name, properties)| {
+            let properties = properties
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("{}[{}]", name, properties)
+        })
+    }
+
+    #[test]
+    fn maps_bedrock_states_back_to_java() {
+        let data = BeBlocksData::new().unwrap();
+        assert_eq!(
+            to_java(
+                &data,
+                "minecraft:oak_stairs",
+                &[("weirdo_direction", "3"), ("upside_down_bit", "true")]
+            )
+            .as_deref(),
+            Some("minecraft:oak_stairs[facing=north,half=top]")
+        );
+        assert_eq!(
+            to_java(
+                &data,
+                "minecraft:oak_double_slab",
+                &[("minecraft:vertical_half", "bottom")]
+            )
+            .as_deref(),
+            Some("minecraft:oak_slab[type=double]")
+        );
+        assert_eq!(
+            to_java(&data, "minecraft:snow_layer", &[("height", "2")]).as_deref(),
+            Some("minecraft:snow[layers=3]")
+        );
+        assert_eq!(
+            to_java(&data, "minecraft:air", &[]).as_deref(),
+            Some("minecraft:air[]")
+        );
+    }
+
+    #[test]
+    fn picks_block_by_fixed_states() {
+        let data = BeBlocksData::new().unwrap();
+        assert_eq!(
+            to_java(
+                &data,
+                "minecraft:torch",
+                &[("torch_facing_direction", "top")]
+            )
+            .as_deref(),
+            Some("minecraft:torch[]")
+        );
+        assert_eq!(
+            to_java(
+                &data,
+                "minecraft:torch",
+                &[("torch_facing_direction", "north")]
+            )
+            .as_deref(),
+            Some("minecraft:wall_torch[facing=north]")
+        );
+    }
+
+    #[test]
+    fn unknown_bedrock_block_is_unmapped() {
+        let data = BeBlocksData::new().unwrap();
+        assert_eq!(to_java(&data, "minecraft:not_a_block", &[]), None);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::utils::block_state_pos_list::BlockData;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+const BLOCK_STATES_PATH: &str = "./data/je_block_states.json";
+const FORMAT_VERSION: i32 = 1;
+
+// 某个正式版起属性的全部取值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertyRevision {
+    pub since: (u32, u32),
+    pub values: Vec<String>,
+}
+
+// 同一类方块共用的属性表, 按方块名或后缀匹配
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateFamily {
+    #[serde(default)]
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub suffixes: Vec<String>,
+    pub properties: HashMap<String, Vec<PropertyRevision>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockStatesData {
+    pub format_version: i32,
+    pub families: Vec<StateFamily>,
+}
+
+impl BlockStatesData {
+    pub fn new() -> Result<Self> {
+        let str = fs::read_to_string(BLOCK_STATES_PATH)?;
+        Self::parse(str.as_str())
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let data: BlockStatesData = serde_json::from_str(json)?;
+        if data.format_version > FORMAT_VERSION {
+            return Err(anyhow!(
+                "unsupported block states format: {}",
+                data.format_version
+            ));
+        }
+        Ok(data)
+    }
+
+    // 方块名精确匹配优先, 其次取最长的后缀
+    fn family(&self, block_name: &str) -> Option<&StateFamily> {
+        let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
+        self.families
+            .iter()
+            .find(|family| family.names.iter().any(|n| n == name))
+            .or_else(|| {
+                self.families
+                    .iter()
+                    .filter_map(|family| {
+                        family
+                            .suffixes
+                            .iter()
+                            .filter(|suffix| name.ends_with(suffix.as_str()))
+                            .map(|suffix| (suffix.len(), family))
+                            .max_by_key(|(len, _)| *len)
+                    })
+                    .max_by_key(|(len, _)| *len)
+                    .map(|(_, family)| family)
+            })
+    }
+
+    // 目标正式版中属性的取值, 属性尚未加入时为 None
+    fn values_at<'a>(
+        family: &'a StateFamily,
+        key: &str,
+        release: (u32, u32),
+    ) -> Option<&'a [String]> {
+        family
+            .properties
+            .get(key)?
+            .iter()
+            .filter(|revision| revision.since <= release)
+            .max_by_key(|revision| revision.since)
+            .map(|revision| revision.values.as_slice())
+    }
+
+    // 目标正式版中不存在的属性或取值, 表中未收录的方块不检查
+    pub fn unsupported_properties(
+        &self,
+        block: &BlockData,
+        release: (u32, u32),
+    ) -> Vec<(Arc<str>, Arc<str>)> {
+        let Some(family) = self.family(&block.id.name) else {
+            return Vec::new();
+        };
+        block
+            .properties
+            .iter()
+            .filter(|(key, value)| {
+                !Self::values_at(family, key, release)
+                    .is_some_and(|values| values.iter().any(|v| v == value.as_ref()))
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockId;
+
+    fn block(name: &str, properties: &[(&str, &str)]) -> BlockData {
+        BlockData {
+            id: BlockId {
+                name: Arc::from(format!("minecraft:{}", name)),
+            },
+            properties: properties
+                .iter()
+                .map(|(k, v)| (Arc::from(*k), Arc::from(*v)))
+                .collect(),
+        }
+    }
+
+    fn unsupported(block: &BlockData, release: (u32, u32)) -> Vec<String> {
+        let data = BlockStatesData::new().unwrap();
+        data.unsupported_properties(block, release)
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect()
+    }
+
+    #[test]
+    fn checks_values_per_release() {
+        let old_wall = block("cobblestone_wall", &[("north", "true"), ("up", "true")]);
+        let new_wall = block("cobblestone_wall", &[("north", "low"), ("up", "true")]);
+        assert!(unsupported(&old_wall, (15, 2)).is_empty());
+        assert_eq!(unsupported(&old_wall, (16, 0)), vec!["north=true"]);
+        assert_eq!(unsupported(&new_wall, (15, 2)), vec!["north=low"]);
+        let leaves = block("oak_leaves", &[("distance", "7"), ("waterlogged", "false")]);
+        assert_eq!(unsupported(&leaves, (18, 2)), vec!["waterlogged=false"]);
+        assert!(unsupported(&leaves, (19, 0)).is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_properties_and_values() {
+        let stairs = block(
+            "oak_stairs",
+            &[("facing", "up"), ("half", "top"), ("color", "red")],
+        );
+        assert_eq!(
+            unsupported(&stairs, (20, 1)),
+            vec!["color=red", "facing=up"]
+        );
+        // 精确名称优先于后缀: mushroom_stem 不是原木
+        let stem = block("mushroom_stem", &[("up", "true")]);
+        assert!(unsupported(&stem, (20, 1)).is_empty());
+        // 表中未收录的方块不检查
+        let note_block = block("note_block", &[("note", "30")]);
+        assert!(unsupported(&note_block, (20, 1)).is_empty());
+    }
+}
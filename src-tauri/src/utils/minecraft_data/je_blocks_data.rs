@@ -1,7 +1,13 @@
+use crate::utils::minecraft_data::block_states_data::BlockStatesData;
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+
+const LANG_DIR: &str = "./data/lang";
+// 语言文件中按优先级读取的键前缀
+const LANG_PREFIXES: [&str; 3] = ["block.minecraft.", "item.minecraft.", "entity.minecraft."];
 
 #[derive(Debug, Deserialize)]
 struct RawBlock {
@@ -24,22 +30,113 @@ pub struct SubData {
     pub version_map: HashMap<u32, String>,
 }
 
+impl SubData {
+    // 指定正式版中该方块使用的名称, 尚未加入时为 None
+    pub fn name_at(&self, release: (u32, u32)) -> Option<&str> {
+        self.version_map
+            .iter()
+            .map(|(code, name)| (release_key(*code), name))
+            .filter(|(key, _)| *key <= release)
+            .max_by_key(|(key, _)| *key)
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+// version_map 的键: 1XX 为 1.XX, 1XXYY 为 1.XX.YY
+pub fn release_key(code: u32) -> (u32, u32) {
+    if code < 1000 {
+        (code.saturating_sub(100), 0)
+    } else {
+        (code.saturating_sub(10000) / 100, code % 100)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlocksData {
     pub blocks: Vec<SubData>,
     pub block_to_cn: HashMap<String, String>,
+    // 1.13 前的数字 ID, 键为 id << 4 | meta
+    #[serde(skip)]
+    pub legacy_ids: HashMap<u32, String>,
+    // 各版本中出现过的方块名 -> blocks 下标
+    #[serde(skip)]
+    pub name_index: HashMap<String, Vec<usize>>,
+    // 语言代码 (en_us) -> 方块 ID -> 名称, 来自 data/lang 下的原版语言文件
+    #[serde(skip)]
+    pub locale_names: HashMap<String, HashMap<String, String>>,
+    // 各正式版的方块属性表, 来自 data/je_block_states.json
+    #[serde(skip)]
+    pub block_states: BlockStatesData,
+    // 读取时跳过的语言文件及原因, 启动后写入日志
+    #[serde(skip)]
+    pub skipped_files: Vec<String>,
 }
 
 impl BlocksData {
     pub fn new() -> Result<BlocksData> {
         let path = "./data/je_blocks.json";
         let str = fs::read_to_string(path)?;
-        Ok(Self::parse(str.as_str())?)
+        let mut data = Self::parse(str.as_str())?;
+        data.block_states = BlockStatesData::new()?;
+        // 语言文件只影响显示名称, 读取失败不影响启动
+        if let Err(e) = data.load_lang_dir(Path::new(LANG_DIR)) {
+            data.skipped_files
+                .push(format!("Failed to load lang dir: {:#}", e));
+        }
+        Ok(data)
+    }
+
+    // 语言目录不存在时只保留内置的中文名, 无法解析的语言文件跳过
+    pub fn load_lang_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(locale) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            match Self::load_lang_file(&path) {
+                Ok(names) => {
+                    self.locale_names.insert(locale.to_lowercase(), names);
+                }
+                Err(e) => self.skipped_files.push(format!("{:#}", e)),
+            }
+        }
+        Ok(())
+    }
+
+    fn load_lang_file(path: &Path) -> Result<HashMap<String, String>> {
+        let str = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lang: HashMap<String, String> = serde_json::from_str(&str)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Self::parse_lang(&lang))
+    }
+
+    fn parse_lang(lang: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut names = HashMap::new();
+        for prefix in LANG_PREFIXES {
+            for (key, value) in lang {
+                // 只取 "block.minecraft.stone" 这类一级键, 跳过 ".desc" 等附加说明
+                let Some(id) = key.strip_prefix(prefix).filter(|id| !id.contains('.')) else {
+                    continue;
+                };
+                names.entry(id.to_string()).or_insert_with(|| value.clone());
+            }
+        }
+        names
     }
     pub fn parse(json: &str) -> Result<Self> {
         let raw_blocks: Vec<RawBlock> = serde_json::from_str(json)?;
         let mut blocks = Vec::new();
         let mut block_to_cn = HashMap::new();
+        let mut legacy_key = None;
+        let mut legacy_ids = HashMap::new();
+        let mut name_index: HashMap<String, Vec<usize>> = HashMap::new();
 
         for raw in raw_blocks {
             let id_parts: Vec<&str> = raw.ID.split(',').collect();
@@ -51,6 +148,9 @@ impl BlocksData {
                 let (prefix, name) = part.split_once('.')
                     .ok_or_else(|| anyhow!("Invalid ID segment: '{}'", part))?;
 
+                if index == 0 && prefix == "num" {
+                    legacy_key = Self::parse_legacy_key(name);
+                }
                 if index == 0 {
                     block_id = if prefix == "num" {
                         name.replace('_', "")
@@ -66,6 +166,17 @@ impl BlocksData {
                 block_name = name.to_string();
             }
 
+            if let Some(key) = legacy_key.take() {
+                legacy_ids.entry(key).or_insert_with(|| block_name.clone());
+            }
+
+            for name in version_map.values() {
+                let indices = name_index.entry(name.clone()).or_default();
+                if !indices.contains(&blocks.len()) {
+                    indices.push(blocks.len());
+                }
+            }
+
             let zh_cn = raw.n.clone();
             block_to_cn.insert(block_name.clone(), zh_cn.clone());
 
@@ -80,10 +191,103 @@ impl BlocksData {
         Ok(BlocksData {
             blocks,
             block_to_cn,
+            legacy_ids,
+            name_index,
+            locale_names: HashMap::new(),
+            block_states: BlockStatesData::default(),
+            skipped_files: Vec::new(),
         })
     }
 
+    // "17_12" -> 17 << 4 | 12, 省略 meta 时为 0
+    fn parse_legacy_key(name: &str) -> Option<u32> {
+        let (id, meta) = name.split_once('_').unwrap_or((name, "0"));
+        let id = id.parse::<u32>().ok()?;
+        let meta = meta.parse::<u32>().ok()?;
+        (meta < 16).then_some(id << 4 | meta)
+    }
+
+    // 找不到对应 meta 时退回该 ID 的默认方块
+    pub fn get_legacy_name(&self, id: u16, meta: u8) -> Option<&str> {
+        let key = (id as u32) << 4 | (meta as u32 & 0x0f);
+        self.legacy_ids
+            .get(&key)
+            .or_else(|| self.legacy_ids.get(&((id as u32) << 4)))
+            .map(|s| s.as_str())
+    }
+
+    // 方块表收录的最新正式版
+    pub fn latest_release(&self) -> (u32, u32) {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.version_map.keys())
+            .map(|code| release_key(*code))
+            .max()
+            .unwrap_or((13, 0))
+    }
+
+    // 方块在目标正式版中是否存在, 方块表中没有的方块返回 None
+    pub fn exists_in(&self, block_name: &str, release: (u32, u32)) -> Option<bool> {
+        let name = block_name.strip_prefix("minecraft:").unwrap_or(block_name);
+        let indices = self.name_index.get(name)?;
+        Some(
+            indices
+                .iter()
+                .any(|&index| self.blocks[index].name_at(release) == Some(name)),
+        )
+    }
+
     pub fn get_zh_cn(&self, block_name: &str) -> Option<&str> {
         self.block_to_cn.get(block_name).map(|s| s.as_str())
     }
+
+    // 前端的 en/ja/zh 对应原版语言文件名
+    pub fn normalize_locale(locale: &str) -> String {
+        let locale = locale.trim().to_lowercase().replace('-', "_");
+        match locale.as_str() {
+            "en" => "en_us".to_string(),
+            "ja" => "ja_jp".to_string(),
+            "zh" => "zh_cn".to_string(),
+            _ => locale,
+        }
+    }
+
+    // 指定语言的名称, 缺少翻译时退回美化后的 ID
+    pub fn get_locale_name(&self, id: &str, locale: &str) -> String {
+        let name = id.rsplit(':').next().unwrap_or(id);
+        let locale = Self::normalize_locale(locale);
+        // 中文优先使用方块表自带的译名
+        let builtin = if locale == "zh_cn" {
+            self.get_zh_cn(name)
+        } else {
+            None
+        };
+        builtin
+            .or_else(|| {
+                self.locale_names
+                    .get(&locale)
+                    .and_then(|names| names.get(name))
+                    .map(|s| s.as_str())
+            })
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| prettify_id(name))
+    }
+}
+
+// "oak_planks" -> "Oak Planks"
+pub fn prettify_id(id: &str) -> String {
+    id.rsplit(':')
+        .next()
+        .unwrap_or(id)
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
 }
@@ -1,3 +1,6 @@
+pub mod be_blocks_data;
+pub mod block_states_data;
 pub mod je_blocks_data;
 pub mod map_art_data;
+pub mod recipes_data;
 pub mod versions_data;
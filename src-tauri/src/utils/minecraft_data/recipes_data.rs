@@ -0,0 +1,260 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+// 从原版 jar 中解压的 data 目录, 如 ./data/recipes/minecraft/recipe/*.json
+const RECIPES_DIR: &str = "./data/recipes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeKind {
+    Crafting,
+    Stonecutting,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub id: String,
+    pub kind: RecipeKind,
+    // 每种原料的可选物品 (物品 ID 或 #标签) 与所需数量
+    pub ingredients: Vec<(Vec<String>, u32)>,
+    pub result: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RecipesData {
+    // 产物 -> 配方
+    pub recipes: HashMap<String, Vec<Recipe>>,
+    // 物品标签 -> 物品 ID 或嵌套标签
+    pub tags: HashMap<String, Vec<String>>,
+    // 读取时跳过的配方文件及原因, 启动后写入日志
+    pub skipped_files: Vec<String>,
+}
+
+fn with_namespace(id: &str) -> String {
+    let (tag, id) = match id.strip_prefix('#') {
+        Some(id) => ("#", id),
+        None => ("", id),
+    };
+    if id.contains(':') {
+        format!("{}{}", tag, id)
+    } else {
+        format!("{}minecraft:{}", tag, id)
+    }
+}
+
+// 兼容 {"item": ..} / {"tag": ..} / "id" / "#tag" 以及它们组成的数组
+fn parse_ingredient(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(id) => vec![with_namespace(id)],
+        Value::Array(options) => options.iter().flat_map(parse_ingredient).collect(),
+        Value::Object(map) => {
+            if let Some(Value::String(item)) = map.get("item") {
+                vec![with_namespace(item)]
+            } else if let Some(Value::String(tag)) = map.get("tag") {
+                vec![format!("#{}", with_namespace(tag))]
+            } else {
+                Vec::new()
+            }
+        }
+        _ => Vec::new(),
+    }
+}
+
+// 产物: 1.20.5 前为 {"item", "count"}, 之后为 {"id", "count"}, 切石机旧格式为字符串 + 顶层 count
+fn parse_result(recipe: &Value) -> Option<(String, u32)> {
+    let result = recipe.get("result")?;
+    let count = |value: &Value| value.get("count").and_then(Value::as_u64);
+    match result {
+        Value::String(id) => Some((with_namespace(id), count(recipe).unwrap_or(1) as u32)),
+        Value::Object(map) => {
+            let id = map.get("id").or_else(|| map.get("item"))?.as_str()?;
+            Some((with_namespace(id), count(result).unwrap_or(1) as u32))
+        }
+        _ => None,
+    }
+}
+
+fn merge_ingredients(ingredients: Vec<Vec<String>>) -> Vec<(Vec<String>, u32)> {
+    let mut merged: Vec<(Vec<String>, u32)> = Vec::new();
+    for options in ingredients {
+        if options.is_empty() {
+            continue;
+        }
+        match merged.iter_mut().find(|(o, _)| *o == options) {
+            Some((_, n)) => *n += 1,
+            None => merged.push((options, 1)),
+        }
+    }
+    merged
+}
+
+impl RecipesData {
+    pub fn new() -> Result<RecipesData> {
+        let mut data = Self::default();
+        data.load_dir(Path::new(RECIPES_DIR))?;
+        Ok(data)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    pub fn recipes_for(&self, item: &str) -> &[Recipe] {
+        self.recipes
+            .get(item)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // 目录不存在时为空, 此时不做配方展开; 无法解析的文件跳过
+    pub fn load_dir(&mut self, dir: &Path) -> Result<()> {
+        if !dir.is_dir() {
+            return Ok(());
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.load_dir(&path)?;
+                continue;
+            }
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let json = match Self::read_json(&path) {
+                Ok(json) => json,
+                Err(e) => {
+                    self.skipped_files.push(format!("{:#}", e));
+                    continue;
+                }
+            };
+            if json.get("values").is_some() {
+                if let Some(name) = Self::tag_name(&path) {
+                    self.add_tag(name, &json);
+                }
+            } else if let Some(recipe) = Self::parse_recipe(&path, &json) {
+                self.recipes
+                    .entry(recipe.result.clone())
+                    .or_default()
+                    .push(recipe);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_json(path: &Path) -> Result<Value> {
+        let str = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&str).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    // .../<namespace>/tags/item(s)/<path>.json -> #<namespace>:<path>
+    fn tag_name(path: &Path) -> Option<String> {
+        let parts: Vec<&str> = path.iter().filter_map(|p| p.to_str()).collect();
+        let tags = parts.iter().rposition(|p| *p == "tags")?;
+        if !matches!(parts.get(tags + 1), Some(&"item") | Some(&"items")) {
+            return None;
+        }
+        let namespace = tags.checked_sub(1).map(|i| parts[i]).unwrap_or("minecraft");
+        let name = parts[tags + 2..].join("/");
+        let name = name.strip_suffix(".json")?;
+        Some(format!("#{}:{}", namespace, name))
+    }
+
+    fn add_tag(&mut self, name: String, json: &Value) {
+        let values = json
+            .get("values")
+            .and_then(Value::as_array)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().or_else(|| v.get("id")?.as_str()))
+                    .map(with_namespace)
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.tags.insert(name, values);
+    }
+
+    // 只读取工作台与切石机配方, 熔炼产物视为基础材料
+    fn parse_recipe(path: &Path, json: &Value) -> Option<Recipe> {
+        let kind = json.get("type")?.as_str()?;
+        let (kind, ingredients) = match kind.trim_start_matches("minecraft:") {
+            "crafting_shaped" => {
+                let key = json.get("key")?.as_object()?;
+                let symbols = json
+                    .get("pattern")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .flat_map(str::chars)
+                    .filter(|c| *c != ' ')
+                    .filter_map(|c| key.get(&c.to_string()))
+                    .map(parse_ingredient)
+                    .collect();
+                (RecipeKind::Crafting, merge_ingredients(symbols))
+            }
+            "crafting_shapeless" => {
+                let list = json
+                    .get("ingredients")?
+                    .as_array()?
+                    .iter()
+                    .map(parse_ingredient)
+                    .collect();
+                (RecipeKind::Crafting, merge_ingredients(list))
+            }
+            "stonecutting" => {
+                let ingredient = parse_ingredient(json.get("ingredient")?);
+                (
+                    RecipeKind::Stonecutting,
+                    merge_ingredients(vec![ingredient]),
+                )
+            }
+            _ => return None,
+        };
+        let (result, count) = parse_result(json)?;
+        if ingredients.is_empty() || count == 0 {
+            return None;
+        }
+        let id = path.file_stem()?.to_str()?.to_string();
+        Some(Recipe {
+            id,
+            kind,
+            ingredients,
+            result,
+            count,
+        })
+    }
+
+    // 展开 #标签 (含嵌套标签) 为物品 ID
+    pub fn expand(&self, option: &str) -> Vec<String> {
+        let mut items = Vec::new();
+        let mut visited = HashSet::new();
+        self.expand_into(option, &mut items, &mut visited);
+        items
+    }
+
+    fn expand_into(&self, option: &str, items: &mut Vec<String>, visited: &mut HashSet<String>) {
+        if !option.starts_with('#') {
+            if !items.iter().any(|i| i == option) {
+                items.push(option.to_string());
+            }
+            return;
+        }
+        if !visited.insert(option.to_string()) {
+            return;
+        }
+        let Some(values) = self.tags.get(option) else {
+            // 未知标签原样保留, 作为无法展开的材料
+            items.push(option.to_string());
+            return;
+        };
+        for value in values {
+            self.expand_into(value, items, visited);
+        }
+    }
+}
@@ -1,16 +1,229 @@
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::sync::Arc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
-pub struct VersionData {
+// 随程序发布的版本表, 导入的版本表写入配置目录并优先读取
+const VERSIONS_PATH: &str = "./data/versions.json";
+pub const VERSIONS_FILE: &str = "versions.json";
+const FORMAT_VERSION: i32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionTable {
     pub name_to_id: BTreeMap<Arc<str>, i32>,
     pub id_to_name: BTreeMap<i32, Arc<str>>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionEntry {
+    pub name: String,
+    pub data_version: i32,
+}
+
+// data/versions.json 的文件结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionFile {
+    pub format_version: i32,
+    pub versions: Vec<VersionEntry>,
+}
+
+// 导入后会整体替换, 所以放在锁里
+#[derive(Debug)]
+pub struct VersionData {
+    table: RwLock<VersionTable>,
+    user_path: PathBuf,
+}
+
+// 写出蓝图时的目标游戏版本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TargetVersion {
+    pub name: Arc<str>,
+    pub data_version: i32,
+    // 所属正式版 (次版本号, 修订号), 如 1.20.1 -> (20, 1)
+    pub release: (u32, u32),
+}
+
+impl VersionTable {
+    fn insert(&mut self, name: &str, id: i32) {
+        let name: Arc<str> = Arc::from(name);
+        self.name_to_id.insert(name.clone(), id);
+        self.id_to_name.insert(id, name);
+    }
+
+    // 编译进程序的版本表, 数据文件缺失或损坏时使用
+    pub fn compiled() -> Self {
+        let mut table = Self::default();
+        for line in COMPILED_TABLE.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            if let (Some(name), Some(id_str)) = (parts.next(), parts.next()) {
+                if let Ok(id) = id_str.parse::<i32>() {
+                    table.insert(name, id);
+                }
+            }
+        }
+        table
+    }
+
+    pub fn from_file(file: &VersionFile) -> Result<Self> {
+        if file.format_version > FORMAT_VERSION {
+            return Err(anyhow!(
+                "unsupported versions file format: {}",
+                file.format_version
+            ));
+        }
+        let mut table = Self::default();
+        // 文件按新到旧排列, 倒序插入使同一 DataVersion 保留最新的名称
+        for entry in file.versions.iter().rev() {
+            table.insert(entry.name.trim(), entry.data_version);
+        }
+        if table.id_to_name.is_empty() {
+            return Err(anyhow!("versions file is empty"));
+        }
+        Ok(table)
+    }
+
+    pub fn to_file(&self) -> VersionFile {
+        let mut versions: Vec<VersionEntry> = self
+            .name_to_id
+            .iter()
+            .map(|(name, id)| VersionEntry {
+                name: name.to_string(),
+                data_version: *id,
+            })
+            .collect();
+        versions.sort_by(|a, b| b.data_version.cmp(&a.data_version));
+        VersionFile {
+            format_version: FORMAT_VERSION,
+            versions,
+        }
+    }
+
+    pub fn merge(&mut self, other: &VersionTable) {
+        for (name, id) in &other.name_to_id {
+            self.insert(name, *id);
+        }
+        // 同一 DataVersion 以导入文件中的名称为准
+        for (id, name) in &other.id_to_name {
+            self.id_to_name.insert(*id, name.clone());
+        }
+    }
+}
+
 impl VersionData {
-    pub fn new() -> Self {
-        let raw_data = "
+    pub fn new(config_dir: &Path) -> Self {
+        let user_path = config_dir.join(VERSIONS_FILE);
+        let table = Self::load_file(&user_path)
+            .or_else(|_| Self::load_file(Path::new(VERSIONS_PATH)))
+            .unwrap_or_else(|_| VersionTable::compiled());
+        Self {
+            table: RwLock::new(table),
+            user_path,
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<VersionTable> {
+        let str = fs::read_to_string(path)?;
+        let file: VersionFile = serde_json::from_str(&str)?;
+        VersionTable::from_file(&file)
+    }
+
+    fn table(&self) -> std::sync::RwLockReadGuard<'_, VersionTable> {
+        self.table.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // 合并外部版本表并写入配置目录, 返回合并后的版本数
+    pub fn import(&self, path: &Path) -> Result<usize> {
+        let imported = Self::load_file(path)?;
+        let mut table = self.table.write().unwrap_or_else(|e| e.into_inner());
+        let mut merged = table.clone();
+        merged.merge(&imported);
+        let json = serde_json::to_string_pretty(&merged.to_file())?;
+        fs::write(&self.user_path, json)
+            .with_context(|| format!("Failed to write {}", self.user_path.display()))?;
+        let count = merged.id_to_name.len();
+        *table = merged;
+        Ok(count)
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<i32> {
+        self.table().name_to_id.get(name).copied()
+    }
+
+    pub fn get_name(&self, id: i32) -> Option<Arc<str>> {
+        self.table().id_to_name.get(&id).cloned()
+    }
+
+    // 未收录的 DataVersion 显示为不高于它的最近正式版加 "+"
+    pub fn display_name(&self, id: i32) -> String {
+        if let Some(name) = self.get_name(id) {
+            return name.to_string();
+        }
+        self.table()
+            .id_to_name
+            .range(..id)
+            .rev()
+            .find(|(_, name)| parse_release(name).is_some())
+            .map(|(_, name)| format!("{}+", name))
+            .unwrap_or_else(|| "unknown_version".to_string())
+    }
+
+    // 接受版本名 ("1.20.1") 或 DataVersion 数字
+    pub fn resolve(&self, version: &str) -> Option<TargetVersion> {
+        let version = version.trim();
+        let data_version = match self.get_id(version) {
+            Some(id) => id,
+            None => version.parse::<i32>().ok()?,
+        };
+        let name = self
+            .get_name(data_version)
+            .unwrap_or_else(|| Arc::from(self.display_name(data_version)));
+        let release = self.release_of(data_version)?;
+        Some(TargetVersion {
+            name,
+            data_version,
+            release,
+        })
+    }
+
+    // 快照归入随后发布的正式版, 比最新正式版还新时取最新正式版
+    pub fn release_of(&self, data_version: i32) -> Option<(u32, u32)> {
+        let table = self.table();
+        table
+            .id_to_name
+            .range(data_version..)
+            .find_map(|(_, name)| parse_release(name))
+            .or_else(|| {
+                table
+                    .id_to_name
+                    .values()
+                    .rev()
+                    .find_map(|name| parse_release(name))
+            })
+    }
+}
+
+// "1.20.1" -> (20, 1), 快照与预览版返回 None
+pub fn parse_release(name: &str) -> Option<(u32, u32)> {
+    let mut parts = name.split('.');
+    if parts.next()? != "1" {
+        return None;
+    }
+    let minor = parts.next()?.parse().ok()?;
+    let patch = match parts.next() {
+        Some(patch) => patch.parse().ok()?,
+        None => 0,
+    };
+    parts.next().is_none().then_some((minor, patch))
+}
+
+// 编译时内置的版本表, 收录到 25w16a
+const COMPILED_TABLE: &str = "
             25w16a	4423
             25w15a	4422
             1.21.5	4325
@@ -610,33 +823,3 @@ impl VersionData {
             15w32b	103
             15w32a	100
             ";
-        let mut name_to_id = BTreeMap::new();
-        let mut id_to_name = BTreeMap::new();
-
-        for line in raw_data.lines() {
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-            let mut parts = line.split_whitespace();
-            if let (Some(name), Some(id_str)) = (parts.next(), parts.next()) {
-                if let Ok(id) = id_str.parse::<i32>() {
-                    let name: Arc<str> = Arc::from(name);
-                    name_to_id.insert(name.clone(), id);
-                    id_to_name.insert(id, name);
-                }
-            }
-        }
-        Self {
-            name_to_id,
-            id_to_name,
-        }
-    }
-    pub fn get_id(&self, name: &str) -> Option<i32> {
-        self.name_to_id.get(name).copied()
-    }
-
-    pub fn get_name(&self, id: i32) -> Option<&Arc<str>> {
-        self.id_to_name.get(&id)
-    }
-}
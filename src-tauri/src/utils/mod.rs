@@ -1,9 +1,19 @@
+pub mod block_items;
 pub mod block_state_pos_list;
+pub mod block_volume;
+pub mod container_items;
+pub mod entities;
 pub mod extend_value;
 pub mod extend_write;
+pub mod inventory;
 pub mod loading;
+pub mod map_art_image;
+pub mod map_art_profile;
+pub mod material_export;
 pub mod minecraft_data;
 pub mod requirements;
 pub mod schematic_data;
 pub mod tile_entities;
-pub mod open_dev;
\ No newline at end of file
+pub mod open_dev;
+pub mod recipe_resolver;
+pub mod transform;pub mod version_migration;
@@ -0,0 +1,383 @@
+use crate::utils::minecraft_data::recipes_data::{Recipe, RecipeKind, RecipesData};
+use crate::utils::requirements::Requirements;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// 视为基础材料的物品, 避免与粒/块之间的互相合成来回展开
+const BASE_ITEMS: [&str; 12] = [
+    "minecraft:iron_ingot",
+    "minecraft:gold_ingot",
+    "minecraft:copper_ingot",
+    "minecraft:netherite_ingot",
+    "minecraft:diamond",
+    "minecraft:emerald",
+    "minecraft:lapis_lazuli",
+    "minecraft:redstone",
+    "minecraft:coal",
+    "minecraft:quartz",
+    "minecraft:amethyst_shard",
+    "minecraft:wheat",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeMode {
+    // 消耗基础材料最少的配方
+    #[default]
+    Cheapest,
+    // 有切石机配方时优先使用
+    Stonecutter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeNode {
+    pub item: String,
+    pub count: i64,
+    // 为 None 时为基础材料
+    pub recipe: Option<String>,
+    pub kind: Option<RecipeKind>,
+    pub crafts: i64,
+    // 按批次合成后多出的数量
+    pub surplus: i64,
+    pub children: Vec<RecipeNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeBreakdown {
+    pub mode: RecipeMode,
+    pub tree: Vec<RecipeNode>,
+    // 合并同一中间产物后的基础材料总数
+    pub totals: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Clone)]
+struct Choice {
+    recipe: String,
+    kind: RecipeKind,
+    count: u32,
+    ingredients: Vec<(String, u32)>,
+}
+
+pub struct RecipeResolver<'a> {
+    data: &'a RecipesData,
+    mode: RecipeMode,
+    // 不依赖展开路径的结果
+    costs: HashMap<String, (f64, Option<Choice>)>,
+    choices: HashMap<String, Choice>,
+}
+
+fn crafts_for(count: i64, per_craft: u32) -> i64 {
+    (count + per_craft as i64 - 1) / per_craft as i64
+}
+
+impl<'a> RecipeResolver<'a> {
+    pub fn new(data: &'a RecipesData, mode: RecipeMode) -> Self {
+        Self {
+            data,
+            mode,
+            costs: HashMap::new(),
+            choices: HashMap::new(),
+        }
+    }
+
+    fn is_base(item: &str) -> bool {
+        item.starts_with('#') || BASE_ITEMS.contains(&item)
+    }
+
+    // 存储方块拆回 9 个物品的配方不参与展开
+    fn is_unpacking(recipe: &Recipe) -> bool {
+        recipe.count == 9 && recipe.ingredients.len() == 1 && recipe.ingredients[0].1 == 1
+    }
+
+    fn better(&self, cost: f64, kind: RecipeKind, best_cost: f64, best_kind: RecipeKind) -> bool {
+        if self.mode == RecipeMode::Stonecutter && kind != best_kind {
+            return kind == RecipeKind::Stonecutting;
+        }
+        if (cost - best_cost).abs() > 1e-9 {
+            return cost < best_cost;
+        }
+        // 花费相同时优先工作台
+        kind == RecipeKind::Crafting && best_kind != RecipeKind::Crafting
+    }
+
+    // 每个物品折合的基础材料数量与选用的配方; 第三项为是否跳过了正在展开的物品,
+    // 跳过时结果依赖展开路径, 不写入缓存
+    fn evaluate(&mut self, item: &str, visiting: &mut Vec<String>) -> (f64, Option<Choice>, bool) {
+        if let Some((cost, choice)) = self.costs.get(item) {
+            return (*cost, choice.clone(), false);
+        }
+        if Self::is_base(item) {
+            return (1.0, None, false);
+        }
+        visiting.push(item.to_string());
+        let data = self.data;
+        let mut best: Option<(f64, Choice)> = None;
+        let mut cyclic = false;
+        for recipe in data.recipes_for(item) {
+            if Self::is_unpacking(recipe) {
+                continue;
+            }
+            let (result, skipped) = self.recipe_cost(recipe, visiting);
+            cyclic |= skipped;
+            let Some((cost, choice)) = result else {
+                continue;
+            };
+            let replace = match &best {
+                Some((best_cost, best_choice)) => {
+                    self.better(cost, choice.kind, *best_cost, best_choice.kind)
+                }
+                None => true,
+            };
+            if replace {
+                best = Some((cost, choice));
+            }
+        }
+        visiting.pop();
+        let (cost, choice) = match best {
+            Some((cost, choice)) => (cost, Some(choice)),
+            None => (1.0, None),
+        };
+        if !cyclic {
+            self.costs.insert(item.to_string(), (cost, choice.clone()));
+        }
+        (cost, choice, cyclic)
+    }
+
+    // 原料中的物品正在展开时跳过, 没有可用原料时返回 None
+    fn recipe_cost(
+        &mut self,
+        recipe: &Recipe,
+        visiting: &mut Vec<String>,
+    ) -> (Option<(f64, Choice)>, bool) {
+        let mut total = 0.0;
+        let mut ingredients = Vec::new();
+        let mut cyclic = false;
+        for (options, n) in &recipe.ingredients {
+            let mut best: Option<(String, f64)> = None;
+            for item in options.iter().flat_map(|option| self.data.expand(option)) {
+                if visiting.contains(&item) {
+                    cyclic = true;
+                    continue;
+                }
+                let (cost, _, skipped) = self.evaluate(&item, visiting);
+                cyclic |= skipped;
+                if best.as_ref().is_none_or(|(_, best_cost)| cost < *best_cost) {
+                    best = Some((item, cost));
+                }
+            }
+            let Some((item, cost)) = best else {
+                return (None, cyclic);
+            };
+            total += cost * *n as f64;
+            ingredients.push((item, *n));
+        }
+        let choice = Choice {
+            recipe: recipe.id.clone(),
+            kind: recipe.kind,
+            count: recipe.count,
+            ingredients,
+        };
+        (Some((total / recipe.count as f64, choice)), cyclic)
+    }
+
+    // 按物品单独展开时的配方选用, 原料回到当前路径时改为排除路径上的物品重新选择,
+    // 保证记录的配方之间不存在循环
+    fn assign(&mut self, item: &str, path: &mut Vec<String>, done: &mut HashSet<String>) {
+        if done.contains(item) {
+            return;
+        }
+        let (_, mut choice, _) = self.evaluate(item, &mut Vec::new());
+        let loops_back = choice.as_ref().is_some_and(|choice| {
+            choice
+                .ingredients
+                .iter()
+                .any(|(ingredient, _)| ingredient == item || path.contains(ingredient))
+        });
+        if loops_back {
+            choice = self.evaluate(item, &mut path.clone()).1;
+        }
+        path.push(item.to_string());
+        if let Some(choice) = &choice {
+            for (ingredient, _) in &choice.ingredients {
+                self.assign(ingredient, path, done);
+            }
+        }
+        path.pop();
+        done.insert(item.to_string());
+        if let Some(choice) = choice {
+            self.choices.insert(item.to_string(), choice);
+        }
+    }
+
+    fn node(&self, item: &str, count: i64, path: &mut Vec<String>) -> RecipeNode {
+        let choice = self
+            .choices
+            .get(item)
+            .filter(|_| !path.iter().any(|p| p == item));
+        let Some(choice) = choice else {
+            return RecipeNode {
+                item: item.to_string(),
+                count,
+                recipe: None,
+                kind: None,
+                crafts: 0,
+                surplus: 0,
+                children: Vec::new(),
+            };
+        };
+        let crafts = crafts_for(count, choice.count);
+        path.push(item.to_string());
+        let children = choice
+            .ingredients
+            .iter()
+            .map(|(ingredient, n)| self.node(ingredient, crafts * *n as i64, path))
+            .collect();
+        path.pop();
+        RecipeNode {
+            item: item.to_string(),
+            count,
+            recipe: Some(choice.recipe.clone()),
+            kind: Some(choice.kind),
+            crafts,
+            surplus: crafts * choice.count as i64 - count,
+            children,
+        }
+    }
+
+    fn post_order(&self, item: &str, visited: &mut HashSet<String>, order: &mut Vec<String>) {
+        if !visited.insert(item.to_string()) {
+            return;
+        }
+        if let Some(choice) = self.choices.get(item) {
+            for (ingredient, _) in &choice.ingredients {
+                self.post_order(ingredient, visited, order);
+            }
+        }
+        order.push(item.to_string());
+    }
+
+    // 按依赖顺序累加需求, 同一中间产物合并后再按批次取整
+    fn flatten(&self, items: &[(String, i64)]) -> BTreeMap<String, i64> {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for (item, _) in items {
+            self.post_order(item, &mut visited, &mut order);
+        }
+        let mut demand: HashMap<String, i64> = items.iter().cloned().collect();
+        let mut totals = BTreeMap::new();
+        for item in order.iter().rev() {
+            let need = demand.get(item).copied().unwrap_or(0);
+            if need <= 0 {
+                continue;
+            }
+            match self.choices.get(item) {
+                Some(choice) => {
+                    let crafts = crafts_for(need, choice.count);
+                    for (ingredient, n) in &choice.ingredients {
+                        *demand.entry(ingredient.clone()).or_insert(0) += crafts * *n as i64;
+                    }
+                }
+                None => {
+                    totals.insert(item.clone(), need);
+                }
+            }
+        }
+        totals
+    }
+
+    pub fn resolve(mut self, requirements: &Requirements) -> RecipeBreakdown {
+        let mut items: Vec<(String, i64)> = requirements
+            .get_requirements()
+            .iter()
+            .filter(|(_, &count)| count > 0)
+            .map(|(id, &count)| (id.name.to_string(), count as i64))
+            .collect();
+        items.sort();
+        let mut done = HashSet::new();
+        for (item, _) in &items {
+            self.assign(item, &mut Vec::new(), &mut done);
+        }
+        let tree = items
+            .iter()
+            .map(|(item, count)| self.node(item, *count, &mut Vec::new()))
+            .collect();
+        let totals = self.flatten(&items);
+        RecipeBreakdown {
+            mode: self.mode,
+            tree,
+            totals,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockId;
+    use std::sync::Arc;
+
+    fn recipe(id: &str, ingredient: &str, n: u32, result: &str, count: u32) -> Recipe {
+        Recipe {
+            id: id.to_string(),
+            kind: RecipeKind::Crafting,
+            ingredients: vec![(vec![ingredient.to_string()], n)],
+            result: result.to_string(),
+            count,
+        }
+    }
+
+    fn recipes(list: Vec<Recipe>) -> RecipesData {
+        let mut data = RecipesData::default();
+        for recipe in list {
+            data.recipes
+                .entry(recipe.result.clone())
+                .or_default()
+                .push(recipe);
+        }
+        data
+    }
+
+    fn requirements(items: &[(&str, i32)]) -> Requirements {
+        let mut requirements = Requirements::new();
+        for (item, count) in items {
+            requirements.set_requirement(
+                BlockId {
+                    name: Arc::from(*item),
+                },
+                *count,
+            );
+        }
+        requirements
+    }
+
+    // a 与 b 可以互相合成, 单独展开时各自都会选用对方的配方
+    fn cycle_data() -> RecipesData {
+        recipes(vec![
+            recipe("a_from_x", "x", 4, "a", 1),
+            recipe("a_from_b", "b", 1, "a", 1),
+            recipe("b_from_a", "a", 1, "b", 2),
+            recipe("b_from_y", "y", 3, "b", 1),
+        ])
+    }
+
+    #[test]
+    fn two_recipe_cycle_is_broken() {
+        let data = cycle_data();
+        let breakdown = RecipeResolver::new(&data, RecipeMode::Cheapest)
+            .resolve(&requirements(&[("a", 1), ("b", 1)]));
+        assert_eq!(breakdown.totals, BTreeMap::from([("y".to_string(), 6)]));
+    }
+
+    #[test]
+    fn cycle_items_alone_use_their_cheapest_recipe() {
+        let data = cycle_data();
+        let breakdown =
+            RecipeResolver::new(&data, RecipeMode::Cheapest).resolve(&requirements(&[("b", 2)]));
+        assert_eq!(breakdown.tree[0].recipe.as_deref(), Some("b_from_a"));
+        assert_eq!(breakdown.totals, BTreeMap::from([("x".to_string(), 4)]));
+        let breakdown =
+            RecipeResolver::new(&data, RecipeMode::Cheapest).resolve(&requirements(&[("a", 1)]));
+        assert_eq!(breakdown.tree[0].recipe.as_deref(), Some("a_from_b"));
+        assert_eq!(breakdown.totals, BTreeMap::from([("y".to_string(), 3)]));
+    }
+}
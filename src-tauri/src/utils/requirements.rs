@@ -1,15 +1,38 @@
-use crate::utils::block_state_pos_list::{BlockId, BlockStatePosList};
+use crate::utils::block_items::block_items;
+use crate::utils::block_state_pos_list::{BlockData as BlockState, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::container_items::{container_contents, ContentItem};
+use crate::utils::entities::EntitiesList;
+use crate::utils::material_export::MaterialRow;
 use crate::utils::minecraft_data::je_blocks_data::BlocksData;
 use crate::utils::schematic_data::SchematicError;
+use crate::utils::tile_entities::TileEntitiesList;
+use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::iter::ParallelIterator;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Requirements {
+    // 按物品统计的材料
     requirements: HashMap<BlockId, i32>,
+    #[serde(default)]
+    entities: HashMap<BlockId, i32>,
+    // 未经物品换算的方块数量
+    #[serde(default)]
+    raw_blocks: HashMap<BlockId, i32>,
+    // 容器中预先放入的物品
+    #[serde(default)]
+    contents: Vec<ContentItem>,
+    // 地图画支撑方块/参考行等辅助材料
+    #[serde(default)]
+    support: HashMap<BlockId, i32>,
+}
+
+fn default_category() -> String {
+    "block".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -17,6 +40,14 @@ struct BlockData {
     id: String,
     zh_cn: String,
     num: i64,
+    #[serde(default = "default_category")]
+    category: String,
+    // 按请求语言填充, 入库的数据不含此字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    // 容器物品的 NBT, 用于区分附魔/命名等不同物品
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nbt: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -27,7 +58,70 @@ impl Requirements {
     pub fn new() -> Self {
         Self {
             requirements: HashMap::new(),
+            entities: HashMap::new(),
+            raw_blocks: HashMap::new(),
+            contents: Vec::new(),
+            support: HashMap::new(),
+        }
+    }
+
+    // 实体 (盔甲架/物品展示框/矿车等) 单独计数, 不混入方块数量
+    pub fn with_entities(mut self, entities: &EntitiesList) -> Self {
+        for entity in &entities.elements {
+            let Some(id) = entity.get_id() else {
+                continue;
+            };
+            *self
+                .entities
+                .entry(BlockId {
+                    name: Arc::from(id),
+                })
+                .or_insert(0) += 1;
         }
+        self
+    }
+
+    // 箱子/漏斗/潜影盒等容器中的物品, 包括潜影盒内嵌的物品
+    pub fn with_contents(mut self, tiles: &TileEntitiesList) -> Self {
+        self.contents = container_contents(tiles);
+        self
+    }
+
+    // 从方块数量中划出辅助材料, 单独列为 support 分类
+    pub fn with_support(mut self, support: &BTreeMap<String, u64>) -> Self {
+        for (id, &count) in support {
+            let id = BlockId {
+                name: Arc::from(id.as_str()),
+            };
+            let Some(total) = self.requirements.get_mut(&id) else {
+                continue;
+            };
+            let moved = (count.min(i32::MAX as u64) as i32).min(*total);
+            *total -= moved;
+            if *total == 0 {
+                self.requirements.remove(&id);
+            }
+            if moved > 0 {
+                *self.support.entry(id).or_insert(0) += moved;
+            }
+        }
+        self
+    }
+
+    pub fn get_support(&self) -> &HashMap<BlockId, i32> {
+        &self.support
+    }
+
+    pub fn get_entities(&self) -> &HashMap<BlockId, i32> {
+        &self.entities
+    }
+
+    pub fn get_raw_blocks(&self) -> &HashMap<BlockId, i32> {
+        &self.raw_blocks
+    }
+
+    pub fn get_contents(&self) -> &[ContentItem] {
+        &self.contents
     }
 
     pub fn set_requirement(&mut self, key: BlockId, value: i32) {
@@ -60,6 +154,29 @@ impl Requirements {
     pub fn export_to_string(&self) -> String {
         serde_json::to_string(&self.requirements).unwrap_or_default()
     }
+
+    // 从数据库中保存的需求表还原物品数量, 还原方块/辅助材料/容器物品三类
+    pub fn from_requirement_str(json: &str) -> Result<Self, SchematicError> {
+        let map: HashMap<String, BlockData> =
+            serde_json::from_str(json).map_err(SchematicError::Json)?;
+        let mut requirements = Self::new();
+        for block in map.into_values() {
+            let id = BlockId {
+                name: Arc::from(block.id.as_str()),
+            };
+            match block.category.as_str() {
+                "block" => requirements.add_requirement(id, block.num as i32),
+                "support" => *requirements.support.entry(id).or_insert(0) += block.num as i32,
+                "contents" => requirements.contents.push(ContentItem {
+                    id: block.id,
+                    nbt: block.nbt,
+                    count: block.num,
+                }),
+                _ => {}
+            }
+        }
+        Ok(requirements)
+    }
 }
 
 impl RequirementStr {
@@ -93,19 +210,88 @@ impl RequirementStr {
 
     pub fn from_requirements(req: &Requirements, data: &BlocksData) -> Self {
         let mut map = HashMap::new();
-
-        for (block_id, &count) in req.get_requirements() {
-            let zh_cn = data
-                .get_zh_cn(&block_id.name.replace("minecraft:", ""))
+        let zh_cn = |block_id: &BlockId| {
+            data.get_zh_cn(&block_id.name.replace("minecraft:", ""))
                 .map(|s| s.to_owned())
-                .unwrap_or_else(|| block_id.name.to_string());
+                .unwrap_or_else(|| block_id.name.to_string())
+        };
 
+        for (block_id, &count) in req.get_requirements() {
             map.insert(
                 block_id.clone(),
                 BlockData {
                     id: block_id.name.to_string(),
-                    zh_cn,
+                    zh_cn: zh_cn(block_id),
                     num: count as i64,
+                    category: default_category(),
+                    name: None,
+                    nbt: None,
+                },
+            );
+        }
+        // 原始方块数量单独存放, 键加上 raw: 前缀
+        for (block_id, &count) in req.get_raw_blocks() {
+            map.insert(
+                BlockId {
+                    name: Arc::from(format!("raw:{}", block_id.name)),
+                },
+                BlockData {
+                    id: block_id.name.to_string(),
+                    zh_cn: zh_cn(block_id),
+                    num: count as i64,
+                    category: "raw".to_string(),
+                    name: None,
+                    nbt: None,
+                },
+            );
+        }
+        // 实体与同名方块 (如 minecraft:chest_minecart) 区分开, 键加上分类前缀
+        for (entity_id, &count) in req.get_entities() {
+            map.insert(
+                BlockId {
+                    name: Arc::from(format!("entity:{}", entity_id.name)),
+                },
+                BlockData {
+                    id: entity_id.name.to_string(),
+                    zh_cn: zh_cn(entity_id),
+                    num: count as i64,
+                    category: "entity".to_string(),
+                    name: None,
+                    nbt: None,
+                },
+            );
+        }
+        for (block_id, &count) in req.get_support() {
+            map.insert(
+                BlockId {
+                    name: Arc::from(format!("support:{}", block_id.name)),
+                },
+                BlockData {
+                    id: block_id.name.to_string(),
+                    zh_cn: zh_cn(block_id),
+                    num: count as i64,
+                    category: "support".to_string(),
+                    name: None,
+                    nbt: None,
+                },
+            );
+        }
+        // 容器物品按 id + NBT 区分, 键加上 contents: 前缀
+        for item in req.get_contents() {
+            let nbt = item.nbt.as_deref().unwrap_or_default();
+            map.insert(
+                BlockId {
+                    name: Arc::from(format!("contents:{}{}", item.id, nbt)),
+                },
+                BlockData {
+                    id: item.id.clone(),
+                    zh_cn: zh_cn(&BlockId {
+                        name: Arc::from(item.id.as_str()),
+                    }),
+                    num: item.count,
+                    category: "contents".to_string(),
+                    name: None,
+                    nbt: item.nbt.clone(),
                 },
             );
         }
@@ -113,39 +299,113 @@ impl RequirementStr {
         Self { requirements: map }
     }
 
+    // 为数据库中保存的需求表补充指定语言的名称
+    pub fn localize_str(
+        json: &str,
+        data: &BlocksData,
+        locale: &str,
+    ) -> Result<String, SchematicError> {
+        let mut map: HashMap<String, BlockData> =
+            serde_json::from_str(json).map_err(SchematicError::Json)?;
+        for block in map.values_mut() {
+            block.name = Some(data.get_locale_name(&block.id, locale));
+        }
+        serde_json::to_string(&map).map_err(SchematicError::Json)
+    }
+
+    // 需求表中的方块材料, 按数量从多到少排列, 未指定语言时使用中文名
+    pub fn material_rows(
+        json: &str,
+        data: &BlocksData,
+        locale: Option<&str>,
+    ) -> Result<Vec<MaterialRow>, SchematicError> {
+        let map: HashMap<String, BlockData> =
+            serde_json::from_str(json).map_err(SchematicError::Json)?;
+        let mut rows: Vec<MaterialRow> = map
+            .into_values()
+            .filter(|block| block.category == default_category() && block.num > 0)
+            .map(|block| MaterialRow {
+                name: match locale {
+                    Some(locale) => data.get_locale_name(&block.id, locale),
+                    None => block.zh_cn,
+                },
+                id: block.id,
+                count: block.num,
+            })
+            .collect();
+        rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.id.cmp(&b.id)));
+        Ok(rows)
+    }
+
     pub fn par_iter(&self) -> rayon::collections::hash_map::Iter<'_, BlockId, BlockData> {
         self.requirements.par_iter()
     }
 }
 
-pub fn get_requirements(blocks: &BlockStatePosList) -> Result<Requirements, SchematicError> {
+pub fn get_requirements(blocks: &BlockVolume) -> Result<Requirements, SchematicError> {
+    Ok(requirements_from_counts(
+        blocks.palette(),
+        &blocks.count_states(),
+    ))
+}
+
+// 按分组 (层/区段) 分别统计, group 返回方块所属的分组下标
+pub fn get_requirements_by<F>(
+    blocks: &BlockVolume,
+    groups: usize,
+    group: F,
+) -> Result<Vec<Requirements>, SchematicError>
+where
+    F: Fn(&BlockPos) -> Option<usize> + Sync,
+{
+    Ok(blocks
+        .count_states_by(groups, group)
+        .iter()
+        .map(|counts| requirements_from_counts(blocks.palette(), counts))
+        .collect())
+}
+
+// counts 与调色板下标对应
+fn requirements_from_counts(palette: &[Arc<BlockState>], counts: &[u64]) -> Requirements {
     let air = Arc::from("minecraft:air");
-    let requirements_map = blocks
-        .elements
+    let (raw_blocks, requirements) = palette
         .par_iter()
+        .zip(counts.par_iter())
         .fold(
-            || HashMap::new(),
-            |mut acc, block| {
-                let data = Arc::as_ref(&block.block);
-                if data.id.name == air {
-                    return acc;
+            || (HashMap::new(), HashMap::new()),
+            |(mut raw, mut items), (block, &count)| {
+                let data = Arc::as_ref(block);
+                if data.id.name == air || count == 0 {
+                    return (raw, items);
+                }
+                *raw.entry(data.id.clone()).or_insert(0) += count as i32;
+                for (item, per_block) in block_items(data) {
+                    let item_id = BlockId {
+                        name: Arc::from(item),
+                    };
+                    *items.entry(item_id).or_insert(0) += count as i32 * per_block;
                 }
-                let block_id = data.id.clone();
-                *acc.entry(block_id).or_insert(0) += 1;
-                acc
+                (raw, items)
             },
         )
         .reduce(
-            || HashMap::new(),
-            |mut a, b| {
-                for (k, v) in b {
-                    *a.entry(k).or_insert(0) += v;
+            || (HashMap::new(), HashMap::new()),
+            |(mut raw_a, mut items_a), (raw_b, items_b)| {
+                for (k, v) in raw_b {
+                    *raw_a.entry(k).or_insert(0) += v;
                 }
-                a
+                for (k, v) in items_b {
+                    *items_a.entry(k).or_insert(0) += v;
+                }
+                (raw_a, items_a)
             },
         );
 
-    Ok(Requirements {
-        requirements: requirements_map,
-    })
+    Requirements {
+        requirements,
+        entities: HashMap::new(),
+        raw_blocks,
+        contents: Vec::new(),
+        support: HashMap::new(),
+    }
 }
@@ -1,4 +1,6 @@
-use crate::utils::block_state_pos_list::{BlockPos, BlockStatePosList};
+use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::entities::EntitiesList;
 use crate::utils::tile_entities::TileEntitiesList;
 use flate2::CompressError;
 use flate2::DecompressError;
@@ -55,24 +57,119 @@ impl Size {
         format!("{},{},{}", self.width, self.height, self.length)
     }
 }
+
+// Litematica 命名选区, size 为负时选区从 position 向负方向延伸
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub position: BlockPos,
+    pub size: BlockPos,
+}
+
+impl Region {
+    pub fn min(&self) -> BlockPos {
+        let corner = |pos: i32, size: i32| if size < 0 { pos + size + 1 } else { pos };
+        BlockPos {
+            x: corner(self.position.x, self.size.x),
+            y: corner(self.position.y, self.size.y),
+            z: corner(self.position.z, self.size.z),
+        }
+    }
+
+    pub fn max(&self) -> BlockPos {
+        let min = self.min();
+        let size = self.abs_size();
+        BlockPos {
+            x: min.x + size.x - 1,
+            y: min.y + size.y - 1,
+            z: min.z + size.z - 1,
+        }
+    }
+
+    pub fn abs_size(&self) -> BlockPos {
+        BlockPos {
+            x: self.size.x.abs().max(1),
+            y: self.size.y.abs().max(1),
+            z: self.size.z.abs().max(1),
+        }
+    }
+
+    pub fn contains(&self, pos: &BlockPos) -> bool {
+        let (min, max) = (self.min(), self.max());
+        (min.x..=max.x).contains(&pos.x)
+            && (min.y..=max.y).contains(&pos.y)
+            && (min.z..=max.z).contains(&pos.z)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SchematicData {
-    pub blocks: BlockStatePosList,
+    pub blocks: BlockVolume,
     pub tile_entities_list: TileEntitiesList,
+    #[serde(default)]
+    pub entities: EntitiesList,
+    #[serde(default)]
+    pub regions: Vec<Region>,
     pub size: Size,
+    // 源蓝图记录的 DataVersion, 基岩版和建筑小帮手蓝图没有
+    #[serde(default)]
+    pub data_version: Option<i32>,
 }
 
 impl SchematicData {
     pub fn new(
-        blocks: BlockStatePosList,
+        blocks: BlockVolume,
         tile_entities_list: TileEntitiesList,
         size: Size,
     ) -> Self {
         Self {
             blocks,
             tile_entities_list,
+            entities: EntitiesList::default(),
+            regions: Vec::new(),
             size,
+            data_version: None,
         }
     }
+
+    pub fn with_entities(mut self, entities: EntitiesList) -> Self {
+        self.entities = entities;
+        self
+    }
+
+    pub fn with_regions(mut self, regions: Vec<Region>) -> Self {
+        self.regions = regions;
+        self
+    }
+
+    pub fn with_data_version(mut self, data_version: Option<i32>) -> Self {
+        self.data_version = data_version;
+        self
+    }
+
+    // 只保留指定选区内的方块与实体, 坐标保持不变
+    pub fn region(&self, name: &str) -> Result<Self, SchematicError> {
+        let region = self
+            .regions
+            .iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| SchematicError::MissingField(format!("region {}", name)))?;
+        let blocks = self.blocks.sub_volume(region.min(), region.max());
+        let tile_entities = self.tile_entities_list.filter_by_blocks(&blocks);
+        let entities = self.entities.filter_by_blocks(&blocks);
+        let size = region.abs_size();
+        Ok(Self::new(
+            blocks,
+            tile_entities,
+            Size {
+                width: size.x,
+                height: size.y,
+                length: size.z,
+            },
+        )
+        .with_entities(entities)
+        .with_regions(vec![region.clone()])
+        .with_data_version(self.data_version))
+    }
 }
 
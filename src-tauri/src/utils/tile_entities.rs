@@ -1,20 +1,99 @@
 use crate::utils::block_state_pos_list::BlockPos;
+use crate::utils::block_volume::BlockVolume;
 use fastnbt::Value;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+// nbt 统一为原版方块实体格式: 保留 id, 去掉坐标字段, 坐标由 pos 记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TileEntities {
-    pos: BlockPos,
-    nbt: Value,
+    pub pos: BlockPos,
+    pub nbt: Value,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct TileEntitiesList {
-    elements: Vec<TileEntities>,
+    pub elements: Vec<TileEntities>,
+}
+
+impl TileEntities {
+    pub fn new(pos: BlockPos, nbt: Value) -> Self {
+        Self { pos, nbt }
+    }
+
+    pub fn get_id(&self) -> Option<&str> {
+        match &self.nbt {
+            Value::Compound(c) => c.get("id").and_then(Value::as_str),
+            _ => None,
+        }
+    }
+
+    pub fn relative_to(&self, origin: &BlockPos) -> BlockPos {
+        BlockPos {
+            x: self.pos.x - origin.x,
+            y: self.pos.y - origin.y,
+            z: self.pos.z - origin.z,
+        }
+    }
 }
 
 impl TileEntitiesList {
     pub fn new() -> Self {
         Self::default()
     }
+
+    pub fn add(&mut self, pos: BlockPos, nbt: Value) {
+        self.elements.push(TileEntities::new(pos, nbt));
+    }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    pub fn merge(&mut self, other: Self) {
+        self.elements.extend(other.elements);
+    }
+
+    pub fn to_map(&self) -> HashMap<BlockPos, &Value> {
+        self.elements.iter().map(|te| (te.pos, &te.nbt)).collect()
+    }
+
+    // 只保留落在给定方块上的方块实体, 用于分割等操作
+    pub fn filter_by_blocks(&self, blocks: &BlockVolume) -> Self {
+        Self {
+            elements: self
+                .elements
+                .iter()
+                .filter(|te| blocks.contains(&te.pos))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+pub fn strip_keys(nbt: &Value, keys: &[&str]) -> Value {
+    match nbt {
+        Value::Compound(c) => Value::Compound(
+            c.iter()
+                .filter(|(k, _)| !keys.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+pub fn with_keys(nbt: &Value, extra: Vec<(&str, Value)>) -> Value {
+    let mut compound = match nbt {
+        Value::Compound(c) => c.clone(),
+        _ => HashMap::new(),
+    };
+    for (k, v) in extra {
+        compound.insert(k.to_string(), v);
+    }
+    Value::Compound(compound)
 }
@@ -0,0 +1,588 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::block_volume::BlockVolume;
+use crate::utils::entities::EntitiesList;
+use crate::utils::schematic_data::{Region, SchematicData, Size};
+use crate::utils::tile_entities::{with_keys, TileEntitiesList};
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+// 旋转角度为绕轴顺时针 (从轴正方向看向原点) 的度数, 只接受 90 的倍数
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Transform {
+    Rotate { axis: Axis, angle: i32 },
+    Mirror { axis: Axis },
+}
+
+const HORIZONTAL: [&str; 4] = ["north", "east", "south", "west"];
+
+// facing 可以朝上下的方块, 楼梯/门/床/箱子等其余方块只有水平朝向
+const VERTICAL_FACING: [&str; 17] = [
+    "observer",
+    "piston",
+    "sticky_piston",
+    "piston_head",
+    "moving_piston",
+    "dispenser",
+    "dropper",
+    "end_rod",
+    "lightning_rod",
+    "barrel",
+    "command_block",
+    "chain_command_block",
+    "repeating_command_block",
+    "amethyst_cluster",
+    "large_amethyst_bud",
+    "medium_amethyst_bud",
+    "small_amethyst_bud",
+];
+
+// 物品展示框的 Facing 顺序, 画只有后四个水平朝向且顺序不同
+const ENTITY_FACING: [&str; 6] = ["down", "up", "north", "south", "west", "east"];
+const PAINTING_FACING: [&str; 4] = ["south", "west", "north", "east"];
+
+fn allows_facing(name: &str, facing: &str) -> bool {
+    let name = name.strip_prefix("minecraft:").unwrap_or(name);
+    match facing {
+        "up" | "down" if name == "hopper" => facing == "down",
+        "up" | "down" => VERTICAL_FACING.contains(&name) || name.ends_with("shulker_box"),
+        _ => true,
+    }
+}
+
+impl Transform {
+    fn quarter_turns(angle: i32) -> Result<i32> {
+        if angle % 90 != 0 {
+            return Err(anyhow!(
+                "rotation angle must be a multiple of 90: {}",
+                angle
+            ));
+        }
+        Ok((angle / 90).rem_euclid(4))
+    }
+
+    // 拆成单步变换; 绕水平轴转 180 度等价于两次镜像, 这样半砖/楼梯的上下才能正确翻转
+    fn steps(&self) -> Result<Vec<Step>> {
+        let steps = match *self {
+            Transform::Rotate { axis, angle } => match (axis, Self::quarter_turns(angle)?) {
+                (Axis::X, 2) => vec![Step::Mirror(Axis::Y), Step::Mirror(Axis::Z)],
+                (Axis::Z, 2) => vec![Step::Mirror(Axis::X), Step::Mirror(Axis::Y)],
+                (axis, turns) => vec![Step::Rotate(axis); turns as usize],
+            },
+            Transform::Mirror { axis } => vec![Step::Mirror(axis)],
+        };
+        Ok(steps)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Rotate(Axis),
+    Mirror(Axis),
+}
+
+impl Step {
+    // 方向向量变换, 与原版一致: 绕 Y 顺时针 north -> east -> south -> west
+    fn vector(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        match self {
+            Step::Rotate(Axis::Y) => [-z, y, x],
+            Step::Rotate(Axis::X) => [x, -z, y],
+            Step::Rotate(Axis::Z) => [-y, x, z],
+            Step::Mirror(Axis::X) => [-x, y, z],
+            Step::Mirror(Axis::Y) => [x, -y, z],
+            Step::Mirror(Axis::Z) => [x, y, -z],
+        }
+    }
+
+    // 变换后的包围盒尺寸 (宽, 高, 长)
+    fn dims(&self, [w, h, l]: [f64; 3]) -> [f64; 3] {
+        match self {
+            Step::Rotate(Axis::Y) => [l, h, w],
+            Step::Rotate(Axis::X) => [w, l, h],
+            Step::Rotate(Axis::Z) => [h, w, l],
+            Step::Mirror(_) => [w, h, l],
+        }
+    }
+
+    // 包围盒内的连续坐标变换, dims 为变换前的尺寸
+    fn point(&self, [x, y, z]: [f64; 3], [w, h, l]: [f64; 3]) -> [f64; 3] {
+        match self {
+            Step::Rotate(Axis::Y) => [l - z, y, x],
+            Step::Rotate(Axis::X) => [x, l - z, y],
+            Step::Rotate(Axis::Z) => [h - y, x, z],
+            Step::Mirror(Axis::X) => [w - x, y, z],
+            Step::Mirror(Axis::Y) => [x, h - y, z],
+            Step::Mirror(Axis::Z) => [x, y, l - z],
+        }
+    }
+
+    fn direction(&self, name: &str) -> Option<&'static str> {
+        let vector = match name {
+            "north" => [0.0, 0.0, -1.0],
+            "south" => [0.0, 0.0, 1.0],
+            "east" => [1.0, 0.0, 0.0],
+            "west" => [-1.0, 0.0, 0.0],
+            "up" => [0.0, 1.0, 0.0],
+            "down" => [0.0, -1.0, 0.0],
+            _ => return None,
+        };
+        Some(match self.vector(vector) {
+            [x, _, _] if x > 0.5 => "east",
+            [x, _, _] if x < -0.5 => "west",
+            [_, y, _] if y > 0.5 => "up",
+            [_, y, _] if y < -0.5 => "down",
+            [_, _, z] if z > 0.5 => "south",
+            _ => "north",
+        })
+    }
+
+    fn axis(&self, name: &str) -> Option<&'static str> {
+        let vector = match name {
+            "x" => [1.0, 0.0, 0.0],
+            "y" => [0.0, 1.0, 0.0],
+            "z" => [0.0, 0.0, 1.0],
+            _ => return None,
+        };
+        Some(match self.vector(vector) {
+            [x, _, _] if x.abs() > 0.5 => "x",
+            [_, y, _] if y.abs() > 0.5 => "y",
+            _ => "z",
+        })
+    }
+
+    // 水平镜像会交换左右手性
+    fn is_mirror(&self) -> bool {
+        matches!(self, Step::Mirror(Axis::X) | Step::Mirror(Axis::Z))
+    }
+
+    // 水平面保持为水平面时, 四向连接和铁轨形状才能跟着变换
+    fn keeps_horizontal(&self) -> bool {
+        !matches!(self, Step::Rotate(Axis::X) | Step::Rotate(Axis::Z))
+    }
+
+    // 告示牌/旗帜/头颅的 0-15 朝向, 0 为南, 顺时针递增
+    fn rotation16(&self, value: i32) -> Option<i32> {
+        match self {
+            Step::Rotate(Axis::Y) => Some((value + 4).rem_euclid(16)),
+            Step::Mirror(Axis::X) => Some((16 - value).rem_euclid(16)),
+            Step::Mirror(Axis::Z) => Some((8 - value).rem_euclid(16)),
+            _ => None,
+        }
+    }
+
+    // 铁轨形状由两个方向组成, 原版命名中南北在前
+    fn rail_shape(&self, shape: &str) -> Option<String> {
+        if !self.keeps_horizontal() {
+            return None;
+        }
+        if let Some(dir) = shape.strip_prefix("ascending_") {
+            return self.direction(dir).map(|d| format!("ascending_{}", d));
+        }
+        let (a, b) = shape.split_once('_')?;
+        let (a, b) = (self.direction(a)?, self.direction(b)?);
+        Some(match (a, b) {
+            ("north", "south") | ("south", "north") => "north_south".to_string(),
+            ("east", "west") | ("west", "east") => "east_west".to_string(),
+            ("east" | "west", _) => format!("{}_{}", b, a),
+            _ => format!("{}_{}", a, b),
+        })
+    }
+
+    fn block(&self, block: &BlockData) -> BlockData {
+        let props = &block.properties;
+        let mut properties: BTreeMap<Arc<str>, Arc<str>> = BTreeMap::new();
+        let is_rail = block.id.name.ends_with("rail");
+        for (key, value) in props {
+            let new_value = match key.as_ref() {
+                // 只有水平朝向的方块转到上下时保留原朝向
+                "facing" => self
+                    .direction(value)
+                    .filter(|facing| allows_facing(&block.id.name, facing))
+                    .map(str::to_string),
+                "axis" => self.axis(value).map(str::to_string),
+                "rotation" => value
+                    .parse::<i32>()
+                    .ok()
+                    .and_then(|r| self.rotation16(r))
+                    .map(|r| r.to_string()),
+                "shape" if is_rail => self.rail_shape(value),
+                "shape" if self.is_mirror() => Some(swap_side(value)),
+                "hinge" | "type" if self.is_mirror() => Some(swap_side(value)),
+                "half" | "type" if self.flips_vertical() => Some(swap_vertical(value)),
+                _ => None,
+            };
+            // 墙/栅栏/玻璃板/红石线等的四向连接, 值跟随方向移动
+            if HORIZONTAL.contains(&key.as_ref()) && self.keeps_horizontal() {
+                if let Some(dir) = self.direction(key) {
+                    properties.insert(Arc::from(dir), value.clone());
+                    continue;
+                }
+            }
+            properties.insert(
+                key.clone(),
+                new_value.map(Arc::from).unwrap_or_else(|| value.clone()),
+            );
+        }
+        BlockData {
+            id: block.id.clone(),
+            properties,
+        }
+    }
+
+    // 只有上下翻转才影响 half/type
+    fn flips_vertical(&self) -> bool {
+        matches!(self, Step::Mirror(Axis::Y))
+    }
+
+    fn yaw(&self, yaw: f32) -> Option<f32> {
+        match self {
+            Step::Rotate(Axis::Y) => Some(yaw + 90.0),
+            Step::Mirror(Axis::X) => Some(-yaw),
+            Step::Mirror(Axis::Z) => Some(180.0 - yaw),
+            _ => None,
+        }
+    }
+}
+
+fn swap_side(value: &str) -> String {
+    match value {
+        "left" => "right".to_string(),
+        "right" => "left".to_string(),
+        "inner_left" => "inner_right".to_string(),
+        "inner_right" => "inner_left".to_string(),
+        "outer_left" => "outer_right".to_string(),
+        "outer_right" => "outer_left".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn swap_vertical(value: &str) -> String {
+    match value {
+        "top" => "bottom".to_string(),
+        "bottom" => "top".to_string(),
+        "upper" => "lower".to_string(),
+        "lower" => "upper".to_string(),
+        _ => value.to_string(),
+    }
+}
+
+impl SchematicData {
+    pub fn transform(&self, transform: Transform) -> Result<Self> {
+        Ok(transform
+            .steps()?
+            .into_iter()
+            .fold(self.clone(), |data, step| data.apply_step(step)))
+    }
+
+    pub fn rotate(&self, axis: Axis, angle: i32) -> Result<Self> {
+        self.transform(Transform::Rotate { axis, angle })
+    }
+
+    pub fn mirror(&self, axis: Axis) -> Self {
+        self.apply_step(Step::Mirror(axis))
+    }
+
+    fn apply_step(&self, step: Step) -> Self {
+        let Some((min, max)) = self.blocks.bounds() else {
+            return self.clone();
+        };
+        let dims = [
+            (max.x - min.x + 1) as f64,
+            (max.y - min.y + 1) as f64,
+            (max.z - min.z + 1) as f64,
+        ];
+        // 方块坐标取格子中心变换, 再落回格子
+        let move_block = |pos: BlockPos| {
+            let p = [
+                (pos.x - min.x) as f64 + 0.5,
+                (pos.y - min.y) as f64 + 0.5,
+                (pos.z - min.z) as f64 + 0.5,
+            ];
+            let [x, y, z] = step.point(p, dims);
+            BlockPos {
+                x: min.x + x.floor() as i32,
+                y: min.y + y.floor() as i32,
+                z: min.z + z.floor() as i32,
+            }
+        };
+
+        let mut blocks = BlockVolume::default();
+        let new_dims = step.dims(dims);
+        blocks.set_index(min, 0);
+        blocks.set_index(
+            BlockPos {
+                x: min.x + new_dims[0] as i32 - 1,
+                y: min.y + new_dims[1] as i32 - 1,
+                z: min.z + new_dims[2] as i32 - 1,
+            },
+            0,
+        );
+        let remap: Vec<u32> = self
+            .blocks
+            .palette()
+            .iter()
+            .map(|block| blocks.state_index(&Arc::new(step.block(block))))
+            .collect();
+        for (pos, index) in self.blocks.iter_indices() {
+            blocks.set_index(move_block(pos), remap[index as usize]);
+        }
+
+        let mut tile_entities = TileEntitiesList::default();
+        for te in &self.tile_entities_list.elements {
+            tile_entities.add(move_block(te.pos), te.nbt.clone());
+        }
+
+        let mut entities = EntitiesList::default();
+        for entity in &self.entities.elements {
+            let relative = [
+                entity.pos[0] - min.x as f64,
+                entity.pos[1] - min.y as f64,
+                entity.pos[2] - min.z as f64,
+            ];
+            let [x, y, z] = step.point(relative, dims);
+            let pos = [x + min.x as f64, y + min.y as f64, z + min.z as f64];
+            entities.add(pos, rotate_entity_nbt(&entity.nbt, step, &move_block));
+        }
+
+        // 选区原点与对角各自变换, 保留负尺寸的朝向
+        let regions = self
+            .regions
+            .iter()
+            .map(|region| {
+                let sign = |v: i32| if v < 0 { -1 } else { 1 };
+                let corner = BlockPos {
+                    x: region.position.x + region.size.x - sign(region.size.x),
+                    y: region.position.y + region.size.y - sign(region.size.y),
+                    z: region.position.z + region.size.z - sign(region.size.z),
+                };
+                let position = move_block(region.position);
+                let corner = move_block(corner);
+                let extent = |a: i32, b: i32| b - a + sign(b - a);
+                Region {
+                    name: region.name.clone(),
+                    position,
+                    size: BlockPos {
+                        x: extent(position.x, corner.x),
+                        y: extent(position.y, corner.y),
+                        z: extent(position.z, corner.z),
+                    },
+                }
+            })
+            .collect();
+
+        let size = step.dims([
+            self.size.width as f64,
+            self.size.height as f64,
+            self.size.length as f64,
+        ]);
+        SchematicData::new(
+            blocks,
+            tile_entities,
+            Size {
+                width: size[0] as i32,
+                height: size[1] as i32,
+                length: size[2] as i32,
+            },
+        )
+        .with_entities(entities)
+        .with_regions(regions)
+        .with_data_version(self.data_version)
+    }
+}
+
+// 实体修正水平朝向 (Rotation 的 yaw), 悬挂实体 (物品展示框/画) 还要修正附着方块和朝向
+fn rotate_entity_nbt(nbt: &Value, step: Step, move_block: &impl Fn(BlockPos) -> BlockPos) -> Value {
+    let Value::Compound(compound) = nbt else {
+        return nbt.clone();
+    };
+    let mut changes = Vec::new();
+    if let Some(Value::List(rotation)) = compound.get("Rotation") {
+        let new_yaw = match rotation.first() {
+            Some(Value::Float(yaw)) => step.yaw(*yaw),
+            _ => None,
+        };
+        if let Some(new_yaw) = new_yaw {
+            let mut new_rotation = rotation.clone();
+            new_rotation[0] = Value::Float(new_yaw.rem_euclid(360.0));
+            changes.push(("Rotation", Value::List(new_rotation)));
+        }
+    }
+    if let (Some(Value::Int(x)), Some(Value::Int(y)), Some(Value::Int(z))) = (
+        compound.get("TileX"),
+        compound.get("TileY"),
+        compound.get("TileZ"),
+    ) {
+        let tile = move_block(BlockPos {
+            x: *x,
+            y: *y,
+            z: *z,
+        });
+        changes.push(("TileX", Value::Int(tile.x)));
+        changes.push(("TileY", Value::Int(tile.y)));
+        changes.push(("TileZ", Value::Int(tile.z)));
+    }
+    let is_painting =
+        matches!(compound.get("id"), Some(Value::String(id)) if id.ends_with("painting"));
+    let directions: &[&str] = if is_painting {
+        &PAINTING_FACING
+    } else {
+        &ENTITY_FACING
+    };
+    // 旧版本为 Facing, 新版本的画为 facing
+    for key in ["Facing", "facing"] {
+        let Some(Value::Byte(facing)) = compound.get(key) else {
+            continue;
+        };
+        let new_facing = directions
+            .get(*facing as usize)
+            .and_then(|dir| step.direction(dir))
+            .and_then(|dir| directions.iter().position(|d| *d == dir));
+        if let Some(index) = new_facing {
+            changes.push((key, Value::Byte(index as i8)));
+        }
+    }
+    if changes.is_empty() {
+        return nbt.clone();
+    }
+    with_keys(nbt, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockId;
+
+    fn block(name: &str, properties: &[(&str, &str)]) -> Arc<BlockData> {
+        Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from(format!("minecraft:{}", name)),
+            },
+            properties: properties
+                .iter()
+                .map(|(k, v)| (Arc::from(*k), Arc::from(*v)))
+                .collect(),
+        })
+    }
+
+    fn schematic(blocks: &[(BlockPos, Arc<BlockData>)], size: Size) -> SchematicData {
+        let mut volume = BlockVolume::new();
+        for (pos, block) in blocks {
+            volume.add(*pos, block.clone());
+        }
+        SchematicData::new(volume, TileEntitiesList::default(), size)
+    }
+
+    // 相对包围盒最小点的坐标 -> 方块状态
+    fn states(data: &SchematicData) -> BTreeMap<(i32, i32, i32), String> {
+        let (min, _) = data.blocks.bounds().unwrap();
+        data.blocks
+            .iter()
+            .map(|(pos, block)| {
+                let properties: Vec<String> = block
+                    .properties
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect();
+                (
+                    (pos.x - min.x, pos.y - min.y, pos.z - min.z),
+                    format!("{}[{}]", block.id.name, properties.join(",")),
+                )
+            })
+            .collect()
+    }
+
+    fn pos(x: i32, y: i32, z: i32) -> BlockPos {
+        BlockPos { x, y, z }
+    }
+
+    fn size(width: i32, height: i32, length: i32) -> Size {
+        Size {
+            width,
+            height,
+            length,
+        }
+    }
+
+    #[test]
+    fn rotate_y_turns_facing_and_positions() {
+        let data = schematic(
+            &[
+                (pos(0, 0, 0), block("oak_stairs", &[("facing", "north")])),
+                (pos(1, 0, 0), block("stone", &[])),
+            ],
+            size(2, 1, 1),
+        );
+        let rotated = data.rotate(Axis::Y, 90).unwrap();
+        assert_eq!(rotated.size, size(1, 1, 2));
+        assert_eq!(
+            states(&rotated),
+            BTreeMap::from([
+                ((0, 0, 0), "minecraft:oak_stairs[facing=east]".to_string()),
+                ((0, 0, 1), "minecraft:stone[]".to_string()),
+            ])
+        );
+        let back = rotated.rotate(Axis::Y, 270).unwrap();
+        assert_eq!(states(&back), states(&data));
+    }
+
+    #[test]
+    fn mirror_x_swaps_sides() {
+        let data = schematic(
+            &[(
+                pos(0, 0, 0),
+                block(
+                    "stone_stairs",
+                    &[("facing", "east"), ("half", "top"), ("shape", "inner_left")],
+                ),
+            )],
+            size(1, 1, 1),
+        );
+        assert_eq!(
+            states(&data.mirror(Axis::X)),
+            BTreeMap::from([(
+                (0, 0, 0),
+                "minecraft:stone_stairs[facing=west,half=top,shape=inner_right]".to_string()
+            )])
+        );
+        assert_eq!(
+            states(&data.mirror(Axis::Y)),
+            BTreeMap::from([(
+                (0, 0, 0),
+                "minecraft:stone_stairs[facing=east,half=bottom,shape=inner_left]".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn rotate_x_keeps_horizontal_only_facing() {
+        let data = schematic(
+            &[
+                (pos(0, 0, 0), block("oak_stairs", &[("facing", "north")])),
+                (pos(0, 0, 1), block("observer", &[("facing", "north")])),
+            ],
+            size(1, 1, 2),
+        );
+        let rotated = data.rotate(Axis::X, 90).unwrap();
+        let states: Vec<String> = states(&rotated).into_values().collect();
+        assert!(states.contains(&"minecraft:oak_stairs[facing=north]".to_string()));
+        assert!(states.contains(&"minecraft:observer[facing=up]".to_string()));
+    }
+
+    #[test]
+    fn rejects_partial_rotation() {
+        let data = schematic(&[], size(1, 1, 1));
+        assert!(data.rotate(Axis::Y, 45).is_err());
+        assert!(data.rotate(Axis::Y, -90).is_ok());
+    }
+}
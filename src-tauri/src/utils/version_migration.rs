@@ -0,0 +1,399 @@
+use crate::utils::block_state_pos_list::{BlockData, BlockId};
+use crate::utils::minecraft_data::je_blocks_data::{release_key, BlocksData, SubData};
+use crate::utils::schematic_data::SchematicData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+type Release = (u32, u32);
+
+// 1.16 起墙的四个方向由 true/false 改为 none/low/tall
+const WALL_SINCE: Release = (16, 0);
+const WALL_SIDES: [&str; 4] = ["north", "east", "south", "west"];
+
+// 后加入的方块状态属性: (方块名后缀, 属性, 默认值, 加入版本)
+const ADDED_PROPERTIES: &[(&str, &str, &str, Release)] =
+    &[("_leaves", "waterlogged", "false", (19, 0))];
+
+const STONE_SHAPES: &[&str] = &["", "_slab", "_stairs", "_wall"];
+const BRICK_SHAPES: &[&str] = &["_slab", "_stairs", "_wall"];
+const WOOD_SHAPES: &[&str] = &[
+    "_planks",
+    "_stairs",
+    "_slab",
+    "_fence",
+    "_fence_gate",
+    "_door",
+    "_trapdoor",
+    "_button",
+    "_pressure_plate",
+    "_sign",
+    "_wall_sign",
+    "_hanging_sign",
+    "_wall_hanging_sign",
+    "_log",
+    "_wood",
+    "_leaves",
+    "_sapling",
+];
+const LOG_SHAPES: &[&str] = &["_log", "_wood"];
+
+// 目标版本中不存在时的替换: (材质, 替换材质, 形状后缀), 方块 ID 须为材质加其中一个后缀,
+// 替换结果仍不存在时继续套用
+const SUBSTITUTES: &[(&str, &str, &[&str])] = &[
+    ("tuff", "andesite", STONE_SHAPES),
+    ("polished_tuff", "polished_andesite", STONE_SHAPES),
+    ("tuff_brick", "stone_brick", BRICK_SHAPES),
+    ("tuff_bricks", "stone_bricks", &[""]),
+    ("chiseled_tuff", "chiseled_stone_bricks", &[""]),
+    ("chiseled_tuff_bricks", "chiseled_stone_bricks", &[""]),
+    ("deepslate", "stone", &[""]),
+    ("cobbled_deepslate", "cobblestone", STONE_SHAPES),
+    ("polished_deepslate", "polished_andesite", STONE_SHAPES),
+    ("deepslate_brick", "stone_brick", BRICK_SHAPES),
+    ("deepslate_tile", "stone_brick", BRICK_SHAPES),
+    ("deepslate_bricks", "stone_bricks", &[""]),
+    ("deepslate_tiles", "stone_bricks", &[""]),
+    ("cracked_deepslate_bricks", "cracked_stone_bricks", &[""]),
+    ("cracked_deepslate_tiles", "cracked_stone_bricks", &[""]),
+    ("chiseled_deepslate", "chiseled_stone_bricks", &[""]),
+    ("mud_brick", "brick", BRICK_SHAPES),
+    ("mud_bricks", "bricks", &[""]),
+    ("packed_mud", "dirt", &[""]),
+    ("calcite", "diorite", &[""]),
+    ("smooth_basalt", "basalt", &[""]),
+    ("basalt", "stone", &[""]),
+    ("polished_basalt", "smooth_stone", &[""]),
+    ("crimson_stem", "spruce_log", &[""]),
+    ("warped_stem", "dark_oak_log", &[""]),
+    ("crimson_hyphae", "spruce_wood", &[""]),
+    ("warped_hyphae", "dark_oak_wood", &[""]),
+    ("stripped_crimson_stem", "stripped_spruce_log", &[""]),
+    ("stripped_warped_stem", "stripped_dark_oak_log", &[""]),
+    ("stripped_crimson_hyphae", "stripped_spruce_wood", &[""]),
+    ("stripped_warped_hyphae", "stripped_dark_oak_wood", &[""]),
+    ("crimson", "spruce", WOOD_SHAPES),
+    ("warped", "dark_oak", WOOD_SHAPES),
+    ("bamboo_block", "oak_log", &[""]),
+    ("stripped_bamboo_block", "stripped_oak_log", &[""]),
+    ("bamboo_mosaic", "bamboo_planks", &[""]),
+    ("bamboo_mosaic", "bamboo", &["_slab", "_stairs"]),
+    ("bamboo", "oak", WOOD_SHAPES),
+    ("cherry", "birch", WOOD_SHAPES),
+    ("stripped_cherry", "stripped_birch", LOG_SHAPES),
+    ("mangrove", "jungle", WOOD_SHAPES),
+    ("stripped_mangrove", "stripped_jungle", LOG_SHAPES),
+    ("pale_oak", "dark_oak", WOOD_SHAPES),
+    ("stripped_pale_oak", "stripped_dark_oak", LOG_SHAPES),
+];
+
+// 铜质方块按氧化阶段与涂蜡前缀替换为同阶段的切制铜块/铜块
+const COPPER_STAGES: [&str; 3] = ["exposed_", "weathered_", "oxidized_"];
+const COPPER_SUBSTITUTES: &[(&str, &str)] = &[
+    ("copper_grate", "cut_copper"),
+    ("chiseled_copper", "cut_copper"),
+    ("copper_bulb", "copper_block"),
+];
+
+// 替换为完整方块时只保留这些属性, 其余属性目标方块没有
+const FULL_BLOCK_PROPERTIES: &[(&str, &[&str])] = &[("_log", &["axis"]), ("_wood", &["axis"])];
+
+// 以上都不可用时按形状退回最常见的方块
+const SHAPE_FALLBACKS: &[(&str, &str)] = &[
+    ("_stairs", "stone_brick_stairs"),
+    ("_slab", "stone_slab"),
+    ("_wall", "cobblestone_wall"),
+    ("_fence_gate", "oak_fence_gate"),
+    ("_fence", "oak_fence"),
+    ("_planks", "oak_planks"),
+    ("_log", "oak_log"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Substitution {
+    pub from: String,
+    pub to: String,
+    pub count: u64,
+}
+
+// 按替换表精确匹配方块 ID, 第二项为替换前后形状是否相同
+fn substitute_id(name: &str) -> Option<(String, bool)> {
+    let shaped = SUBSTITUTES
+        .iter()
+        .find_map(|(material, replacement, shapes)| {
+            let shape = name.strip_prefix(material)?;
+            shapes
+                .contains(&shape)
+                .then(|| (format!("{}{}", replacement, shape), !shape.is_empty()))
+        });
+    shaped.or_else(|| {
+        let (waxed, rest) = match name.strip_prefix("waxed_") {
+            Some(rest) => ("waxed_", rest),
+            None => ("", name),
+        };
+        let (stage, base) = COPPER_STAGES
+            .iter()
+            .find_map(|stage| Some((*stage, rest.strip_prefix(stage)?)))
+            .unwrap_or(("", rest));
+        let (_, target) = COPPER_SUBSTITUTES.iter().find(|(from, _)| *from == base)?;
+        // 氧化后的铜块没有 _block 后缀, 如 exposed_copper
+        let target = match (*target, stage) {
+            ("copper_block", stage) if !stage.is_empty() => "copper",
+            (target, _) => target,
+        };
+        Some((format!("{}{}{}", waxed, stage, target), false))
+    })
+}
+
+struct Migrator<'a> {
+    je_blocks: &'a BlocksData,
+    from: Option<Release>,
+    to: Release,
+}
+
+impl<'a> Migrator<'a> {
+    // 找到方块所属的方块表条目, 同名方块优先取源版本中的含义
+    fn entry(&self, name: &str) -> Option<&'a SubData> {
+        let indices = self.je_blocks.name_index.get(name)?;
+        let entries: Vec<&SubData> = indices.iter().map(|&i| &self.je_blocks.blocks[i]).collect();
+        if let Some(from) = self.from {
+            if let Some(entry) = entries.iter().find(|e| e.name_at(from) == Some(name)) {
+                return Some(entry);
+            }
+        }
+        // 源版本未知时取扁平化之后最晚使用该名称的条目
+        entries
+            .iter()
+            .filter_map(|e| {
+                e.version_map
+                    .iter()
+                    .filter(|(code, n)| n.as_str() == name && release_key(**code) >= (13, 0))
+                    .map(|(code, _)| release_key(*code))
+                    .max()
+                    .map(|key| (key, *e))
+            })
+            .max_by_key(|(key, _)| *key)
+            .map(|(_, e)| e)
+            .or_else(|| entries.first().copied())
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.je_blocks.exists_in(name, self.to) == Some(true)
+    }
+
+    // 替换后的名称, 第二项为原属性能否保留 (同形状替换时保留)
+    fn substitute(&self, name: &str) -> Option<(String, bool)> {
+        let mut current = name.to_string();
+        let mut keep = true;
+        // 替换表中的链不超过三步, 如 smooth_basalt -> basalt -> stone
+        for _ in 0..3 {
+            let Some((next, same_shape)) = substitute_id(&current) else {
+                break;
+            };
+            current = next;
+            keep &= same_shape;
+            if self.exists(&current) {
+                return Some((current, keep));
+            }
+        }
+        SHAPE_FALLBACKS
+            .iter()
+            .find(|(suffix, _)| name.ends_with(suffix))
+            .map(|(_, fallback)| fallback.to_string())
+            .filter(|fallback| self.exists(fallback))
+            .map(|fallback| (fallback, true))
+    }
+
+    // 返回目标版本中的名称与原属性能否保留, None 表示没有可用替代
+    fn rename(&self, name: &str) -> Option<(String, bool)> {
+        let Some(entry) = self.entry(name) else {
+            return Some((name.to_string(), true));
+        };
+        match entry.name_at(self.to) {
+            Some(target) if self.exists(target) => Some((target.to_string(), true)),
+            _ if self.exists(name) => Some((name.to_string(), true)),
+            _ => self.substitute(name),
+        }
+    }
+
+    fn migrate_properties(&self, block: &mut BlockData) {
+        let name = block.id.name.as_ref();
+        if name.ends_with("_wall") {
+            for side in WALL_SIDES {
+                let Some(value) = block.properties.get(side) else {
+                    continue;
+                };
+                let value = match (self.to >= WALL_SINCE, value.as_ref()) {
+                    (true, "true") => "low",
+                    (true, "false") => "none",
+                    (false, "low") | (false, "tall") => "true",
+                    (false, "none") => "false",
+                    _ => continue,
+                };
+                block.properties.insert(Arc::from(side), Arc::from(value));
+            }
+        }
+        for (suffix, key, default, since) in ADDED_PROPERTIES {
+            if !name.ends_with(suffix) {
+                continue;
+            }
+            if self.to < *since {
+                block.properties.remove(*key);
+            } else if !block.properties.is_empty() && !block.properties.contains_key(*key) {
+                block
+                    .properties
+                    .insert(Arc::from(*key), Arc::from(*default));
+            }
+        }
+    }
+
+    // None 表示无需改动或没有可用替代, 后者由写出时的版本检查给出警告
+    fn migrate(&self, block: &BlockData) -> Option<BlockData> {
+        let name = block.id.name.strip_prefix("minecraft:")?;
+        let (new_name, keep) = self.rename(name)?;
+        let mut properties = block.properties.clone();
+        if !keep {
+            let kept = FULL_BLOCK_PROPERTIES
+                .iter()
+                .find(|(suffix, _)| new_name.ends_with(suffix))
+                .map(|(_, kept)| *kept)
+                .unwrap_or_default();
+            properties.retain(|key, _| kept.contains(&key.as_ref()));
+        }
+        let mut new_block = BlockData {
+            id: BlockId {
+                name: Arc::from(format!("minecraft:{}", new_name)),
+            },
+            properties,
+        };
+        self.migrate_properties(&mut new_block);
+        (new_block != *block).then_some(new_block)
+    }
+}
+
+fn state_string(block: &BlockData) -> String {
+    if block.properties.is_empty() {
+        return block.id.name.to_string();
+    }
+    let properties: Vec<String> = block
+        .properties
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    format!("{}[{}]", block.id.name, properties.join(","))
+}
+
+impl SchematicData {
+    // 在两个游戏版本之间迁移方块名称与属性, from 为 None 时按名称推断源版本
+    pub fn migrate_version(
+        &mut self,
+        from: Option<Release>,
+        to: Release,
+        je_blocks: &BlocksData,
+    ) -> Vec<Substitution> {
+        let migrator = Migrator {
+            je_blocks,
+            from,
+            to,
+        };
+        let mut substitutions = Vec::new();
+        let counts = self.blocks.count_states();
+        let mut mapping: HashMap<Arc<BlockData>, Arc<BlockData>> = HashMap::new();
+        for (block, &count) in self.blocks.palette().iter().zip(counts.iter()).skip(1) {
+            if count == 0 {
+                continue;
+            }
+            if let Some(new_block) = migrator.migrate(block) {
+                substitutions.push(Substitution {
+                    from: state_string(block),
+                    to: state_string(&new_block),
+                    count,
+                });
+                mapping.insert(block.clone(), Arc::new(new_block));
+            }
+        }
+        if !mapping.is_empty() {
+            self.blocks
+                .map_palette(|block| mapping.get(block).cloned().unwrap_or_else(|| block.clone()));
+        }
+        substitutions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::block_state_pos_list::BlockPos;
+    use crate::utils::block_volume::BlockVolume;
+    use crate::utils::schematic_data::Size;
+    use crate::utils::tile_entities::TileEntitiesList;
+    use std::collections::BTreeMap;
+
+    fn block(name: &str, properties: &[(&str, &str)]) -> Arc<BlockData> {
+        Arc::new(BlockData {
+            id: BlockId {
+                name: Arc::from(format!("minecraft:{}", name)),
+            },
+            properties: properties
+                .iter()
+                .map(|(k, v)| (Arc::from(*k), Arc::from(*v)))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    }
+
+    fn migrate(block: Arc<BlockData>, to: Release) -> Vec<Substitution> {
+        let je_blocks = BlocksData::new().unwrap();
+        let mut blocks = BlockVolume::new();
+        blocks.add(BlockPos { x: 0, y: 0, z: 0 }, block);
+        let mut data = SchematicData::new(
+            blocks,
+            TileEntitiesList::default(),
+            Size {
+                width: 1,
+                height: 1,
+                length: 1,
+            },
+        );
+        data.migrate_version(None, to, &je_blocks)
+    }
+
+    #[test]
+    fn substitutes_match_whole_ids() {
+        assert_eq!(
+            substitute_id("tuff_brick_wall"),
+            Some(("stone_brick_wall".to_string(), true))
+        );
+        assert_eq!(
+            substitute_id("deepslate"),
+            Some(("stone".to_string(), false))
+        );
+        assert_eq!(substitute_id("deepslate_coal_ore"), None);
+        assert_eq!(substitute_id("bamboo"), None);
+        assert_eq!(
+            substitute_id("waxed_exposed_copper_bulb"),
+            Some(("waxed_exposed_copper".to_string(), false))
+        );
+    }
+
+    #[test]
+    fn full_block_substitute_drops_properties() {
+        let substitutions = migrate(
+            block("copper_bulb", &[("lit", "true"), ("powered", "false")]),
+            (20, 1),
+        );
+        assert_eq!(substitutions.len(), 1);
+        assert_eq!(substitutions[0].to, "minecraft:copper_block");
+    }
+
+    #[test]
+    fn shaped_substitute_keeps_properties() {
+        let substitutions = migrate(
+            block("cherry_stairs", &[("facing", "east"), ("half", "top")]),
+            (19, 2),
+        );
+        assert_eq!(
+            substitutions[0].to,
+            "minecraft:birch_stairs[facing=east,half=top]"
+        );
+    }
+}
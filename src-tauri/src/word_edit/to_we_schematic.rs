@@ -1,94 +1,47 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos, BlockStatePos};
+use crate::utils::block_state_pos_list::{BlockData, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::schematic_data::{SchematicData, SchematicError};
+use crate::utils::entities::{pos_value, EntitiesList};
+use crate::utils::tile_entities::{strip_keys, with_keys, TileEntitiesList};
+use crate::word_edit::we_schematic_data::WeSize;
 use fastnbt::Value;
 use fastnbt::Value::Compound;
-use rayon::iter::IntoParallelRefIterator;
-use rayon::iter::ParallelIterator;
-use std::collections::{BTreeMap, HashMap, VecDeque};
-use std::sync::atomic::{AtomicI32, Ordering};
+use rayon::prelude::*;
+use std::collections::HashMap;
 use std::sync::Arc;
 use anyhow::Result;
 #[derive(Debug)]
-pub struct ToWeSchematic {
-    blocks: VecDeque<BlockStatePos>,
+pub struct ToWeSchematic<'a> {
+    blocks: &'a BlockVolume,
     start_pos: BlockPos,
     end_pos: BlockPos,
     width: i32,
     height: i32,
     length: i32,
-    air_index: usize,
+    tile_entities: TileEntitiesList,
+    entities: EntitiesList,
     pub unique_block_states: Vec<Arc<BlockData>>,
     pub block_state_to_index: HashMap<Arc<BlockData>, usize>,
 }
 
-impl ToWeSchematic {
-    pub fn new(schematic: &SchematicData) -> Result<Self, SchematicError> {
-        let block_list = schematic.blocks.clone();
-        let blocks = schematic.blocks.clone().elements;
-        if blocks.is_empty() {
+impl<'a> ToWeSchematic<'a> {
+    pub fn new(schematic: &'a SchematicData) -> Result<Self, SchematicError> {
+        let blocks = &schematic.blocks;
+        let Some((min, max)) = blocks.bounds() else {
             return Err(SchematicError::InvalidFormat("Block list cannot be empty"));
-        }
-        let (min, max) = blocks.iter().fold(
-            (
-                BlockPos {
-                    x: i32::MAX,
-                    y: i32::MAX,
-                    z: i32::MAX,
-                },
-                BlockPos {
-                    x: i32::MIN,
-                    y: i32::MIN,
-                    z: i32::MIN,
-                },
-            ),
-            |(mut min, mut max), bp| {
-                min.x = min.x.min(bp.pos.x);
-                min.y = min.y.min(bp.pos.y);
-                min.z = min.z.min(bp.pos.z);
-                max.x = max.x.max(bp.pos.x);
-                max.y = max.y.max(bp.pos.y);
-                max.z = max.z.max(bp.pos.z);
-                (min, max)
-            },
-        );
+        };
 
         let width = max.x - min.x + 1;
         let height = max.y - min.y + 1;
         let length = max.z - min.z + 1;
 
-        let (unique_block_states, block_state_to_index, air_index) = {
-            let mut seen = HashMap::new();
-            let mut unique = Vec::new();
-            let mut index_map = HashMap::new();
-            let air = Arc::new(BlockData {
-                id: BlockId {
-                    name: Arc::from("minecraft:air"),
-                },
-                properties: BTreeMap::new(),
-            });
-
-            for block_pos in &block_list.elements {
-                let block_data = block_pos.block.clone();
-
-                if !seen.contains_key(&block_data) {
-                    let index = unique.len();
-                    seen.insert(block_data.clone(), index);
-                    unique.push(block_data.clone());
-                    index_map.insert(block_data, index);
-                }
-            }
-
-            if !seen.contains_key(&air) {
-                let index = unique.len();
-                seen.insert(air.clone(), index);
-                unique.push(air.clone());
-                index_map.insert(air.clone(), index);
-            }
-
-            let air_index = *seen.get(&air).unwrap();
-
-            (unique, index_map, air_index)
-        };
+        // 体积调色板 0 号即为空气, 直接沿用其下标
+        let unique_block_states = blocks.palette().to_vec();
+        let block_state_to_index = unique_block_states
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (block.clone(), index))
+            .collect::<HashMap<_, _>>();
 
         Ok(Self {
             blocks,
@@ -97,41 +50,32 @@ impl ToWeSchematic {
             width,
             height,
             length,
-            air_index,
+            tile_entities: schematic.tile_entities_list.clone(),
+            entities: schematic.entities.clone(),
             unique_block_states,
             block_state_to_index,
         })
     }
 
     pub fn get_block_id_list(&self) -> Vec<i32> {
+        let size = WeSize {
+            width: self.width,
+            height: self.height,
+            length: self.length,
+        };
         let total_blocks = (self.length * self.width * self.height) as usize;
-        let air_index = self.air_index as i32;
-        let atomic_block_list: Vec<AtomicI32> =
-            (0..total_blocks).map(|_| AtomicI32::new(air_index)).collect();
-        let atomic_block_list = Arc::new(atomic_block_list);
-
-        self.blocks.par_iter().for_each(|block| {
-            let dx = block.pos.x - self.start_pos.x;
-            let dy = block.pos.y - self.start_pos.y;
-            let dz = block.pos.z - self.start_pos.z;
-
-            let id = (dy * self.width * self.length) + (dz * self.width) + dx;
-
-            if id >= 0 && (id as usize) < atomic_block_list.len() {
-                let state_id = self
-                    .block_state_to_index
-                    .get(&block.block)
-                    .map(|v| *v as i32)
-                    .unwrap_or(air_index);
-
-                atomic_block_list[id as usize].store(state_id, Ordering::Relaxed);
-            }
-        });
 
-        Arc::try_unwrap(atomic_block_list)
-            .unwrap()
-            .into_iter()
-            .map(|atomic| atomic.into_inner())
+        (0..total_blocks)
+            .into_par_iter()
+            .map(|id| {
+                let (x, y, z) = size.pos_of(id);
+                let pos = BlockPos {
+                    x: self.start_pos.x + x,
+                    y: self.start_pos.y + y,
+                    z: self.start_pos.z + z,
+                };
+                self.blocks.get_index(pos) as i32
+            })
             .collect()
     }
 
@@ -183,16 +127,65 @@ impl ToWeSchematic {
         output
     }
 
+    pub fn we_block_entities(&self, type_version: i32) -> Value {
+        let list = self
+            .tile_entities
+            .elements
+            .iter()
+            .map(|te| {
+                let pos = te.relative_to(&self.start_pos);
+                let pos = Value::IntArray(fastnbt::IntArray::new(vec![pos.x, pos.y, pos.z]));
+                let id = Value::String(te.get_id().unwrap_or_default().to_string());
+                let data = strip_keys(&te.nbt, &["id"]);
+                if type_version == 1 {
+                    with_keys(
+                        &Compound(HashMap::new()),
+                        vec![("Pos", pos), ("Id", id), ("Data", data)],
+                    )
+                } else {
+                    with_keys(&data, vec![("Pos", pos), ("Id", id)])
+                }
+            })
+            .collect();
+        Value::List(list)
+    }
+
+    pub fn we_entities(&self, type_version: i32) -> Value {
+        let list = self
+            .entities
+            .elements
+            .iter()
+            .map(|e| {
+                let pos = pos_value(e.relative_to(&self.start_pos));
+                let id = Value::String(e.get_id().unwrap_or_default().to_string());
+                let data = strip_keys(&e.nbt, &["id"]);
+                if type_version == 1 {
+                    with_keys(
+                        &Compound(HashMap::new()),
+                        vec![("Pos", pos), ("Id", id), ("Data", data)],
+                    )
+                } else {
+                    with_keys(&data, vec![("Pos", pos), ("Id", id)])
+                }
+            })
+            .collect();
+        Value::List(list)
+    }
+
     pub fn we_palette(&self) -> Value {
         let mut palette = HashMap::new();
         for (block, index) in &self.block_state_to_index {
-            let str = ToWeSchematic::block_to_string(block);
+            let str = Self::block_to_string(block);
             palette.insert(str, Value::Int(*index as i32));
         }
         Compound(palette)
     }
 
-    pub fn we_schematic(&self, type_version: i32) -> Result<Value, SchematicError> {
+    pub fn we_schematic(
+        &self,
+        type_version: i32,
+        data_version: i32,
+    ) -> Result<Value, SchematicError> {
         match type_version {
             0 => {
                 let mut nbt = HashMap::new();
@@ -204,14 +197,15 @@ impl ToWeSchematic {
                 nbt.insert("Length".to_string(), Value::Short(self.length as i16));
                 nbt.insert("Height".to_string(), Value::Short(self.height as i16));
                 nbt.insert("Width".to_string(), Value::Short(self.width as i16));
-                nbt.insert("DataVersion".to_string(), Value::Int(3465));
+                nbt.insert("DataVersion".to_string(), Value::Int(data_version));
                 nbt.insert("Palette".to_string(), self.we_palette());
                 let bytes_array = self.decode_to_bytes();
                 nbt.insert(
                     "BlockData".to_string(),
                     Value::ByteArray(fastnbt::ByteArray::new(bytes_array)),
                 );
-                nbt.insert("BlockEntities".to_string(), Value::List(Vec::new()));
+                nbt.insert("BlockEntities".to_string(), self.we_block_entities(0));
+                nbt.insert("Entities".to_string(), self.we_entities(0));
                 Ok(Compound(nbt))
             }
             1 => {
@@ -224,13 +218,14 @@ impl ToWeSchematic {
                     "BlockData".to_string(),
                     Value::ByteArray(fastnbt::ByteArray::new(bytes_array)),
                 );
-                blocks.insert("BlockEntities".to_string(), Value::List(Vec::new()));
+                blocks.insert("BlockEntities".to_string(), self.we_block_entities(1));
                 schematic.insert("Blocks".to_string(), Compound(blocks));
+                schematic.insert("Entities".to_string(), self.we_entities(1));
                 schematic.insert("Version".to_string(), Value::Int(3));
                 schematic.insert("Length".to_string(), Value::Short(self.length as i16));
                 schematic.insert("Height".to_string(), Value::Short(self.height as i16));
                 schematic.insert("Width".to_string(), Value::Short(self.width as i16));
-                schematic.insert("DataVersion".to_string(), Value::Int(3465));
+                schematic.insert("DataVersion".to_string(), Value::Int(data_version));
                 nbt.insert("Schematic".to_string(), Compound(schematic));
                 Ok(Compound(nbt))
             }
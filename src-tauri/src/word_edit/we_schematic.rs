@@ -1,7 +1,9 @@
-use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockStatePosList};
+use crate::utils::block_state_pos_list::{BlockData, BlockId, BlockPos};
+use crate::utils::block_volume::BlockVolume;
 use crate::utils::extend_value::NbtExt;
 use crate::utils::schematic_data::{SchematicData, SchematicError, Size};
-use crate::utils::tile_entities::TileEntitiesList;
+use crate::utils::entities::{read_pos, EntitiesList};
+use crate::utils::tile_entities::{strip_keys, with_keys, TileEntitiesList};
 use crate::word_edit::var_int_iterator::VarIntIterator;
 use crate::word_edit::we_schematic_data::{WeSchematicData, WeSize};
 use fastnbt::Value::Compound;
@@ -224,28 +226,115 @@ impl WeSchematic {
         })
     }
 
+    // v2 的方块实体数据直接平铺在条目中, v3 放在 Data 里, 统一转为带 id 的原版格式
+    pub fn get_tile_entities(&self, type_version: i32) -> Result<TileEntitiesList, SchematicError> {
+        let mut tile_entities = TileEntitiesList::default();
+        let Ok(block_entities) = self.get_block_entities(type_version) else {
+            return Ok(tile_entities);
+        };
+        for entity in block_entities {
+            let Compound(entry) = entity else {
+                continue;
+            };
+            let pos = entry.get_i32_array("Pos")?;
+            if pos.len() != 3 {
+                return Err(SchematicError::InvalidFormat(
+                    "Position requires 3 coordinates",
+                ));
+            }
+            let id = entry.get_str("Id").map(|s| s.clone()).unwrap_or_default();
+            let nbt = match (type_version, entry.get("Data")) {
+                (1, Some(data)) => data.clone(),
+                _ => strip_keys(entity, &["Pos", "Id"]),
+            };
+            tile_entities.add(
+                BlockPos {
+                    x: pos[0],
+                    y: pos[1],
+                    z: pos[2],
+                },
+                with_keys(&nbt, vec![("id", Value::String(id))]),
+            );
+        }
+        Ok(tile_entities)
+    }
+
+    pub fn get_entities(&self, type_version: i32) -> Result<EntitiesList, SchematicError> {
+        let Compound(root) = &self.nbt else {
+            return Err(SchematicError::InvalidFormat("Root is not a Compound"));
+        };
+        let mut entities = EntitiesList::default();
+        let list = match type_version {
+            0 => root.get_list("Entities"),
+            1 => root.get_compound("Schematic")?.get_list("Entities"),
+            _ => Err(SchematicError::InvalidFormat("Root is not a Compound"))?,
+        };
+        let Ok(list) = list else {
+            return Ok(entities);
+        };
+        for entity in list {
+            let Compound(entry) = entity else {
+                continue;
+            };
+            let Some(pos) = read_pos(entry.get("Pos")) else {
+                continue;
+            };
+            let id = entry.get_str("Id").map(|s| s.clone()).unwrap_or_default();
+            let nbt = match (type_version, entry.get("Data")) {
+                (1, Some(data)) => data.clone(),
+                _ => strip_keys(entity, &["Pos", "Id"]),
+            };
+            entities.add_read(
+                pos,
+                BlockPos::default(),
+                with_keys(&nbt, vec![("id", Value::String(id))]),
+            );
+        }
+        Ok(entities)
+    }
+
     pub fn get_blocks_pos(&self) -> Result<SchematicData, SchematicError> {
-        let tile_entities = TileEntitiesList::default();
-        let mut block_list = BlockStatePosList::default();
+        let mut block_list = BlockVolume::default();
         let type_version = self.get_type()?;
+        let tile_entities = self.get_tile_entities(type_version)?;
+        let entities = self.get_entities(type_version)?;
         let data = self.get_we_data(type_version)?;
         let palette = self.parse_palette(type_version)?;
         let block_data_i8 = data.block_data;
         let size = self.get_size(type_version)?;
-        let width = size.width;
-        let height = size.height;
-        let length = size.length;
-        let iter = VarIntIterator::new(&block_data_i8);
-        for (i, value) in iter.enumerate() {
-            let unsigned_state_id = (value & 0xFF) as i32;
-            let y = (i + 1) as i32 / (width * length);
-            let z = ((i + 1) as i32 % (width * length)) / width;
-            let x = (i + 1) as i32 % width;
-            let block_data = palette
-                .get(&unsigned_state_id)
-                .ok_or(SchematicError::InvalidFormat("miss unsigned_state_id"))?;
-            block_list.add_by_pos(x, y, z, block_data.clone());
+        let (width, height, length) = (size.width, size.height, size.length);
+        let max_id = palette.keys().copied().max().unwrap_or(0).max(0) as usize;
+        let mut palette_list = vec![BlockVolume::air(); max_id + 1];
+        for (id, block_data) in &palette {
+            if *id >= 0 {
+                palette_list[*id as usize] = block_data.clone();
+            }
         }
+        let state_ids = VarIntIterator::new(&block_data_i8)
+            .map(|value| {
+                let unsigned_state_id = (value & 0xFF) as i32;
+                if palette.contains_key(&unsigned_state_id) {
+                    Ok(unsigned_state_id as usize)
+                } else {
+                    Err(SchematicError::InvalidFormat("miss unsigned_state_id"))
+                }
+            })
+            .collect::<Result<Vec<usize>, SchematicError>>()?;
+        block_list.fill_region(
+            BlockPos { x: 0, y: 0, z: 0 },
+            BlockPos {
+                x: width,
+                y: height,
+                z: length,
+            },
+            &palette_list,
+            |x, y, z| {
+                state_ids
+                    .get(size.index_of(x, y, z))
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            },
+        );
         Ok(SchematicData::new(
             block_list,
             tile_entities,
@@ -254,6 +343,8 @@ impl WeSchematic {
                 height,
                 length,
             },
-        ))
+        )
+        .with_entities(entities)
+        .with_data_version(self.get_data_version(type_version).ok()))
     }
 }
@@ -26,6 +26,21 @@ impl WeSize {
     pub fn to_string(&self) -> String {
         format!("{},{},{}", self.width, self.height, self.length)
     }
+
+    // BlockData 的下标顺序为 y -> z -> x, 从 0 开始
+    pub fn index_of(&self, x: i32, y: i32, z: i32) -> usize {
+        ((y * self.length + z) * self.width + x) as usize
+    }
+
+    pub fn pos_of(&self, index: usize) -> (i32, i32, i32) {
+        let width = self.width as usize;
+        let layer = width * self.length as usize;
+        (
+            (index % width) as i32,
+            (index / layer) as i32,
+            ((index % layer) / width) as i32,
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -48,3 +63,33 @@ pub struct WeSchematicData {
     pub block_data: Vec<i8>,
     pub block_entities: Vec<Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size() -> WeSize {
+        WeSize {
+            width: 3,
+            height: 2,
+            length: 4,
+        }
+    }
+
+    #[test]
+    fn index_round_trips() {
+        let size = size();
+        for index in 0..(size.width * size.height * size.length) as usize {
+            let (x, y, z) = size.pos_of(index);
+            assert_eq!(size.index_of(x, y, z), index);
+        }
+    }
+
+    #[test]
+    fn layer_ends_before_next_y() {
+        let size = size();
+        let layer = (size.width * size.length) as usize;
+        assert_eq!(size.pos_of(layer - 1), (2, 0, 3));
+        assert_eq!(size.pos_of(layer), (0, 1, 0));
+    }
+}
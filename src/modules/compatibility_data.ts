@@ -0,0 +1,46 @@
+import {invoke} from "@tauri-apps/api/core";
+import {toast} from "./others.ts";
+
+export interface CompatibilityIssue {
+    block: string;
+    property: string | null;
+    states: number;
+    count: number;
+    positions: { x: number; y: number; z: number }[];
+}
+
+export interface CompatibilityReport {
+    schematic_id: number;
+    name: string;
+    game_version: string;
+    data_version: number;
+    issues: CompatibilityIssue[];
+    error?: string;
+}
+
+export const checkSchematicCompatibility = async (id: number, gameVersion: string):Promise<CompatibilityReport> => {
+    try {
+        return await invoke('check_schematic_compatibility', {
+            id: id,
+            gameVersion: gameVersion,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
+
+export const checkLibraryCompatibility = async (gameVersion: string):Promise<CompatibilityReport[]> => {
+    try {
+        return await invoke('check_library_compatibility', {
+            gameVersion: gameVersion,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
@@ -28,4 +28,22 @@ export const fetchConvertData = async (id: number):Promise<ConvertData> => {
         });
         throw new Error(` ${err}`);
     }
-}
\ No newline at end of file
+}
+export interface SchematicRegion {
+    name: string;
+    position: { x: number; y: number; z: number };
+    size: { x: number; y: number; z: number };
+}
+
+export const fetchSchematicRegions = async (id: number):Promise<SchematicRegion[]> => {
+    try {
+        return await invoke('get_schematic_regions', {
+            id: id,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
@@ -0,0 +1,46 @@
+import {invoke} from "@tauri-apps/api/core";
+import {toast} from "./others.ts";
+
+export interface InventoryEntry {
+    id: string;
+    // block: 方块, support: 地图画辅助材料, contents: 容器物品
+    category: 'block' | 'support' | 'contents';
+    have: number;
+    need: number;
+    missing: number;
+}
+
+export interface InventoryComparison {
+    schematic_id: number;
+    source: string;
+    updated_at: string;
+    entries: InventoryEntry[];
+}
+
+// path: 玩家 .dat、容器/潜影盒 NBT 或物品数量 CSV
+export const compareSchematicInventory = async (id: number, path: string):Promise<InventoryComparison> => {
+    try {
+        return await invoke('compare_schematic_inventory', {
+            id: id,
+            path: path,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
+
+export const getSchematicInventory = async (id: number):Promise<InventoryComparison | null> => {
+    try {
+        return await invoke('get_schematic_inventory', {
+            id: id,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
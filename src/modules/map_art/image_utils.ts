@@ -1,7 +1,7 @@
 import {SubData} from "./map_art_data.ts";
 import {getBlockImg, toast} from "../others.ts";
 import {invoke} from "@tauri-apps/api/core";
-import {BlockStatePos} from "./schematic_data.ts";
+import {BlockPos, BlockStatePos} from "./schematic_data.ts";
 
 export function hexToRgb(hex: string): { r: number; g: number; b: number } | null {
     const result = /^#?([a-f\d]{2})([a-f\d]{2})([a-f\d]{2})$/i.exec(hex)
@@ -53,7 +53,8 @@ export const createMapArt = async (
     file_name: string,
     size: Size,
     schematic_type: number,
-    sub_version: number
+    sub_version: number,
+    support?: MapArtSupport
 ): Promise<boolean> => {
     try {
         return await invoke<boolean>(
@@ -64,6 +65,7 @@ export const createMapArt = async (
                 size: size,
                 schematicType: schematic_type,
                 subVersion: sub_version,
+                support: support,
             }
         )
     } catch (error) {
@@ -73,3 +75,118 @@ export const createMapArt = async (
         throw new Error(`创建地图画失败: ${error}`);
     }
 }
+
+export type MapArtDithering = 'none' | 'floyd_steinberg' | 'atkinson' | 'ordered'
+
+// flat: 平面; staircase: 阶梯; valley: 最低高度的阶梯
+export type MapArtMode = 'flat' | 'staircase' | 'valley'
+
+export interface MapArtSupportOptions {
+    // 支撑方块 id, 默认 minecraft:cobblestone
+    block?: string,
+    // 北侧参考行, 默认开启
+    noobline?: boolean,
+}
+
+export interface MapArtOptions {
+    maps_x: number,
+    maps_y: number,
+    mode?: MapArtMode,
+    dithering?: MapArtDithering,
+    // 可用方块 id (不含命名空间), 为空时使用全部方块
+    blocks?: string[],
+    support?: MapArtSupportOptions,
+}
+
+// 创建蓝图时由后端添加支撑方块, references 取自 generateMapArt 的结果
+export interface MapArtSupport {
+    mode?: MapArtMode,
+    options?: MapArtSupportOptions,
+    references?: BlockPos[],
+}
+
+export interface MapArtResult {
+    // 不含支撑方块与参考行
+    blocks: BlockStatePos[],
+    references: BlockPos[],
+    size: Size,
+    counts: Record<string, number>,
+    // 预计添加的支撑方块与参考行数量
+    support: Record<string, number>,
+    // data:image/png;base64 预览图
+    preview: string,
+}
+
+// 由后端完成缩放、CIEDE2000 匹配与抖动; profile 为调色板配置名
+export const generateMapArt = async (
+    path: string,
+    options: MapArtOptions,
+    profile?: string
+): Promise<MapArtResult> => {
+    try {
+        return await invoke<MapArtResult>(
+            'generate_map_art',
+            {
+                path: path,
+                options: options,
+                profile: profile,
+            }
+        )
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`生成地图画失败: ${error}`);
+    }
+}
+
+export interface PaletteProfile {
+    name: string,
+    description: string,
+    // 只使用地毯, 颜色与对应的羊毛/苔藓块相同
+    carpet_only: boolean,
+    survival_only: boolean,
+    no_gravity: boolean,
+    no_block_entity: boolean,
+    // 版本名或 DataVersion
+    game_version?: string,
+    exclude: string[],
+}
+
+export const listMapArtProfiles = async (): Promise<PaletteProfile[]> => {
+    try {
+        return await invoke<PaletteProfile[]>('list_map_art_profiles')
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${error}`);
+    }
+}
+
+// 同名配置会被覆盖
+export const saveMapArtProfile = async (profile: PaletteProfile): Promise<PaletteProfile[]> => {
+    try {
+        return await invoke<PaletteProfile[]>('save_map_art_profile', {
+            profile: profile,
+        })
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${error}`);
+    }
+}
+
+export const deleteMapArtProfile = async (name: string): Promise<PaletteProfile[]> => {
+    try {
+        return await invoke<PaletteProfile[]>('delete_map_art_profile', {
+            name: name,
+        })
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${error}`);
+    }
+}
@@ -0,0 +1,58 @@
+import {invoke} from "@tauri-apps/api/core";
+import {toast} from "./others.ts";
+
+export type RecipeMode = 'cheapest' | 'stonecutter';
+
+export interface RecipeNode {
+    item: string;
+    count: number;
+    recipe: string | null;
+    kind: 'crafting' | 'stonecutting' | null;
+    crafts: number;
+    surplus: number;
+    children: RecipeNode[];
+}
+
+export interface RecipeBreakdown {
+    mode: RecipeMode;
+    tree: RecipeNode[];
+    totals: Record<string, number>;
+}
+
+export const getSchematicMaterials = async (id: number, mode: RecipeMode = 'cheapest'):Promise<RecipeBreakdown> => {
+    try {
+        return await invoke('get_schematic_materials', {
+            id: id,
+            mode: mode,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
+
+export type ExportFormat = 'csv' | 'litematica' | 'stacks';
+
+// 返回实际写入的文件路径, target 为目录时使用默认文件名
+export const exportRequirements = async (
+    id: number,
+    format: ExportFormat,
+    target: string,
+    locale?: string
+):Promise<string> => {
+    try {
+        return await invoke('export_requirements', {
+            id: id,
+            format: format,
+            target: target,
+            locale: locale,
+        })
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}
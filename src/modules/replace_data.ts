@@ -1,5 +1,6 @@
 import {invoke} from "@tauri-apps/api/core";
 import {toast} from "./others.ts";
+import i18n from "../i18n";
 
 export interface BlockId  {
     name: string;
@@ -12,6 +13,7 @@ export interface BlockDataNew  {
 export interface BlockData  {
     id: string;
     properties: Record<string, string>;
+    name?: string;
 }
 
 
@@ -21,10 +23,11 @@ export interface BlockPos {
     z: number,
 }
 export async function fetchUniqueBlocks(
-    schematicId: number
+    schematicId: number,
+    locale: string = i18n.global.locale.value
 ): Promise<BlockData[]> {
     try {
-        const response = await invoke<string>('get_unique_block', { id: schematicId });
+        const response = await invoke<string>('get_unique_block', { id: schematicId, locale: locale });
         return JSON.parse(response) as BlockData[];
     } catch (error) {
         toast.error(`发生了一个错误:${error}`, {
@@ -1,9 +1,13 @@
 import {invoke} from "@tauri-apps/api/core";
 import {toast} from "./others.ts";
+import i18n from "../i18n";
 export interface Requirement {
     id: string,
     zh_cn: string,
-    num: number
+    num: number,
+    category?: string,
+    name?: string,
+    nbt?: string
 }
 
 export interface RequirementStatistic extends Requirement {
@@ -17,17 +21,56 @@ export interface RequirementStatistics {
 }
 export function parseRequirements(jsonStr: string): Requirement[] {
     const rawData = JSON.parse(jsonStr) as Record<string, Requirement>;
-    return Object.values(rawData).map(block => ({
+    return Object.values(rawData).filter(block => block.category !== 'raw' && block.category !== 'contents' && block.category !== 'support').map(block => ({
         id: block.id,
         zh_cn: block.zh_cn,
-        num: block.num
+        num: block.num,
+        category: block.category ?? 'block',
+        name: block.name ?? block.zh_cn
+    }));
+}
+
+// 未经物品换算的原始方块数量
+export function parseRawBlocks(jsonStr: string): Requirement[] {
+    const rawData = JSON.parse(jsonStr) as Record<string, Requirement>;
+    return Object.values(rawData).filter(block => block.category === 'raw').map(block => ({
+        id: block.id,
+        zh_cn: block.zh_cn,
+        num: block.num,
+        category: block.category,
+        name: block.name ?? block.zh_cn
+    }));
+}
+
+// 容器中预先放入的物品, 不同 NBT 的物品分开列出
+export function parseContents(jsonStr: string): Requirement[] {
+    const rawData = JSON.parse(jsonStr) as Record<string, Requirement>;
+    return Object.values(rawData).filter(block => block.category === 'contents').map(block => ({
+        id: block.id,
+        zh_cn: block.zh_cn,
+        num: block.num,
+        category: block.category,
+        name: block.name ?? block.zh_cn,
+        nbt: block.nbt
+    }));
+}
+
+// 地图画的支撑方块与参考行
+export function parseSupport(jsonStr: string): Requirement[] {
+    const rawData = JSON.parse(jsonStr) as Record<string, Requirement>;
+    return Object.values(rawData).filter(block => block.category === 'support').map(block => ({
+        id: block.id,
+        zh_cn: block.zh_cn,
+        num: block.num,
+        category: block.category,
+        name: block.name ?? block.zh_cn
     }));
 }
 
 export function parseRequirementsList(jsonStr: string): Requirement[][] {
     const rawData = JSON.parse(jsonStr) as Record<string, Requirement>[];
     return rawData.map(data =>
-        Object.values(data).map(block => ({
+        Object.values(data).filter(block => block.category !== 'raw' && block.category !== 'contents' && block.category !== 'support').map(block => ({
             id: block.id,
             zh_cn: block.zh_cn,
             num: block.num
@@ -59,10 +102,11 @@ export function calculateStatistics(requirements: Requirement[]): RequirementSta
 }
 
 export async function fetchRequirementsWithStats(
-    schematicId: number
+    schematicId: number,
+    locale: string = i18n.global.locale.value
 ): Promise<RequirementStatistics> {
     try {
-        const response = await invoke<string>('get_schematic_requirements', { id: schematicId });
+        const response = await invoke<string>('get_schematic_requirements', { id: schematicId, locale: locale });
         const requirements = parseRequirements(response);
         return calculateStatistics(requirements);
     } catch (error) {
@@ -72,4 +116,51 @@ export async function fetchRequirementsWithStats(
         throw new Error(`err: ${error}`);
     }
 
-}
\ No newline at end of file
+}
+export type LayerMode =
+    | { mode: 'layer' }
+    | { mode: 'slab', layers: number }
+    | { mode: 'split', split_type: number, split_number: number };
+
+export interface LayerGroup {
+    index: number;
+    min: number;
+    max: number;
+    total: number;
+}
+
+export interface LayerBlock {
+    id: string;
+    zh_cn: string;
+    name?: string;
+    total: number;
+}
+
+// counts[层][方块], 与 layers / blocks 的顺序对应
+export interface LayerMatrix {
+    axis: 'x' | 'y';
+    layers: LayerGroup[];
+    blocks: LayerBlock[];
+    counts: number[][];
+}
+
+export async function fetchLayerRequirements(
+    schematicId: number,
+    mode: LayerMode = { mode: 'layer' },
+    region?: string,
+    locale: string = i18n.global.locale.value
+): Promise<LayerMatrix> {
+    try {
+        return await invoke<LayerMatrix>('get_layer_requirements', {
+            id: schematicId,
+            mode: mode,
+            region: region,
+            locale: locale
+        });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
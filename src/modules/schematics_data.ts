@@ -22,6 +22,7 @@ export let schematicTypeList = {
     2: "投影蓝图",
     3: "创世神",
     4: "建筑小帮手",
+    6: "旧版 MCEdit",
 }
 export let schematicTypeListWeb = {
     0: "香草结构",
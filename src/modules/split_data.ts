@@ -25,6 +25,7 @@ const getExtensions = (type: number): string => {
         case 3: return '.schem';
         case 4: return '.json';
         case 5: return '.mcstruct';
+        case 6: return '.schematic';
         default: return '.unknown';
     }
 };
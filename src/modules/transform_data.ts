@@ -0,0 +1,23 @@
+import {invoke} from "@tauri-apps/api/core";
+import {toast} from "./others.ts";
+
+export type TransformAxis = 'x' | 'y' | 'z';
+
+export type SchematicTransform =
+    | { type: 'rotate'; axis: TransformAxis; angle: number }
+    | { type: 'mirror'; axis: TransformAxis };
+
+export async function transformSchematic(
+    schematicId: number,
+    transforms: SchematicTransform[],
+    includeContents: boolean = true
+): Promise<boolean> {
+    try {
+        return await invoke<boolean>('schematic_transform', { schematicId, transforms, includeContents });
+    } catch (error) {
+        toast.error(`发生了一个错误:${error}`, {
+            timeout: 3000
+        });
+        throw new Error(`err: ${error}`);
+    }
+}
@@ -4,13 +4,18 @@ import {userData} from "./user_data.ts";
 import {toast} from "./others.ts";
 import {fetch_data} from "./tools_data.ts";
 
+export interface UploadReport {
+    schematic_id: number;
+    unmapped_blocks: Record<string, number>;
+}
+
 export const files = ref<File[]>([]);
 export const uploadStatus = ref<'idle' | 'uploading' | 'success' | 'error'>('idle');
 export const uploadError = ref<string | null>(null);
 export const progressTimer = ref<number | null>(null)
 export const progressValue = ref(100)
 
-export const handleUpload = async (update_id: number) => {
+export const handleUpload = async (update_id: number, includeContents: boolean = true) => {
     if (files.value.length === 0) return;
 
     uploadStatus.value = 'uploading';
@@ -24,12 +29,19 @@ export const handleUpload = async (update_id: number) => {
             const arrayBuffer = await file.arrayBuffer();
             const uint8Array = new Uint8Array(arrayBuffer);
 
-            await invoke('encode_uploaded_schematic', {
+            const report = await invoke<UploadReport>('encode_uploaded_schematic', {
                 fileName: file.name,
                 data: Array.from(uint8Array),
                 update: update_id != -1,
-                updateId: update_id
+                updateId: update_id,
+                includeContents
             });
+            const unmapped = Object.entries(report.unmapped_blocks)
+            if (unmapped.length > 0) {
+                toast.warning(`${file.name}: ${unmapped.length} 种方块无法映射为 Java 版: ${unmapped.map(([id, n]) => `${id}×${n}`).join(', ')}`, {
+                    timeout: 5000
+                });
+            }
         }
         if (update_id != -1) {
             await fetch_data(update_id)
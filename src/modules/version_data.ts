@@ -0,0 +1,33 @@
+import {invoke} from "@tauri-apps/api/core";
+import {open} from "@tauri-apps/plugin-dialog";
+import {toast} from "./others.ts";
+
+export const importVersionData = async (): Promise<number | undefined> => {
+    try {
+        const path = await open({
+            multiple: false,
+            filters: [
+                {
+                    name: 'Version Table',
+                    extensions: ['json'],
+                },
+            ],
+        });
+
+        if (!path) {
+            toast.error(`未选择版本表文件`, { timeout: 3000 });
+            return;
+        }
+
+        const count = await invoke<number>('import_version_data', {
+            path: path,
+        });
+        toast.success(`版本表已更新, 共 ${count} 个版本`, { timeout: 3000 });
+        return count;
+    } catch (err) {
+        toast.error(`发生了一个错误:${err}`, {
+            timeout: 3000
+        });
+        throw new Error(` ${err}`);
+    }
+}